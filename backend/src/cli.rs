@@ -0,0 +1,67 @@
+use mongodb::bson::Uuid;
+use rocket_db_pools::mongodb::Client;
+
+use crate::models::{audit_log::AuditLogAuthor, tenant::Tenant, tenant_archive::TenantArchive, user::User};
+
+const USAGE: &str = "Usage:
+  shelfwatcher-backend export <tenant-id> <file>
+  shelfwatcher-backend import <file> <owner-user-id> [name]";
+
+// Runs a command against the database configured in Rocket.toml without starting the server, returns the exit code
+pub async fn run(args: &[String]) -> i32 {
+    let client = match connect().await {
+        Ok(client) => client,
+        Err(err) => {
+            eprintln!("{}", err);
+            return 1;
+        }
+    };
+
+    let result = match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["export", tenant_id, file] => export(tenant_id, file, &client).await,
+        ["import", file, owner_id] => import(file, owner_id, None, &client).await,
+        ["import", file, owner_id, name] => import(file, owner_id, Some(name.to_string()), &client).await,
+        _ => Err(USAGE.to_string())
+    };
+
+    match result {
+        Ok(message) => {
+            println!("{}", message);
+            0
+        },
+        Err(err) => {
+            eprintln!("{}", err);
+            1
+        }
+    }
+}
+
+async fn connect() -> Result<Client, String> {
+    let url = rocket::Config::figment()
+        .extract_inner::<String>("databases.shelfwatcher-db.url")
+        .map_err(|err| format!("Invalid database config: {}", err))?;
+
+    Client::with_uri_str(url).await.map_err(|err| format!("Error connecting to the database: {:?}", err))
+}
+
+async fn export(tenant_id: &str, file: &str, client: &Client) -> Result<String, String> {
+    let uuid = Uuid::parse_str(tenant_id).map_err(|err| format!("Invalid tenant UUID: {:?}", err))?;
+    let tenant = Tenant::get_by_id(uuid, client).await.map_err(|err| err.message)?;
+
+    let archive = TenantArchive::export(&tenant, client).await.map_err(|err| err.message)?;
+    std::fs::write(file, &archive).map_err(|err| format!("Error writing {}: {}", file, err))?;
+
+    Ok(format!("Exported tenant {} to {}", tenant.name, file))
+}
+
+// Imports keep the archived plan, whoever runs this has access to the database anyway
+async fn import(file: &str, owner_id: &str, name: Option<String>, client: &Client) -> Result<String, String> {
+    let owner_uuid = Uuid::parse_str(owner_id).map_err(|err| format!("Invalid user UUID: {:?}", err))?;
+    let owner = User::get_full_by_id(owner_uuid, client).await.map_err(|err| err.message)?;
+
+    let archive = std::fs::read(file).map_err(|err| format!("Error reading {}: {}", file, err))?;
+    let imported = TenantArchive::import(&archive, name, &owner, true, AuditLogAuthor::System, client).await.map_err(|err| err.message)?;
+
+    let documents: u64 = imported.documents.values().sum();
+    Ok(format!("Imported {} documents into tenant {} ({})", documents, imported.tenant.name, imported.tenant.id))
+}
@@ -1,13 +1,55 @@
-use rocket_db_pools::{mongodb::{Client, Database}, Connection, Database as RocketDB}; 
+use mongodb::bson::Document;
+use rocket_db_pools::{mongodb::{error::{Error, ErrorKind, WriteFailure}, options::IndexOptions, Client, Database, IndexModel}, Database as RocketDB}; 
+
+use crate::models::http_response::HttpResponse;
 
 #[derive(RocketDB)] 
 #[database("shelfwatcher-db")] 
 pub struct ShelfWatcherDatabase(Client);
 
-pub fn get_main_db(connection: &Connection<ShelfWatcherDatabase>) -> Database {
-    connection.database("shelfwatcher_data")
+// Takes the client so background jobs can use it too, a request `Connection` derefs to it
+pub fn get_main_db(client: &Client) -> Database {
+    client.database("shelfwatcher_data")
+}
+
+pub fn get_logs_db(client: &Client) -> Database {
+    client.database("shelfwatcher_logs")
+}
+
+pub async fn count_documents(collection: &str, filter: Document, client: &Client) -> Result<i64, HttpResponse<()>> {
+    let db = get_main_db(client).collection::<Document>(collection);
+
+    match db.count_documents(filter, None).await {
+        Ok(count) => Ok(count as i64),
+        Err(err) => Err(HttpResponse {
+            status: 500,
+            message: format!("Error counting {}: {:?}", collection, err),
+            data: None
+        })
+    }
 }
 
-pub fn get_logs_db(connection: &Connection<ShelfWatcherDatabase>) -> Database {
-    connection.database("shelfwatcher_logs")
-}
\ No newline at end of file
+// Creates the index if it doesn't exist yet. A partial filter leaves documents without the indexed values out of it.
+pub async fn create_unique_index(collection: &str, keys: Document, partial_filter: Option<Document>, client: &Client) -> Result<(), HttpResponse<()>> {
+    let db = get_main_db(client).collection::<Document>(collection);
+
+    let options = IndexOptions::builder().unique(true).partial_filter_expression(partial_filter).build();
+    let index = IndexModel::builder().keys(keys).options(options).build();
+    match db.create_index(index, None).await {
+        Ok(_) => Ok(()),
+        Err(err) => Err(HttpResponse {
+            status: 500,
+            message: format!("Error creating unique index on {}: {:?}", collection, err),
+            data: None
+        })
+    }
+}
+
+// A write collided with a unique index. find-and-modify commands report it as a command error.
+pub fn is_duplicate_key(err: &Error) -> bool {
+    match &*err.kind {
+        ErrorKind::Write(WriteFailure::WriteError(err)) => err.code == 11000,
+        ErrorKind::Command(err) => err.code == 11000,
+        _ => false
+    }
+}
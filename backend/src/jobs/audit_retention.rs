@@ -0,0 +1,80 @@
+use std::time::Duration;
+
+use mongodb::bson::{doc, Bson, Document, Uuid};
+use rocket::{error, info, tokio};
+use rocket_db_pools::mongodb::Client;
+
+use crate::{db::get_main_db, models::{api_key::ApiKey, audit_log::{AuditLog, AuditLogEntityType}, item::Item, location::Location, product::Product, product_batch::ProductBatch, product_group::ProductGroup, reorder_point::ReorderPoint, stocktake::Stocktake, storage_area::StorageArea, tenant::Tenant, transfer::Transfer}};
+
+// Entities that belong to a tenant, with the collection they are stored in
+const TENANT_ENTITIES: [(AuditLogEntityType, &str); 10] = [
+    (AuditLogEntityType::Location, Location::COLLECTION_NAME),
+    (AuditLogEntityType::StorageArea, StorageArea::COLLECTION_NAME),
+    (AuditLogEntityType::ProductGroup, ProductGroup::COLLECTION_NAME),
+    (AuditLogEntityType::Product, Product::COLLECTION_NAME),
+    (AuditLogEntityType::ProductBatch, ProductBatch::COLLECTION_NAME),
+    (AuditLogEntityType::Item, Item::COLLECTION_NAME),
+    (AuditLogEntityType::ApiKey, ApiKey::COLLECTION_NAME),
+    (AuditLogEntityType::Transfer, Transfer::COLLECTION_NAME),
+    (AuditLogEntityType::Stocktake, Stocktake::COLLECTION_NAME),
+    (AuditLogEntityType::ReorderPoint, ReorderPoint::COLLECTION_NAME)
+];
+
+// Deletes audit logs older than the retention of each tenant's plan, every `interval_minutes`
+pub fn spawn(client: Client, interval_minutes: u64) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(interval_minutes.max(1) * 60));
+
+        loop {
+            interval.tick().await;
+            run(&client).await;
+        }
+    });
+}
+
+async fn run(client: &Client) {
+    let tenants = match Tenant::get_all(client).await {
+        Ok(tenants) => tenants,
+        Err(err) => {
+            error!("{}", err.message);
+            return;
+        }
+    };
+
+    for tenant in tenants {
+        match purge(&tenant, client).await {
+            Ok(0) => (),
+            Ok(deleted) => info!("Deleted {} expired audit logs of tenant {}", deleted, tenant.id),
+            Err(err) => error!("Audit retention of tenant {} failed: {}", tenant.id, err)
+        }
+    }
+}
+
+async fn purge(tenant: &Tenant, client: &Client) -> Result<u64, String> {
+    let before = ProductBatch::date_in_days(-tenant.quotas().audit_retention_days);
+
+    let mut deleted = AuditLog::delete_before(AuditLogEntityType::Tenant, &[tenant.id], &before, client).await.map_err(|err| err.message)?;
+
+    for (entity_type, collection) in TENANT_ENTITIES {
+        let entity_ids = entity_ids(collection, tenant.id, client).await?;
+        if entity_ids.is_empty() {
+            continue;
+        }
+
+        deleted += AuditLog::delete_before(entity_type, &entity_ids, &before, client).await.map_err(|err| err.message)?;
+    }
+
+    Ok(deleted)
+}
+
+async fn entity_ids(collection: &str, tenant_id: Uuid, client: &Client) -> Result<Vec<Uuid>, String> {
+    let db = get_main_db(client).collection::<Document>(collection);
+
+    match db.distinct("_id", doc! { "tenantId": tenant_id }, None).await {
+        Ok(values) => Ok(values.into_iter().filter_map(|value| match value {
+            Bson::Binary(binary) => binary.to_uuid().ok(),
+            _ => None
+        }).collect()),
+        Err(err) => Err(format!("Error fetching {} of tenant: {:?}", collection, err))
+    }
+}
@@ -0,0 +1,35 @@
+use std::time::Duration;
+
+use rocket::{error, info, tokio};
+use rocket_db_pools::mongodb::Client;
+
+use crate::models::{low_stock_alert::LowStockAlert, reorder_point::ReorderPoint};
+
+// Re-evaluates the low stock alerts of every tenant with reorder points, every `interval_minutes`
+pub fn spawn(client: Client, interval_minutes: u64) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(interval_minutes.max(1) * 60));
+
+        loop {
+            interval.tick().await;
+            run(&client).await;
+        }
+    });
+}
+
+async fn run(client: &Client) {
+    let tenant_ids = match ReorderPoint::get_tenant_ids(client).await {
+        Ok(tenant_ids) => tenant_ids,
+        Err(err) => {
+            error!("{}", err.message);
+            return;
+        }
+    };
+
+    for tenant_id in tenant_ids {
+        match LowStockAlert::evaluate(tenant_id, client).await {
+            Ok(alerts) => info!("Low stock alerts of tenant {} evaluated, {} open", tenant_id, alerts.len()),
+            Err(err) => error!("Low stock alerts of tenant {} failed: {}", tenant_id, err.message)
+        }
+    }
+}
@@ -0,0 +1,49 @@
+pub mod audit_retention;
+pub mod low_stock_alerts;
+pub mod stock_snapshots;
+pub mod tenant_purge;
+
+use rocket::serde::Deserialize;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct JobsConfig {
+    // How often the stock ledger of every tenant is folded into a new snapshot
+    #[serde(default = "JobsConfig::default_stock_snapshot_interval_minutes")]
+    pub stock_snapshot_interval_minutes: u64,
+    // How often low stock alerts are raised and resolved
+    #[serde(default = "JobsConfig::default_low_stock_interval_minutes")]
+    pub low_stock_interval_minutes: u64,
+    // How often audit logs past the retention of each tenant's plan are deleted
+    #[serde(default = "JobsConfig::default_audit_retention_interval_minutes")]
+    pub audit_retention_interval_minutes: u64,
+    // How often deleted tenants past their grace period are purged
+    #[serde(default = "JobsConfig::default_tenant_purge_interval_minutes")]
+    pub tenant_purge_interval_minutes: u64,
+    // Days a deleted tenant can still be restored before it is purged
+    #[serde(default = "JobsConfig::default_tenant_deletion_grace_days")]
+    pub tenant_deletion_grace_days: i64,
+}
+
+impl JobsConfig {
+    fn default_stock_snapshot_interval_minutes() -> u64 {
+        60
+    }
+
+    fn default_low_stock_interval_minutes() -> u64 {
+        15
+    }
+
+    fn default_audit_retention_interval_minutes() -> u64 {
+        24 * 60
+    }
+
+    fn default_tenant_purge_interval_minutes() -> u64 {
+        60
+    }
+
+    fn default_tenant_deletion_grace_days() -> i64 {
+        30
+    }
+}
+
@@ -0,0 +1,35 @@
+use std::time::Duration;
+
+use rocket::{error, info, tokio};
+use rocket_db_pools::mongodb::Client;
+
+use crate::models::{stock_movement::StockMovement, stock_snapshot::StockSnapshot};
+
+// Takes a stock snapshot for every tenant with movements, every `interval_minutes`
+pub fn spawn(client: Client, interval_minutes: u64) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(interval_minutes.max(1) * 60));
+
+        loop {
+            interval.tick().await;
+            run(&client).await;
+        }
+    });
+}
+
+async fn run(client: &Client) {
+    let tenant_ids = match StockMovement::get_tenant_ids(client).await {
+        Ok(tenant_ids) => tenant_ids,
+        Err(err) => {
+            error!("{}", err.message);
+            return;
+        }
+    };
+
+    for tenant_id in tenant_ids {
+        match StockSnapshot::take(tenant_id, client).await {
+            Ok(snapshot) => info!("Stock snapshot of tenant {} taken with {} lines", tenant_id, snapshot.lines.len()),
+            Err(err) => error!("Stock snapshot of tenant {} failed: {}", tenant_id, err.message)
+        }
+    }
+}
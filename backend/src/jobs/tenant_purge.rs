@@ -0,0 +1,54 @@
+use std::time::Duration;
+
+use rocket::{error, info, tokio};
+use rocket_db_pools::mongodb::Client;
+
+use crate::models::{audit_log::{AuditLog, AuditLogAction, AuditLogAuthor, AuditLogEntityType}, tenant::Tenant};
+
+// Purges deleted tenants whose grace period is over, every `interval_minutes`
+pub fn spawn(client: Client, interval_minutes: u64) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(interval_minutes.max(1) * 60));
+
+        loop {
+            interval.tick().await;
+            run(&client).await;
+        }
+    });
+}
+
+async fn run(client: &Client) {
+    let tenants = match Tenant::get_due_for_purge(client).await {
+        Ok(tenants) => tenants,
+        Err(err) => {
+            error!("{}", err.message);
+            return;
+        }
+    };
+
+    for mut tenant in tenants {
+        match tenant.claim_for_purge(client).await {
+            Ok(true) => (),
+            Ok(false) => continue,
+            Err(err) => {
+                error!("Claiming tenant {} for purging failed: {}", tenant.id, err.message);
+                continue;
+            }
+        }
+
+        let summary = match tenant.purge(client).await {
+            Ok(summary) => summary,
+            Err(err) => {
+                error!("Purging tenant {} failed: {}", tenant.id, err.message);
+                continue;
+            }
+        };
+
+        info!("Tenant {} purged", tenant.id);
+
+        match AuditLog::new(tenant.id, AuditLogEntityType::Tenant, AuditLogAction::Delete, "Tenant purged.".to_string(), AuditLogAuthor::System, None, Some(summary)).insert(client).await {
+            Ok(_) => (),
+            Err(err) => error!("{}", err)
+        }
+    }
+}
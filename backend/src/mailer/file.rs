@@ -0,0 +1,40 @@
+use mongodb::bson::Uuid;
+use rocket::{info, tokio::fs};
+
+use super::{Mail, Mailer};
+
+// Local testing sink: writes every mail into `directory`, or only logs it when no directory is configured
+pub struct FileMailer {
+    directory: Option<String>,
+}
+
+impl FileMailer {
+    pub fn new(directory: Option<String>) -> Self {
+        Self { directory }
+    }
+}
+
+#[rocket::async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, mail: Mail) -> Result<(), String> {
+        let content = format!("To: {}\nSubject: {}\n\n{}\n", mail.to, mail.subject, mail.body);
+
+        let directory = match &self.directory {
+            Some(directory) => directory,
+            None => {
+                info!("Outgoing mail:\n{}", content);
+                return Ok(());
+            }
+        };
+
+        if let Err(err) = fs::create_dir_all(directory).await {
+            return Err(format!("Error creating mail directory: {:?}", err));
+        }
+
+        let path = format!("{}/{}.eml", directory, Uuid::new());
+        match fs::write(&path, content).await {
+            Ok(_) => Ok(()),
+            Err(err) => Err(format!("Error writing mail to {}: {:?}", path, err))
+        }
+    }
+}
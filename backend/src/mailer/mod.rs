@@ -0,0 +1,43 @@
+pub mod file;
+pub mod smtp;
+
+use rocket::serde::Deserialize;
+
+use self::{file::FileMailer, smtp::SmtpMailer};
+
+#[derive(Debug, Clone)]
+pub struct Mail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[rocket::async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, mail: Mail) -> Result<(), String>;
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct MailConfig {
+    // "smtp" or "file"
+    pub transport: String,
+    pub from: String,
+    // Base URL of the frontend, used for links in outgoing mail
+    pub app_url: String,
+    pub directory: Option<String>,
+    pub smtp_host: Option<String>,
+    pub smtp_port: Option<u16>,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+}
+
+impl MailConfig {
+    pub fn build_mailer(&self) -> Result<Box<dyn Mailer>, String> {
+        match self.transport.as_str() {
+            "smtp" => Ok(Box::new(SmtpMailer::new(self)?)),
+            "file" => Ok(Box::new(FileMailer::new(self.directory.clone()))),
+            transport => Err(format!("Unknown mail transport: {}", transport))
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use lettre::{message::Mailbox, transport::smtp::authentication::Credentials, AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+use super::{Mail, MailConfig, Mailer};
+
+pub struct SmtpMailer {
+    from: Mailbox,
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpMailer {
+    pub fn new(config: &MailConfig) -> Result<Self, String> {
+        let host = match &config.smtp_host {
+            Some(host) => host,
+            None => return Err("smtp_host is required for the smtp transport".to_string())
+        };
+
+        let mut builder = match AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host) {
+            Ok(builder) => builder,
+            Err(err) => return Err(format!("Invalid SMTP relay: {:?}", err))
+        };
+        if let Some(port) = config.smtp_port {
+            builder = builder.port(port);
+        }
+        if let (Some(username), Some(password)) = (&config.smtp_username, &config.smtp_password) {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+
+        let from = match config.from.parse() {
+            Ok(from) => from,
+            Err(err) => return Err(format!("Invalid sender address: {:?}", err))
+        };
+
+        Ok(Self { from, transport: builder.build() })
+    }
+}
+
+#[rocket::async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, mail: Mail) -> Result<(), String> {
+        let to = match mail.to.parse() {
+            Ok(to) => to,
+            Err(err) => return Err(format!("Invalid recipient address: {:?}", err))
+        };
+
+        let message = match Message::builder().from(self.from.clone()).to(to).subject(mail.subject).body(mail.body) {
+            Ok(message) => message,
+            Err(err) => return Err(format!("Error building mail: {:?}", err))
+        };
+
+        match self.transport.send(message).await {
+            Ok(_) => Ok(()),
+            Err(err) => Err(format!("Error sending mail: {:?}", err))
+        }
+    }
+}
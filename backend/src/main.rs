@@ -1,17 +1,41 @@
+// Handlers and models report failures as `HttpResponse` values, which are large by design
+#![allow(clippy::result_large_err)]
+
+mod cli;
 mod db;
+mod jobs;
+mod mailer;
 mod models;
 mod routes;
 mod middleware;
 
+use jobs::JobsConfig;
+use mailer::MailConfig;
+use middleware::auth::AuthConfig;
 use rocket::{
+    catchers,
+    fairing::AdHoc,
     http::Method::{Connect, Delete, Get, Patch, Post, Put},
-    launch, routes,
+    routes, Build, Rocket,
 };
 use rocket_cors::{AllowedHeaders, AllowedOrigins, CorsOptions};
 use rocket_db_pools::Database;
 
-#[launch]
-fn rocket() -> _ {
+// Without arguments the server starts, otherwise the arguments are a CLI command like `export`
+#[rocket::main]
+async fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        std::process::exit(cli::run(&args).await);
+    }
+
+    if let Err(err) = rocket().launch().await {
+        rocket::error!("{}", err);
+        std::process::exit(1);
+    }
+}
+
+fn rocket() -> Rocket<Build> {
     let cors = CorsOptions::default()
         .allowed_origins(AllowedOrigins::all())
         .allowed_methods(
@@ -26,9 +50,114 @@ fn rocket() -> _ {
     rocket::build()
         .attach(db::ShelfWatcherDatabase::init())
         .attach(cors.to_cors().unwrap())
+        .attach(AdHoc::try_on_ignite("Migrations", |rocket| async {
+            let client = match db::ShelfWatcherDatabase::fetch(&rocket) {
+                Some(database) => (**database).clone(),
+                None => return Err(rocket)
+            };
+
+            match models::tenant::Tenant::migrate_legacy_settings(&client).await {
+                Ok(0) => (),
+                Ok(migrated) => rocket::info!("Moved the expiry warning window of {} tenants into their settings", migrated),
+                Err(err) => {
+                    rocket::error!("{}", err.message);
+                    return Err(rocket);
+                }
+            }
+
+            if let Err(err) = models::product::Product::create_indexes(&client).await {
+                rocket::error!("{}", err.message);
+                return Err(rocket);
+            }
+
+            if let Err(err) = models::item::Item::create_indexes(&client).await {
+                rocket::error!("{}", err.message);
+                return Err(rocket);
+            }
+
+            if let Err(err) = models::login_throttle::LoginThrottle::create_indexes(&client).await {
+                rocket::error!("{}", err.message);
+                return Err(rocket);
+            }
+
+            match models::user::User::migrate_legacy_memberships(&client).await {
+                Ok(0) => Ok(rocket),
+                Ok(migrated) => {
+                    rocket::info!("Turned the tenant ids of {} users into memberships", migrated);
+                    Ok(rocket)
+                },
+                Err(err) => {
+                    rocket::error!("{}", err.message);
+                    Err(rocket)
+                }
+            }
+        }))
+        .attach(AdHoc::try_on_ignite("Auth Config", |rocket| async {
+            match rocket.figment().extract_inner::<AuthConfig>("auth") {
+                Ok(config) => Ok(rocket.manage(config)),
+                Err(err) => {
+                    rocket::error!("Invalid auth config: {}", err);
+                    Err(rocket)
+                }
+            }
+        }))
+        .attach(AdHoc::try_on_ignite("Mailer", |rocket| async {
+            let config = match rocket.figment().extract_inner::<MailConfig>("mail") {
+                Ok(config) => config,
+                Err(err) => {
+                    rocket::error!("Invalid mail config: {}", err);
+                    return Err(rocket);
+                }
+            };
+
+            match config.build_mailer() {
+                Ok(mailer) => Ok(rocket.manage(mailer).manage(config)),
+                Err(err) => {
+                    rocket::error!("{}", err);
+                    Err(rocket)
+                }
+            }
+        }))
+        .attach(AdHoc::try_on_ignite("Jobs Config", |rocket| async {
+            match rocket.figment().extract_inner::<JobsConfig>("jobs") {
+                Ok(config) => Ok(rocket.manage(config)),
+                Err(err) => {
+                    rocket::error!("Invalid jobs config: {}", err);
+                    Err(rocket)
+                }
+            }
+        }))
+        .attach(AdHoc::on_liftoff("Background Jobs", |rocket| Box::pin(async move {
+            let config = rocket.state::<JobsConfig>().unwrap();
+
+            match db::ShelfWatcherDatabase::fetch(rocket) {
+                Some(database) => {
+                    jobs::stock_snapshots::spawn((**database).clone(), config.stock_snapshot_interval_minutes);
+                    jobs::low_stock_alerts::spawn((**database).clone(), config.low_stock_interval_minutes);
+                    jobs::audit_retention::spawn((**database).clone(), config.audit_retention_interval_minutes);
+                    jobs::tenant_purge::spawn((**database).clone(), config.tenant_purge_interval_minutes);
+                },
+                None => rocket::error!("Background jobs not started, database unavailable")
+            }
+        })))
+        .register("/api", catchers![routes::catchers::unauthorized])
         .mount(
             "/api",
             routes![
+                // Auth routes
+                routes::auth::login::login,
+                routes::auth::logout::logout,
+                routes::auth::request_email_verification::request_email_verification,
+                routes::auth::verify_email::verify_email,
+                routes::auth::forgot_password::forgot_password,
+                routes::auth::reset_password::reset_password,
+
+                // Two-factor routes
+                routes::two_factor::enroll::enroll_two_factor,
+                routes::two_factor::confirm::confirm_two_factor,
+                routes::two_factor::disable::disable_two_factor,
+                routes::two_factor::regenerate_recovery_codes::regenerate_recovery_codes,
+
                 // Audit Log routes
                 routes::audit_logs::get_by_type::get_audit_logs_by_type,
                 routes::audit_logs::get_by_id::get_audit_log_by_id,
@@ -42,14 +171,42 @@ fn rocket() -> _ {
                 routes::users::get_all_tenants::get_all_tenants,
                 routes::users::update::update_user,
                 routes::users::delete::delete_user,
+                routes::users::disable::disable_user,
+                routes::users::enable::enable_user,
+                routes::users::unlock::unlock_user,
 
                 // Tenant routes
                 routes::tenants::create::create_tenant,
                 routes::tenants::get_all::get_all_tenants,
                 routes::tenants::get_by_id::get_tenant_by_id,
                 routes::tenants::get_all_members::get_all_members,
+                routes::tenants::add_member::add_member,
+                routes::tenants::update_member::update_member,
+                routes::tenants::remove_member::remove_member,
+                routes::tenants::leave::leave_tenant,
                 routes::tenants::update::update_tenant,
+                routes::tenants::update_plan::update_tenant_plan,
+                routes::tenants::update_settings::update_tenant_settings,
+                routes::tenants::get_usage::get_tenant_usage,
                 routes::tenants::delete::delete_tenant,
+                routes::tenants::restore::restore_tenant,
+                routes::tenants::export::export_tenant,
+                routes::tenants::import::import_tenant,
+                routes::tenants::propose_ownership_transfer::propose_ownership_transfer,
+                routes::tenants::accept_ownership_transfer::accept_ownership_transfer,
+                routes::tenants::cancel_ownership_transfer::cancel_ownership_transfer,
+
+                // Invitation routes
+                routes::invitations::create::create_invitation,
+                routes::invitations::get_all_from_tenant::get_all_invitations_from_tenant,
+                routes::invitations::revoke::revoke_invitation,
+                routes::invitations::accept::accept_invitation,
+
+                // API key routes
+                routes::api_keys::create::create_api_key,
+                routes::api_keys::get_all_from_tenant::get_all_api_keys_from_tenant,
+                routes::api_keys::revoke::revoke_api_key,
+                routes::api_keys::rotate::rotate_api_key,
 
                 // Location routes
                 routes::locations::create::create_location,
@@ -58,6 +215,80 @@ fn rocket() -> _ {
                 routes::locations::get_all_from_tenant::get_all_locations_from_tenant,
                 routes::locations::update::update_location,
                 routes::locations::delete::delete_location,
+
+                // Storage area routes
+                routes::storage_areas::create::create_storage_area,
+                routes::storage_areas::get_all_from_location::get_all_storage_areas_from_location,
+                routes::storage_areas::get_by_code::get_storage_area_by_code,
+                routes::storage_areas::get_stock::get_storage_area_stock,
+                routes::storage_areas::locate_product::locate_product_in_location,
+                routes::storage_areas::update::update_storage_area,
+                routes::storage_areas::delete::delete_storage_area,
+
+                // Product group routes
+                routes::product_groups::create::create_product_group,
+                routes::product_groups::get_all_from_tenant::get_all_product_groups_from_tenant,
+                routes::product_groups::get_by_id::get_product_group_by_id,
+                routes::product_groups::update::update_product_group,
+                routes::product_groups::delete::delete_product_group,
+
+                // Product routes
+                routes::products::create::create_product,
+                routes::products::get_all_from_tenant::get_all_products_from_tenant,
+                routes::products::get_by_barcode::get_product_by_barcode,
+                routes::products::get_by_id::get_product_by_id,
+                routes::products::update::update_product,
+                routes::products::delete::delete_product,
+
+                // Product batch routes
+                routes::product_batches::create::create_product_batch,
+                routes::product_batches::get_all_from_tenant::get_all_product_batches_from_tenant,
+                routes::product_batches::get_by_id::get_product_batch_by_id,
+                routes::product_batches::adjust::adjust_product_batch,
+                routes::product_batches::close::close_product_batch,
+
+                // Item routes
+                routes::items::create::create_item,
+                routes::items::get_all_from_tenant::get_all_items_from_tenant,
+                routes::items::get_by_serial::get_item_by_serial,
+                routes::items::get_history::get_item_history,
+                routes::items::change_status::change_item_status,
+
+                // Stock routes
+                routes::stock::create_movement::create_stock_movement,
+                routes::stock::get_movements::get_stock_movements,
+                routes::stock::get_on_hand::get_stock_on_hand,
+                routes::stock::get_picks::get_stock_picks,
+
+                // Transfer routes
+                routes::transfers::create::create_transfer,
+                routes::transfers::get_all_from_tenant::get_all_transfers_from_tenant,
+                routes::transfers::get_by_id::get_transfer_by_id,
+                routes::transfers::get_in_transit::get_stock_in_transit,
+                routes::transfers::update::update_transfer,
+                routes::transfers::delete::delete_transfer,
+                routes::transfers::ship::ship_transfer,
+                routes::transfers::receive::receive_transfer,
+
+                // Stocktake routes
+                routes::stocktakes::create::create_stocktake,
+                routes::stocktakes::get_all_from_tenant::get_all_stocktakes_from_tenant,
+                routes::stocktakes::get_by_id::get_stocktake_by_id,
+                routes::stocktakes::record_counts::record_stocktake_counts,
+                routes::stocktakes::get_variance::get_stocktake_variance,
+                routes::stocktakes::approve::approve_stocktake,
+                routes::stocktakes::cancel::cancel_stocktake,
+
+                // Reorder point routes
+                routes::reorder_points::create::create_reorder_point,
+                routes::reorder_points::get_all_from_tenant::get_all_reorder_points_from_tenant,
+                routes::reorder_points::update::update_reorder_point,
+                routes::reorder_points::delete::delete_reorder_point,
+
+                // Alert routes
+                routes::alerts::get_low_stock::get_low_stock_alerts,
+                routes::alerts::get_expiring::get_expiring_batches,
+                routes::alerts::acknowledge::acknowledge_alert,
             ],
         )
 }
@@ -0,0 +1,205 @@
+use hmac::{Hmac, Mac};
+use mongodb::bson::Uuid;
+use rand::{distributions::Alphanumeric, Rng};
+use rocket::{http::Status, outcome::Outcome, request::{self, FromRequest}, serde::Deserialize, Request};
+use rocket_db_pools::Connection;
+use sha2::{Digest, Sha256};
+
+use crate::{db::ShelfWatcherDatabase, models::{api_key::ApiKey, audit_log::AuditLogAuthor, http_response::HttpResponse, session::Session, tenant::Tenant, user::User}};
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub fn generate_token(length: usize) -> String {
+    rand::thread_rng().sample_iter(&Alphanumeric).take(length).map(char::from).collect()
+}
+
+// Used for high entropy secrets (recovery codes, one-time tokens), passwords go through bcrypt
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct AuthConfig {
+    pub secret: String,
+    pub session_lifetime_hours: i64,
+    // Failed logins before an account is locked out
+    #[serde(default = "AuthConfig::default_max_failed_logins")]
+    pub max_failed_logins: i64,
+    // Failed logins from one IP address, across all accounts, before it is locked out
+    #[serde(default = "AuthConfig::default_max_failed_logins_per_ip")]
+    pub max_failed_logins_per_ip: i64,
+    #[serde(default = "AuthConfig::default_lockout_minutes")]
+    pub lockout_minutes: i64,
+}
+
+impl AuthConfig {
+    fn default_max_failed_logins() -> i64 {
+        5
+    }
+
+    fn default_max_failed_logins_per_ip() -> i64 {
+        20
+    }
+
+    fn default_lockout_minutes() -> i64 {
+        15
+    }
+
+    // Tokens have the form `<session id>.<hex hmac of the session id>`
+    pub fn sign_session(&self, session_id: Uuid) -> String {
+        format!("{}.{}", session_id, hex::encode(self.mac(session_id).finalize().into_bytes()))
+    }
+
+    pub fn verify_session_token(&self, token: &str) -> Option<Uuid> {
+        let (session_id, signature) = token.split_once('.')?;
+        let session_id = Uuid::parse_str(session_id).ok()?;
+        let signature = hex::decode(signature).ok()?;
+
+        match self.mac(session_id).verify_slice(&signature) {
+            Ok(_) => Some(session_id),
+            Err(_) => None
+        }
+    }
+
+    fn mac(&self, session_id: Uuid) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(self.secret.as_bytes()).expect("HMAC accepts keys of any size");
+        mac.update(session_id.to_string().as_bytes());
+        mac
+    }
+}
+
+// Reason why the guard rejected a request, picked up by the 401 catcher
+pub struct AuthFailure(pub String);
+
+pub struct AuthenticatedUser {
+    pub user: User,
+    pub session: Session,
+}
+
+impl AuthenticatedUser {
+    pub fn author(&self) -> AuditLogAuthor {
+        AuditLogAuthor::User(self.user.id)
+    }
+
+    pub fn is_admin(&self) -> bool {
+        self.user.is_admin
+    }
+
+    pub fn check_two_factor<T>(&self, tenant: &Tenant) -> Result<(), HttpResponse<T>> {
+        if tenant.require_two_factor && self.user.totp_secret.is_none() && !self.is_admin() {
+            return Err(HttpResponse {
+                status: 403,
+                message: "This tenant requires two-factor authentication".to_string(),
+                data: None
+            });
+        }
+
+        Ok(())
+    }
+}
+
+// Caller of a tenant-scoped route, either a logged in user or an API key sent in the `X-Api-Key` header
+pub enum Authenticated {
+    User(AuthenticatedUser),
+    ApiKey(ApiKey),
+}
+
+impl Authenticated {
+    pub fn author(&self) -> AuditLogAuthor {
+        match self {
+            Authenticated::User(auth) => auth.author(),
+            Authenticated::ApiKey(api_key) => AuditLogAuthor::ApiKey(api_key.id)
+        }
+    }
+}
+
+fn reject<T>(req: &Request<'_>, message: &str) -> request::Outcome<T, String> {
+    req.local_cache(|| AuthFailure(message.to_string()));
+    Outcome::Error((Status::Unauthorized, message.to_string()))
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AuthenticatedUser {
+    type Error = String;
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let config = match req.rocket().state::<AuthConfig>() {
+            Some(config) => config,
+            None => return Outcome::Error((Status::InternalServerError, "Auth config is not loaded".to_string()))
+        };
+
+        let token = match req.headers().get_one("Authorization").and_then(|header| header.strip_prefix("Bearer ")) {
+            Some(token) => token.trim(),
+            None => return reject(req, "Missing bearer token")
+        };
+
+        let session_id = match config.verify_session_token(token) {
+            Some(session_id) => session_id,
+            None => return reject(req, "Invalid session token")
+        };
+
+        let db = match req.guard::<Connection<ShelfWatcherDatabase>>().await {
+            Outcome::Success(db) => db,
+            _ => return Outcome::Error((Status::InternalServerError, "Database unavailable".to_string()))
+        };
+
+        let session = match Session::get_by_id(session_id, &db).await {
+            Ok(session) if session.is_valid() => session,
+            _ => return reject(req, "Session expired or revoked")
+        };
+
+        let user = match User::get_full_by_id(session.user_id, &db).await {
+            Ok(user) => user,
+            Err(_) => return reject(req, "User not found")
+        };
+
+        if user.disabled {
+            return reject(req, "User is disabled");
+        }
+
+        Outcome::Success(AuthenticatedUser { user, session })
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Authenticated {
+    type Error = String;
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let key = match req.headers().get_one("X-Api-Key") {
+            Some(key) => key.trim(),
+            None => return req.guard::<AuthenticatedUser>().await.map(Authenticated::User)
+        };
+
+        let db = match req.guard::<Connection<ShelfWatcherDatabase>>().await {
+            Outcome::Success(db) => db,
+            _ => return Outcome::Error((Status::InternalServerError, "Database unavailable".to_string()))
+        };
+
+        let api_key = match ApiKey::get_by_key(key, &db).await {
+            Ok(api_key) if !api_key.revoked => api_key,
+            _ => return reject(req, "Invalid or revoked API key")
+        };
+
+        // Disabling, removing or demoting a user revokes or limits their keys, this also covers keys a failed or concurrent
+        // revocation missed. Unless an admin, the creator must still be a member whose role has all of the key's permissions.
+        let creator = match User::get_full_by_id(api_key.created_by, &db).await {
+            Ok(user) => user,
+            Err(_) => return reject(req, "The creator of the API key no longer exists")
+        };
+        if creator.disabled {
+            return reject(req, "The creator of the API key is disabled");
+        }
+        let role = creator.membership(api_key.tenant_id).map(|membership| membership.role);
+        if !creator.is_admin && !role.is_some_and(|role| api_key.permissions.iter().all(|permission| role.has_permission(*permission))) {
+            return reject(req, "The creator of the API key no longer has its permissions in this tenant");
+        }
+
+        if let Err(err) = api_key.mark_used(&db).await {
+            rocket::error!("{}", err.message);
+        }
+
+        Outcome::Success(Authenticated::ApiKey(api_key))
+    }
+}
@@ -0,0 +1,168 @@
+use mongodb::bson::Uuid;
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, models::{audit_log::{AuditLog, AuditLogEntityType}, http_response::HttpResponse, membership::{TenantPermission, TenantRole}, stocktake::Stocktake, tenant::Tenant}};
+
+use super::auth::{Authenticated, AuthenticatedUser};
+
+impl AuthenticatedUser {
+    pub fn require_admin<T>(&self) -> Result<(), HttpResponse<T>> {
+        if !self.is_admin() {
+            return Err(HttpResponse {
+                status: 403,
+                message: "Only admins can do this".to_string(),
+                data: None
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn require_self_or_admin<T>(&self, user_id: Uuid) -> Result<(), HttpResponse<T>> {
+        if self.user.id != user_id && !self.is_admin() {
+            return Err(HttpResponse {
+                status: 403,
+                message: "You can only access your own account".to_string(),
+                data: None
+            });
+        }
+
+        Ok(())
+    }
+
+    // The tenant owner always counts as owner, even if the membership entry is missing
+    pub fn role_in(&self, tenant: &Tenant) -> Option<TenantRole> {
+        if tenant.owner_id == self.user.id {
+            return Some(TenantRole::Owner);
+        }
+
+        self.user.membership(tenant.id).map(|membership| membership.role)
+    }
+
+    // Checks that the caller may hand out or take away `role` in the tenant
+    pub fn require_can_manage_role<T>(&self, tenant: &Tenant, role: TenantRole) -> Result<(), HttpResponse<T>> {
+        if self.is_admin() && role != TenantRole::Owner {
+            return Ok(());
+        }
+
+        match self.role_in(tenant) {
+            Some(own_role) if own_role.can_manage(role) => Ok(()),
+            _ => Err(HttpResponse {
+                status: 403,
+                message: format!("You are not allowed to manage members with the {:?} role", role),
+                data: None
+            })
+        }
+    }
+
+    // Loads the tenant and checks that the caller may perform `permission` in it. Admins pass every check.
+    pub async fn require_permission<T>(&self, tenant_id: Uuid, permission: TenantPermission, connection: &Connection<ShelfWatcherDatabase>) -> Result<Tenant, HttpResponse<T>> {
+        let tenant = match Tenant::get_by_id(tenant_id, connection).await {
+            Ok(tenant) => tenant,
+            Err(err) => return Err(HttpResponse {
+                status: err.status,
+                message: err.message,
+                data: None
+            })
+        };
+
+        tenant.require_active()?;
+
+        if self.is_admin() {
+            return Ok(tenant);
+        }
+
+        let role = match self.role_in(&tenant) {
+            Some(role) => role,
+            None => return Err(HttpResponse {
+                status: 403,
+                message: "You are not a member of this tenant".to_string(),
+                data: None
+            })
+        };
+
+        self.check_two_factor(&tenant)?;
+
+        if !role.has_permission(permission) {
+            return Err(HttpResponse {
+                status: 403,
+                message: format!("Your role {:?} is missing the {:?} permission", role, permission),
+                data: None
+            });
+        }
+
+        Ok(tenant)
+    }
+
+    // Audit logs of users are visible to the user themself, logs of tenant data to the tenant's members.
+    // Logs of entities that don't exist anymore can't be traced to a tenant and are left to admins.
+    pub async fn require_audit_log_access<T>(&self, entity_id: Uuid, entity_type: &AuditLogEntityType, connection: &Connection<ShelfWatcherDatabase>) -> Result<(), HttpResponse<T>> {
+        if let AuditLogEntityType::User = entity_type {
+            return self.require_self_or_admin(entity_id);
+        }
+
+        match AuditLog::get_entity_tenant_id(entity_id, entity_type, connection).await? {
+            Some(tenant_id) => self.require_permission(tenant_id, TenantPermission::ViewTenant, connection).await.map(|_| ()),
+            None => self.require_admin()
+        }
+    }
+}
+
+impl Authenticated {
+    pub fn require_admin<T>(&self) -> Result<(), HttpResponse<T>> {
+        match self {
+            Authenticated::User(auth) => auth.require_admin(),
+            Authenticated::ApiKey(_) => Err(HttpResponse {
+                status: 403,
+                message: "Only admins can do this".to_string(),
+                data: None
+            })
+        }
+    }
+
+    // API keys only work in their own tenant and only for the permissions they were created with
+    pub async fn require_permission<T>(&self, tenant_id: Uuid, permission: TenantPermission, connection: &Connection<ShelfWatcherDatabase>) -> Result<Tenant, HttpResponse<T>> {
+        let api_key = match self {
+            Authenticated::User(auth) => return auth.require_permission(tenant_id, permission, connection).await,
+            Authenticated::ApiKey(api_key) => api_key
+        };
+
+        if api_key.tenant_id != tenant_id {
+            return Err(HttpResponse {
+                status: 403,
+                message: "API key does not belong to this tenant".to_string(),
+                data: None
+            });
+        }
+
+        if !api_key.has_permission(permission) {
+            return Err(HttpResponse {
+                status: 403,
+                message: format!("API key is missing the {:?} permission", permission),
+                data: None
+            });
+        }
+
+        match Tenant::get_by_id(tenant_id, connection).await {
+            Ok(tenant) => tenant.require_active().map(|_| tenant),
+            Err(err) => Err(HttpResponse {
+                status: err.status,
+                message: err.message,
+                data: None
+            })
+        }
+    }
+
+    // Locations whose stock is hidden from the caller because a blind count is running there
+    pub async fn hidden_stock_locations<T>(&self, tenant_id: Uuid, connection: &Connection<ShelfWatcherDatabase>) -> Result<Vec<Uuid>, HttpResponse<T>> {
+        if self.require_permission::<()>(tenant_id, TenantPermission::ApproveStocktakes, connection).await.is_ok() {
+            return Ok(Vec::new());
+        }
+
+        Stocktake::get_blind_locations(tenant_id, connection).await.map_err(|err| HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    }
+}
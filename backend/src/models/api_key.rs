@@ -0,0 +1,270 @@
+use anyhow::Result;
+use mongodb::bson::{doc, DateTime, Uuid};
+use rocket_db_pools::{mongodb::Collection, Connection};
+use rocket::{futures::StreamExt, serde::{Deserialize, Serialize}};
+use crate::{db::{get_main_db, ShelfWatcherDatabase}, middleware::auth::{generate_token, hash_token}};
+
+use super::{http_response::HttpResponse, membership::TenantPermission};
+
+// Tenant-scoped credential for machine clients. Only the hash of the key is stored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ApiKey {
+    #[serde(rename = "_id")]
+    pub id: Uuid,
+    #[serde(rename = "tenantId")]
+    pub tenant_id: Uuid,
+    pub name: String,
+    // First characters of the key, lets users tell their keys apart
+    pub prefix: String,
+    #[serde(rename = "keyHash")]
+    pub key_hash: String,
+    pub permissions: Vec<TenantPermission>,
+    #[serde(rename = "createdBy")]
+    pub created_by: Uuid,
+    #[serde(rename = "lastUsedAt")]
+    pub last_used_at: Option<String>,
+    pub revoked: bool,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ApiKeyMinimal {
+    #[serde(rename = "_id")]
+    pub id: Uuid,
+    #[serde(rename = "tenantId")]
+    pub tenant_id: Uuid,
+    pub name: String,
+    pub prefix: String,
+    pub permissions: Vec<TenantPermission>,
+    #[serde(rename = "createdBy")]
+    pub created_by: Uuid,
+    #[serde(rename = "lastUsedAt")]
+    pub last_used_at: Option<String>,
+    pub revoked: bool,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+}
+
+impl ApiKey {
+    pub const COLLECTION_NAME: &'static str = "api_keys";
+    pub const KEY_PREFIX: &'static str = "sw_";
+
+    // Returns the key document together with the plain key, which is only shown once
+    pub fn new(tenant_id: Uuid, name: String, permissions: Vec<TenantPermission>, created_by: Uuid) -> (Self, String) {
+        let mut api_key = Self {
+            id: Uuid::new(),
+            tenant_id,
+            name,
+            prefix: String::new(),
+            key_hash: String::new(),
+            permissions,
+            created_by,
+            last_used_at: None,
+            revoked: false,
+            created_at: DateTime::now().to_string(),
+        };
+        let key = api_key.regenerate();
+
+        (api_key, key)
+    }
+
+    // Replaces the secret, the old key stops working once the document is saved
+    pub fn regenerate(&mut self) -> String {
+        let key = format!("{}{}", Self::KEY_PREFIX, generate_token(40));
+        self.prefix = key[..Self::KEY_PREFIX.len() + 6].to_string();
+        self.key_hash = hash_token(&key);
+        key
+    }
+
+    pub fn has_permission(&self, permission: TenantPermission) -> bool {
+        self.permissions.contains(&permission)
+    }
+
+    pub fn to_minimal(&self) -> ApiKeyMinimal {
+        ApiKeyMinimal {
+            id: self.id,
+            tenant_id: self.tenant_id,
+            name: self.name.clone(),
+            prefix: self.prefix.clone(),
+            permissions: self.permissions.clone(),
+            created_by: self.created_by,
+            last_used_at: self.last_used_at.clone(),
+            revoked: self.revoked,
+            created_at: self.created_at.clone()
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn get_from_tenant(id: Uuid, tenant_id: Uuid, connection: &Connection<ShelfWatcherDatabase>) -> Result<Self, HttpResponse<ApiKeyMinimal>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "_id": id,
+            "tenantId": tenant_id
+        };
+        match db.find_one(filter, None).await.unwrap() {
+            Some(api_key) => Ok(api_key),
+            None => Err(HttpResponse {
+                status: 404,
+                message: "API key not found".to_string(),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn get_by_key(key: &str, connection: &Connection<ShelfWatcherDatabase>) -> Result<Self, HttpResponse<ApiKeyMinimal>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "keyHash": hash_token(key)
+        };
+        match db.find_one(filter, None).await.unwrap() {
+            Some(api_key) => Ok(api_key),
+            None => Err(HttpResponse {
+                status: 404,
+                message: "API key not found".to_string(),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn get_all_from_tenant(tenant_id: Uuid, connection: &Connection<ShelfWatcherDatabase>) -> Result<Vec<ApiKeyMinimal>, HttpResponse<Vec<ApiKeyMinimal>>> {
+        let db = Self::get_collection(connection);
+
+        match db.find(doc! { "tenantId": tenant_id }, None).await {
+            Ok(cursor) => {
+                let api_keys = cursor.map(|doc| {
+                    let api_key: ApiKey = doc.unwrap();
+                    api_key.to_minimal()
+                }).collect::<Vec<ApiKeyMinimal>>().await;
+                Ok(api_keys)
+            },
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error fetching API keys from tenant: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn insert(&self, connection: &Connection<ShelfWatcherDatabase>) -> Result<ApiKeyMinimal, HttpResponse<ApiKeyMinimal>> {
+        let db = Self::get_collection(connection);
+
+        match db.insert_one(self.clone(), None).await {
+            Ok(_) => Ok(self.to_minimal()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error inserting API key: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn update(&self, connection: &Connection<ShelfWatcherDatabase>) -> Result<ApiKeyMinimal, HttpResponse<ApiKeyMinimal>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "_id": self.id
+        };
+        match db.replace_one(filter, self.clone(), None).await {
+            Ok(_) => Ok(self.to_minimal()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error updating API key: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    // Writes the regenerated secret unless the key was revoked in the meantime, a revoked key must never become valid again
+    pub async fn update_secret(&self, connection: &Connection<ShelfWatcherDatabase>) -> Result<ApiKeyMinimal, HttpResponse<ApiKeyMinimal>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "_id": self.id,
+            "revoked": false
+        };
+        let update = doc! {
+            "$set": {
+                "prefix": &self.prefix,
+                "keyHash": &self.key_hash
+            }
+        };
+        match db.update_one(filter, update, None).await {
+            Ok(result) if result.matched_count == 0 => Err(HttpResponse {
+                status: 400,
+                message: "Revoked API keys can't be rotated".to_string(),
+                data: None
+            }),
+            Ok(_) => Ok(self.to_minimal()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error updating API key: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    // Revokes the user's keys of one tenant, or of all tenants if None
+    #[allow(unused)]
+    pub async fn revoke_all_created_by(user_id: Uuid, tenant_id: Option<Uuid>, connection: &Connection<ShelfWatcherDatabase>) -> Result<(), HttpResponse<()>> {
+        let db = Self::get_collection(connection);
+
+        let mut filter = doc! {
+            "createdBy": user_id,
+            "revoked": false
+        };
+        if let Some(tenant_id) = tenant_id {
+            filter.insert("tenantId", tenant_id);
+        }
+        let update = doc! {
+            "$set": {
+                "revoked": true
+            }
+        };
+        match db.update_many(filter, update, None).await {
+            Ok(_) => Ok(()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error revoking API keys: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    // Only touches the timestamp so concurrent requests can't overwrite a revocation
+    #[allow(unused)]
+    pub async fn mark_used(&self, connection: &Connection<ShelfWatcherDatabase>) -> Result<(), HttpResponse<()>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "_id": self.id
+        };
+        let update = doc! {
+            "$set": {
+                "lastUsedAt": DateTime::now().to_string()
+            }
+        };
+        match db.update_one(filter, update, None).await {
+            Ok(_) => Ok(()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error updating API key: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    fn get_collection(connection: &Connection<ShelfWatcherDatabase>) -> Collection<Self> {
+        let db = get_main_db(connection);
+        db.collection(Self::COLLECTION_NAME)
+    }
+}
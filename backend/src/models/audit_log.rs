@@ -1,11 +1,11 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt};
 use anyhow::Result;
-use mongodb::bson::{doc, DateTime, Uuid};
-use rocket_db_pools::{mongodb::Collection, Connection};
+use mongodb::bson::{doc, from_bson, DateTime, Document, Uuid};
+use rocket_db_pools::{mongodb::{Client, Collection}, Connection};
 use rocket::{futures::StreamExt, serde::{Deserialize, Serialize}}; 
-use crate::db::{get_logs_db, ShelfWatcherDatabase};
+use crate::db::{get_logs_db, get_main_db, ShelfWatcherDatabase};
 
-use super::http_response::HttpResponse;
+use super::{api_key::ApiKey, http_response::HttpResponse, item::Item, location::Location, product::Product, product_batch::ProductBatch, product_group::ProductGroup, reorder_point::ReorderPoint, stocktake::Stocktake, storage_area::StorageArea, transfer::Transfer};
 
 #[derive(Debug, Clone, Serialize, Deserialize)] 
 #[serde(crate = "rocket::serde")] 
@@ -20,6 +20,8 @@ pub struct AuditLog {
 	pub reason: String,
     #[serde(rename = "userId")]
 	pub author_id: Uuid,
+    #[serde(rename = "authorType", default)]
+    pub author_type: AuditLogAuthorType,
     #[serde(rename = "oldValues")]
 	pub old_values: Option<HashMap<String, String>>,
     #[serde(rename = "newValues")]
@@ -33,7 +35,45 @@ pub struct AuditLog {
 pub enum AuditLogAction {
     Create,
     Update,
-    Delete
+    Delete,
+    Export
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub enum AuditLogAuthorType {
+    #[default]
+    User,
+    System,
+    ApiKey
+}
+
+// Who performed an audited action. Background jobs log as `System`, which is stored with a nil author id.
+#[derive(Debug, Clone, Copy)]
+#[allow(unused)]
+pub enum AuditLogAuthor {
+    User(Uuid),
+    System,
+    ApiKey(Uuid)
+}
+
+impl AuditLogAuthor {
+    pub const SYSTEM_AUTHOR_ID: Uuid = Uuid::from_bytes([0; 16]);
+
+    pub fn id(&self) -> Uuid {
+        match self {
+            AuditLogAuthor::User(id) | AuditLogAuthor::ApiKey(id) => *id,
+            AuditLogAuthor::System => Self::SYSTEM_AUTHOR_ID
+        }
+    }
+
+    pub fn author_type(&self) -> AuditLogAuthorType {
+        match self {
+            AuditLogAuthor::User(_) => AuditLogAuthorType::User,
+            AuditLogAuthor::System => AuditLogAuthorType::System,
+            AuditLogAuthor::ApiKey(_) => AuditLogAuthorType::ApiKey
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -46,6 +86,11 @@ pub enum AuditLogEntityType {
     ProductBatch,
     Product,
     Item,
+    ApiKey,
+    Transfer,
+    Stocktake,
+    ReorderPoint,
+    StorageArea,
     Unknown
 }
 
@@ -60,21 +105,35 @@ impl AuditLogEntityType {
             "PRODUCT-BATCH" => Ok(AuditLogEntityType::ProductBatch),
             "PRODUCT" => Ok(AuditLogEntityType::Product),
             "ITEM" => Ok(AuditLogEntityType::Item),
-            _ => Err(HttpResponse { status: 400, message: format!(""), data: None })
+            "API-KEY" => Ok(AuditLogEntityType::ApiKey),
+            "TRANSFER" => Ok(AuditLogEntityType::Transfer),
+            "STOCKTAKE" => Ok(AuditLogEntityType::Stocktake),
+            "REORDER-POINT" => Ok(AuditLogEntityType::ReorderPoint),
+            "STORAGE-AREA" => Ok(AuditLogEntityType::StorageArea),
+            _ => Err(HttpResponse { status: 400, message: "Invalid audit log entity type provided".to_string(), data: None })
         }
     }
 
-    pub fn to_string(&self) -> String {
-        match self {
-            &AuditLogEntityType::User => "USER".to_string(),
-            &AuditLogEntityType::Tenant => "TENANT".to_string(),
-            &AuditLogEntityType::Location => "LOCATION".to_string(),
-            &AuditLogEntityType::ProductGroup => "PRODUCT-GROUP".to_string(),
-            &AuditLogEntityType::ProductBatch => "PRODUCT-BATCH".to_string(),
-            &AuditLogEntityType::Product => "PRODUCT".to_string(),
-            &AuditLogEntityType::Item => "ITEM".to_string(),
-            _ => "UNKNOWN".to_string()
-        }
+}
+
+impl fmt::Display for AuditLogEntityType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            AuditLogEntityType::User => "USER",
+            AuditLogEntityType::Tenant => "TENANT",
+            AuditLogEntityType::Location => "LOCATION",
+            AuditLogEntityType::ProductGroup => "PRODUCT-GROUP",
+            AuditLogEntityType::ProductBatch => "PRODUCT-BATCH",
+            AuditLogEntityType::Product => "PRODUCT",
+            AuditLogEntityType::Item => "ITEM",
+            AuditLogEntityType::ApiKey => "API-KEY",
+            AuditLogEntityType::Transfer => "TRANSFER",
+            AuditLogEntityType::Stocktake => "STOCKTAKE",
+            AuditLogEntityType::ReorderPoint => "REORDER-POINT",
+            AuditLogEntityType::StorageArea => "STORAGE-AREA",
+            _ => "UNKNOWN"
+        };
+        write!(f, "{}", name)
     }
 }
 
@@ -88,16 +147,22 @@ impl AuditLog {
     pub const COLLECTION_NAME_PRODUCT_BATCHES: &'static str = "product_batch-logs";
     pub const COLLECTION_NAME_PRODUCTS: &'static str = "product-logs";
     pub const COLLECTION_NAME_ITEMS: &'static str = "item-logs";
+    pub const COLLECTION_NAME_API_KEYS: &'static str = "api_key-logs";
+    pub const COLLECTION_NAME_TRANSFERS: &'static str = "transfer-logs";
+    pub const COLLECTION_NAME_STOCKTAKES: &'static str = "stocktake-logs";
+    pub const COLLECTION_NAME_REORDER_POINTS: &'static str = "reorder_point-logs";
+    pub const COLLECTION_NAME_STORAGE_AREAS: &'static str = "storage_area-logs";
 
     #[allow(unused)]
-    pub fn new(entity_id: Uuid, entity_type: AuditLogEntityType, action: AuditLogAction, reason: String, author_id: Uuid, old_values: Option<HashMap<String, String>>, new_values: Option<HashMap<String, String>>) -> Self {
+    pub fn new(entity_id: Uuid, entity_type: AuditLogEntityType, action: AuditLogAction, reason: String, author: AuditLogAuthor, old_values: Option<HashMap<String, String>>, new_values: Option<HashMap<String, String>>) -> Self {
         Self {
             id: Uuid::new(),
             entity_id,
             entity_type,
             action,
             reason,
-            author_id,
+            author_id: author.id(),
+            author_type: author.author_type(),
             old_values,
             new_values,
             created_at: DateTime::now().to_string(),
@@ -121,7 +186,7 @@ impl AuditLog {
         };
         match db.find_one(filter, None).await.unwrap() {
             Some(audit_log) => Ok(audit_log),
-            None => return Err(HttpResponse {
+            None => Err(HttpResponse {
                 status: 404,
                 message: "Audit log not found".to_string(),
                 data: None
@@ -210,7 +275,7 @@ impl AuditLog {
     }
 
     #[allow(unused)]
-    pub async fn insert(&self, connection: &Connection<ShelfWatcherDatabase>) -> Result<(), String> {
+    pub async fn insert(&self, connection: &Client) -> Result<(), String> {
         let db = match Self::get_collection(&self.entity_type, connection) {
             Some(db) => db,
             None => return Err("Invalid audit log entity type provided".to_string())
@@ -222,18 +287,79 @@ impl AuditLog {
         }
     }
 
-    fn get_collection(entity_type: &AuditLogEntityType, connection: &Connection<ShelfWatcherDatabase>) -> Option<Collection<AuditLog>> {
+    // Deletes the logs of the entities created before `before`, a `YYYY-MM-DD` date. Returns how many were deleted.
+    pub async fn delete_before(entity_type: AuditLogEntityType, entity_ids: &[Uuid], before: &str, client: &Client) -> Result<u64, HttpResponse<()>> {
+        let db = match Self::get_collection(&entity_type, client) {
+            Some(db) => db,
+            None => return Err(HttpResponse {
+                status: 400,
+                message: "Invalid audit log entity type provided".to_string(),
+                data: None
+            })
+        };
+
+        // `createdAt` starts with the date, so comparing the strings compares the days
+        let filter = doc! {
+            "entityId": { "$in": entity_ids },
+            "createdAt": { "$lt": before }
+        };
+        match db.delete_many(filter, None).await {
+            Ok(result) => Ok(result.deleted_count),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error deleting audit logs: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    // The tenant the logged entity belongs to. None for users, and for entities that don't exist anymore.
+    pub async fn get_entity_tenant_id<T>(entity_id: Uuid, entity_type: &AuditLogEntityType, connection: &Client) -> Result<Option<Uuid>, HttpResponse<T>> {
+        let collection = match entity_type {
+            AuditLogEntityType::Tenant => return Ok(Some(entity_id)),
+            AuditLogEntityType::User | AuditLogEntityType::Unknown => return Ok(None),
+            AuditLogEntityType::Location => Location::COLLECTION_NAME,
+            AuditLogEntityType::ProductGroup => ProductGroup::COLLECTION_NAME,
+            AuditLogEntityType::ProductBatch => ProductBatch::COLLECTION_NAME,
+            AuditLogEntityType::Product => Product::COLLECTION_NAME,
+            AuditLogEntityType::Item => Item::COLLECTION_NAME,
+            AuditLogEntityType::ApiKey => ApiKey::COLLECTION_NAME,
+            AuditLogEntityType::Transfer => Transfer::COLLECTION_NAME,
+            AuditLogEntityType::Stocktake => Stocktake::COLLECTION_NAME,
+            AuditLogEntityType::ReorderPoint => ReorderPoint::COLLECTION_NAME,
+            AuditLogEntityType::StorageArea => StorageArea::COLLECTION_NAME
+        };
+        let db: Collection<Document> = get_main_db(connection).collection(collection);
+
+        let filter = doc! {
+            "_id": entity_id
+        };
+        match db.find_one(filter, None).await {
+            Ok(entity) => Ok(entity.and_then(|entity| from_bson::<Uuid>(entity.get("tenantId")?.clone()).ok())),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error fetching audited entity: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    fn get_collection(entity_type: &AuditLogEntityType, connection: &Client) -> Option<Collection<AuditLog>> {
         let db = get_logs_db(connection);
-        println!("Entity type: {:?}", entity_type);
         match entity_type {
-            &AuditLogEntityType::User => return Some(db.collection(Self::COLLECTION_NAME_USERS)),
-            &AuditLogEntityType::Tenant => return Some(db.collection(Self::COLLECTION_NAME_TENANTS)),
-            &AuditLogEntityType::Location => return Some(db.collection(Self::COLLECTION_NAME_LOCATIONS)),
-            &AuditLogEntityType::ProductGroup => return Some(db.collection(Self::COLLECTION_NAME_PRODUCT_GROUPS)),
-            &AuditLogEntityType::ProductBatch => return Some(db.collection(Self::COLLECTION_NAME_PRODUCT_BATCHES)),
-            &AuditLogEntityType::Product => return Some(db.collection(Self::COLLECTION_NAME_PRODUCTS)),
-            &AuditLogEntityType::Item => return Some(db.collection(Self::COLLECTION_NAME_ITEMS)),
-            &AuditLogEntityType::Unknown => None
+            AuditLogEntityType::User => Some(db.collection(Self::COLLECTION_NAME_USERS)),
+            AuditLogEntityType::Tenant => Some(db.collection(Self::COLLECTION_NAME_TENANTS)),
+            AuditLogEntityType::Location => Some(db.collection(Self::COLLECTION_NAME_LOCATIONS)),
+            AuditLogEntityType::ProductGroup => Some(db.collection(Self::COLLECTION_NAME_PRODUCT_GROUPS)),
+            AuditLogEntityType::ProductBatch => Some(db.collection(Self::COLLECTION_NAME_PRODUCT_BATCHES)),
+            AuditLogEntityType::Product => Some(db.collection(Self::COLLECTION_NAME_PRODUCTS)),
+            AuditLogEntityType::Item => Some(db.collection(Self::COLLECTION_NAME_ITEMS)),
+            AuditLogEntityType::ApiKey => Some(db.collection(Self::COLLECTION_NAME_API_KEYS)),
+            AuditLogEntityType::Transfer => Some(db.collection(Self::COLLECTION_NAME_TRANSFERS)),
+            AuditLogEntityType::Stocktake => Some(db.collection(Self::COLLECTION_NAME_STOCKTAKES)),
+            AuditLogEntityType::ReorderPoint => Some(db.collection(Self::COLLECTION_NAME_REORDER_POINTS)),
+            AuditLogEntityType::StorageArea => Some(db.collection(Self::COLLECTION_NAME_STORAGE_AREAS)),
+            AuditLogEntityType::Unknown => None
         }
     }
 }
\ No newline at end of file
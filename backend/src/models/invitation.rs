@@ -0,0 +1,236 @@
+use anyhow::Result;
+use mongodb::bson::{doc, DateTime, Uuid};
+use rocket_db_pools::{mongodb::Collection, Connection};
+use rocket::{futures::StreamExt, serde::{Deserialize, Serialize}};
+use crate::{db::{get_main_db, ShelfWatcherDatabase}, middleware::auth::{generate_token, hash_token}};
+
+use super::{http_response::HttpResponse, membership::TenantRole};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Invitation {
+    #[serde(rename = "_id")]
+    pub id: Uuid,
+    #[serde(rename = "tenantId")]
+    pub tenant_id: Uuid,
+    pub email: String,
+    pub role: TenantRole,
+    #[serde(rename = "tokenHash")]
+    pub token_hash: String,
+    #[serde(rename = "invitedBy")]
+    pub invited_by: Uuid,
+    #[serde(rename = "expiresAt")]
+    pub expires_at: DateTime,
+    #[serde(rename = "acceptedAt")]
+    pub accepted_at: Option<String>,
+    pub revoked: bool,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct InvitationMinimal {
+    #[serde(rename = "_id")]
+    pub id: Uuid,
+    #[serde(rename = "tenantId")]
+    pub tenant_id: Uuid,
+    pub email: String,
+    pub role: TenantRole,
+    #[serde(rename = "invitedBy")]
+    pub invited_by: Uuid,
+    #[serde(rename = "expiresAt")]
+    pub expires_at: String,
+    #[serde(rename = "acceptedAt")]
+    pub accepted_at: Option<String>,
+    pub revoked: bool,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+}
+
+impl Invitation {
+    pub const COLLECTION_NAME: &'static str = "invitations";
+    pub const LIFETIME_HOURS: i64 = 72;
+
+    // Returns the invitation together with the plain token, which is only ever sent by mail
+    pub fn new(tenant_id: Uuid, email: String, role: TenantRole, invited_by: Uuid) -> (Self, String) {
+        let token = generate_token(32);
+
+        let invitation = Self {
+            id: Uuid::new(),
+            tenant_id,
+            email,
+            role,
+            token_hash: hash_token(&token),
+            invited_by,
+            expires_at: DateTime::from_millis(DateTime::now().timestamp_millis() + Self::LIFETIME_HOURS * 60 * 60 * 1000),
+            accepted_at: None,
+            revoked: false,
+            created_at: DateTime::now().to_string(),
+        };
+
+        (invitation, token)
+    }
+
+    pub fn is_pending(&self) -> bool {
+        !self.revoked && self.accepted_at.is_none() && self.expires_at > DateTime::now()
+    }
+
+    pub fn to_minimal(&self) -> InvitationMinimal {
+        InvitationMinimal {
+            id: self.id,
+            tenant_id: self.tenant_id,
+            email: self.email.clone(),
+            role: self.role,
+            invited_by: self.invited_by,
+            expires_at: self.expires_at.to_string(),
+            accepted_at: self.accepted_at.clone(),
+            revoked: self.revoked,
+            created_at: self.created_at.clone()
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn get_by_id(id: Uuid, connection: &Connection<ShelfWatcherDatabase>) -> Result<Self, HttpResponse<InvitationMinimal>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "_id": id
+        };
+        match db.find_one(filter, None).await.unwrap() {
+            Some(invitation) => Ok(invitation),
+            None => Err(HttpResponse {
+                status: 404,
+                message: "Invitation not found".to_string(),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn get_by_token(token: &str, connection: &Connection<ShelfWatcherDatabase>) -> Result<Self, HttpResponse<InvitationMinimal>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "tokenHash": hash_token(token)
+        };
+        match db.find_one(filter, None).await.unwrap() {
+            Some(invitation) => Ok(invitation),
+            None => Err(HttpResponse {
+                status: 404,
+                message: "Invitation not found".to_string(),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn get_all_from_tenant(tenant_id: Uuid, connection: &Connection<ShelfWatcherDatabase>) -> Result<Vec<InvitationMinimal>, HttpResponse<Vec<InvitationMinimal>>> {
+        let db = Self::get_collection(connection);
+
+        match db.find(doc! { "tenantId": tenant_id }, None).await {
+            Ok(cursor) => {
+                let invitations = cursor.map(|doc| {
+                    let invitation: Invitation = doc.unwrap();
+                    invitation.to_minimal()
+                }).collect::<Vec<InvitationMinimal>>().await;
+                Ok(invitations)
+            },
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error fetching invitations from tenant: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn insert(&self, connection: &Connection<ShelfWatcherDatabase>) -> Result<InvitationMinimal, HttpResponse<InvitationMinimal>> {
+        let db = Self::get_collection(connection);
+
+        match db.insert_one(self.clone(), None).await {
+            Ok(_) => Ok(self.to_minimal()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error inserting invitation: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn update(&self, connection: &Connection<ShelfWatcherDatabase>) -> Result<InvitationMinimal, HttpResponse<InvitationMinimal>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "_id": self.id
+        };
+        match db.replace_one(filter, self.clone(), None).await {
+            Ok(_) => Ok(self.to_minimal()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error updating invitation: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    // Claims the invitation, fails if it was accepted or revoked in the meantime
+    #[allow(unused)]
+    pub async fn accept(&self, connection: &Connection<ShelfWatcherDatabase>) -> Result<(), HttpResponse<()>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "_id": self.id,
+            "acceptedAt": null,
+            "revoked": false
+        };
+        let update = doc! {
+            "$set": {
+                "acceptedAt": DateTime::now().to_string()
+            }
+        };
+        match db.update_one(filter, update, None).await {
+            Ok(result) if result.modified_count == 1 => Ok(()),
+            Ok(_) => Err(HttpResponse {
+                status: 400,
+                message: "Invitation was already used".to_string(),
+                data: None
+            }),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error accepting invitation: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    // Hands a claimed invitation back when accepting it failed afterwards, so the invitee can try again
+    pub async fn reopen(&self, connection: &Connection<ShelfWatcherDatabase>) -> Result<(), HttpResponse<()>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "_id": self.id,
+            "acceptedAt": { "$ne": null }
+        };
+        let update = doc! {
+            "$set": {
+                "acceptedAt": null
+            }
+        };
+        match db.update_one(filter, update, None).await {
+            Ok(_) => Ok(()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error reopening invitation: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    fn get_collection(connection: &Connection<ShelfWatcherDatabase>) -> Collection<Self> {
+        let db = get_main_db(connection);
+        db.collection(Self::COLLECTION_NAME)
+    }
+}
@@ -0,0 +1,296 @@
+use anyhow::Result;
+use mongodb::bson::{doc, to_bson, DateTime, Uuid};
+use rocket_db_pools::{mongodb::{Client, Collection}, Connection};
+use rocket::{futures::StreamExt, serde::{Deserialize, Serialize}};
+use crate::db::{create_unique_index, get_main_db, is_duplicate_key, ShelfWatcherDatabase};
+
+use super::{audit_log::{AuditLogAuthor, AuditLogAuthorType}, http_response::HttpResponse, stock_movement::{StockMovement, StockMovementType}};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub enum ItemStatus {
+    InStock,
+    Reserved,
+    Sold,
+    Returned,
+    Scrapped,
+    Lost
+}
+
+impl ItemStatus {
+    pub fn from_string<T>(status: &str) -> Result<Self, HttpResponse<T>> {
+        match status.to_uppercase().as_str() {
+            "IN_STOCK" => Ok(ItemStatus::InStock),
+            "RESERVED" => Ok(ItemStatus::Reserved),
+            "SOLD" => Ok(ItemStatus::Sold),
+            "RETURNED" => Ok(ItemStatus::Returned),
+            "SCRAPPED" => Ok(ItemStatus::Scrapped),
+            "LOST" => Ok(ItemStatus::Lost),
+            _ => Err(HttpResponse { status: 400, message: "Invalid item status provided".to_string(), data: None })
+        }
+    }
+
+    // Scrapped items never come back. Lost items can be found again, returned ones restocked.
+    pub fn can_change_to(&self, next: ItemStatus) -> bool {
+        match self {
+            ItemStatus::InStock => matches!(next, ItemStatus::Reserved | ItemStatus::Sold | ItemStatus::Scrapped | ItemStatus::Lost),
+            ItemStatus::Reserved => matches!(next, ItemStatus::InStock | ItemStatus::Sold | ItemStatus::Scrapped | ItemStatus::Lost),
+            ItemStatus::Sold => matches!(next, ItemStatus::Returned),
+            ItemStatus::Returned => matches!(next, ItemStatus::InStock | ItemStatus::Scrapped),
+            ItemStatus::Lost => matches!(next, ItemStatus::InStock),
+            ItemStatus::Scrapped => false
+        }
+    }
+
+    // In stock items count towards the stock on hand of their batch
+    pub fn is_in_stock(&self) -> bool {
+        matches!(self, ItemStatus::InStock | ItemStatus::Reserved)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ItemStatusChange {
+    // None for the initial entry
+    pub from: Option<ItemStatus>,
+    pub to: ItemStatus,
+    #[serde(rename = "locationId")]
+    pub location_id: Uuid,
+    pub reason: Option<String>,
+    #[serde(rename = "authorId")]
+    pub author_id: Uuid,
+    #[serde(rename = "authorType")]
+    pub author_type: AuditLogAuthorType,
+    #[serde(rename = "changedAt")]
+    pub changed_at: String,
+}
+
+// A single serialized unit of a product
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Item {
+    #[serde(rename = "_id")]
+    pub id: Uuid,
+    #[serde(rename = "tenantId")]
+    pub tenant_id: Uuid,
+    // Unique per tenant
+    #[serde(rename = "serialNumber")]
+    pub serial_number: String,
+    #[serde(rename = "productId")]
+    pub product_id: Uuid,
+    #[serde(rename = "batchId")]
+    pub batch_id: Uuid,
+    #[serde(rename = "locationId")]
+    pub location_id: Uuid,
+    pub status: ItemStatus,
+    pub history: Vec<ItemStatusChange>,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+}
+
+impl Item {
+    pub const COLLECTION_NAME: &'static str = "items";
+
+    pub fn new(tenant_id: Uuid, serial_number: String, product_id: Uuid, batch_id: Uuid, location_id: Uuid, author: AuditLogAuthor) -> Self {
+        let mut item = Self {
+            id: Uuid::new(),
+            tenant_id,
+            serial_number,
+            product_id,
+            batch_id,
+            location_id,
+            status: ItemStatus::InStock,
+            history: Vec::new(),
+            created_at: DateTime::now().to_string(),
+        };
+        item.record(None, ItemStatus::InStock, None, author);
+
+        item
+    }
+
+    // Moves the item to `status`, optionally to another location, and appends the change to its history
+    pub fn change_status<T>(&mut self, status: ItemStatus, location_id: Option<Uuid>, reason: Option<String>, author: AuditLogAuthor) -> Result<(), HttpResponse<T>> {
+        if !self.status.can_change_to(status) {
+            return Err(HttpResponse {
+                status: 400,
+                message: format!("Item can't change from {:?} to {:?}", self.status, status),
+                data: None
+            });
+        }
+
+        let from = self.status;
+        self.status = status;
+        if let Some(location_id) = location_id {
+            self.location_id = location_id;
+        }
+        self.record(Some(from), status, reason, author);
+
+        Ok(())
+    }
+
+    // The movement that keeps the ledger in line after the item changed from `from` at `from_location_id`. Leaving stock books
+    // the item out of its old location, coming back books it into the current one, moving while in stock transfers it between
+    // them. None if it stays out of stock, or in stock at the same location.
+    pub fn stock_movement<T>(&self, from: ItemStatus, from_location_id: Uuid, author: AuditLogAuthor) -> Result<Option<StockMovement>, HttpResponse<T>> {
+        let (movement_type, reason_code) = match (from.is_in_stock(), self.status) {
+            (true, ItemStatus::Sold) => (StockMovementType::Issue, "ITEM_SOLD"),
+            (true, ItemStatus::Scrapped) => (StockMovementType::WriteOff, "ITEM_SCRAPPED"),
+            (true, ItemStatus::Lost) => (StockMovementType::WriteOff, "ITEM_LOST"),
+            (true, _) if self.location_id != from_location_id => (StockMovementType::Transfer, "ITEM_MOVED"),
+            (false, status) if status.is_in_stock() => (StockMovementType::Receipt, "ITEM_RESTOCKED"),
+            _ => return Ok(None)
+        };
+
+        let (from_location_id, to_location_id) = match movement_type {
+            StockMovementType::Receipt => (None, Some(self.location_id)),
+            StockMovementType::Transfer => (Some(from_location_id), Some(self.location_id)),
+            _ => (Some(from_location_id), None)
+        };
+        StockMovement::new(self.tenant_id, movement_type, self.product_id, Some(self.batch_id), from_location_id, to_location_id, None, None, 1.0, reason_code, Some(self.id), author).map(Some)
+    }
+
+    fn record(&mut self, from: Option<ItemStatus>, to: ItemStatus, reason: Option<String>, author: AuditLogAuthor) {
+        self.history.push(ItemStatusChange {
+            from,
+            to,
+            location_id: self.location_id,
+            reason,
+            author_id: author.id(),
+            author_type: author.author_type(),
+            changed_at: DateTime::now().to_string()
+        });
+    }
+
+    #[allow(unused)]
+    pub async fn get_from_tenant(id: Uuid, tenant_id: Uuid, connection: &Connection<ShelfWatcherDatabase>) -> Result<Self, HttpResponse<Self>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "_id": id,
+            "tenantId": tenant_id
+        };
+        match db.find_one(filter, None).await.unwrap() {
+            Some(item) => Ok(item),
+            None => Err(HttpResponse {
+                status: 404,
+                message: "Item not found".to_string(),
+                data: None
+            })
+        }
+    }
+
+    // Keeps two concurrent creates from both taking a serial number the `get_by_serial` check found free
+    pub async fn create_indexes(client: &Client) -> Result<(), HttpResponse<()>> {
+        create_unique_index(Self::COLLECTION_NAME, doc! { "tenantId": 1, "serialNumber": 1 }, None, client).await
+    }
+
+    #[allow(unused)]
+    pub async fn get_by_serial(serial_number: &str, tenant_id: Uuid, connection: &Connection<ShelfWatcherDatabase>) -> Result<Self, HttpResponse<Self>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "tenantId": tenant_id,
+            "serialNumber": serial_number
+        };
+        match db.find_one(filter, None).await.unwrap() {
+            Some(item) => Ok(item),
+            None => Err(HttpResponse {
+                status: 404,
+                message: "No item with this serial number".to_string(),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn get_all_from_tenant(tenant_id: Uuid, product_id: Option<Uuid>, batch_id: Option<Uuid>, location_id: Option<Uuid>, status: Option<ItemStatus>, connection: &Connection<ShelfWatcherDatabase>) -> Result<Vec<Self>, HttpResponse<Vec<Self>>> {
+        let db = Self::get_collection(connection);
+
+        let mut filter = doc! {
+            "tenantId": tenant_id
+        };
+        if let Some(product_id) = product_id {
+            filter.insert("productId", product_id);
+        }
+        if let Some(batch_id) = batch_id {
+            filter.insert("batchId", batch_id);
+        }
+        if let Some(location_id) = location_id {
+            filter.insert("locationId", location_id);
+        }
+        if let Some(status) = status {
+            filter.insert("status", to_bson(&status).unwrap());
+        }
+        match db.find(filter, None).await {
+            Ok(cursor) => {
+                let items = cursor.map(|doc| doc.unwrap()).collect::<Vec<Self>>().await;
+                Ok(items)
+            },
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error fetching items from tenant: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn insert(&self, connection: &Connection<ShelfWatcherDatabase>) -> Result<Self, HttpResponse<Self>> {
+        let db = Self::get_collection(connection);
+
+        match db.insert_one(self.clone(), None).await {
+            Ok(_) => Ok(self.clone()),
+            Err(err) if is_duplicate_key(&err) => Err(HttpResponse {
+                status: 400,
+                message: "Serial number already exists".to_string(),
+                data: None
+            }),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error inserting item: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    // Writes the item only while it still has `status` in the database. Returns false if another request changed it first.
+    pub async fn replace_if(&self, status: ItemStatus, connection: &Connection<ShelfWatcherDatabase>) -> Result<bool, HttpResponse<Self>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "_id": self.id,
+            "status": to_bson(&status).unwrap()
+        };
+        match db.replace_one(filter, self.clone(), None).await {
+            Ok(result) => Ok(result.matched_count == 1),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error updating item: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn update(&self, connection: &Connection<ShelfWatcherDatabase>) -> Result<Self, HttpResponse<Self>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "_id": self.id
+        };
+        match db.replace_one(filter, self.clone(), None).await {
+            Ok(_) => Ok(self.clone()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error updating item: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    fn get_collection(connection: &Connection<ShelfWatcherDatabase>) -> Collection<Self> {
+        let db = get_main_db(connection);
+        db.collection(Self::COLLECTION_NAME)
+    }
+}
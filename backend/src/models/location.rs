@@ -1,10 +1,10 @@
 use anyhow::Result;
-use mongodb::bson::{doc, DateTime, Uuid};
-use rocket_db_pools::{mongodb::Collection, Connection};
+use mongodb::bson::{doc, to_bson, DateTime, Uuid};
+use rocket_db_pools::{mongodb::{Client, Collection}, Connection};
 use rocket::{futures::StreamExt, serde::{Deserialize, Serialize}};
-use crate::db::{get_main_db, ShelfWatcherDatabase};
+use crate::db::{count_documents, get_main_db, ShelfWatcherDatabase};
 
-use super::http_response::HttpResponse;
+use super::{http_response::HttpResponse, item::Item, product_batch::ProductBatch, reorder_point::ReorderPoint, stock_movement::StockMovement, stocktake::{Stocktake, StocktakeStatus}, storage_area::StorageArea, transfer::{Transfer, TransferStatus}};
 
 #[derive(Debug, Clone, Serialize, Deserialize)] 
 #[serde(crate = "rocket::serde")] 
@@ -47,6 +47,24 @@ impl Location {
         }
     }
 
+    #[allow(unused)]
+    pub async fn get_from_tenant(id: Uuid, tenant_id: Uuid, connection: &Connection<ShelfWatcherDatabase>) -> Result<Self, HttpResponse<Self>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "_id": id,
+            "tenantId": tenant_id
+        };
+        match db.find_one(filter, None).await.unwrap() {
+            Some(location) => Ok(location),
+            None => Err(HttpResponse {
+                status: 404,
+                message: "Location not found".to_string(),
+                data: None
+            })
+        }
+    }
+
     #[allow(unused)]
     pub async fn get_all(connection: &Connection<ShelfWatcherDatabase>) -> Result<Vec<Self>, HttpResponse<Vec<Self>>> {
         let db = Self::get_collection(connection);
@@ -112,6 +130,32 @@ impl Location {
         }
     }
 
+    // What still refers to the location and keeps it from being deleted, None once nothing does
+    pub async fn get_delete_blocker(&self, client: &Client) -> Result<Option<&'static str>, HttpResponse<()>> {
+        let references = [
+            (StorageArea::COLLECTION_NAME, doc! { "locationId": self.id }, "storage areas"),
+            (ProductBatch::COLLECTION_NAME, doc! { "locationId": self.id }, "batches"),
+            (Item::COLLECTION_NAME, doc! { "locationId": self.id }, "items"),
+            (ReorderPoint::COLLECTION_NAME, doc! { "locationId": self.id }, "reorder points"),
+            (Transfer::COLLECTION_NAME, doc! {
+                "$or": [{ "sourceLocationId": self.id }, { "destinationLocationId": self.id }],
+                "status": { "$in": [to_bson(&TransferStatus::Draft).unwrap(), to_bson(&TransferStatus::Shipped).unwrap()] }
+            }, "open transfers"),
+            (Stocktake::COLLECTION_NAME, doc! { "locationId": self.id, "status": to_bson(&StocktakeStatus::Open).unwrap() }, "open stocktakes")
+        ];
+        for (collection, filter, name) in references {
+            if count_documents(collection, filter, client).await? > 0 {
+                return Ok(Some(name));
+            }
+        }
+
+        if !StockMovement::on_hand(self.tenant_id, None, None, Some(self.id), client).await?.is_empty() {
+            return Ok(Some("stock on hand"));
+        }
+
+        Ok(None)
+    }
+
     #[allow(unused)]
     pub async fn delete(&self, connection: &Connection<ShelfWatcherDatabase>) -> Result<Self, HttpResponse<()>> {
         let db = Self::get_collection(connection);
@@ -0,0 +1,204 @@
+use anyhow::Result;
+use mongodb::bson::{doc, to_bson, DateTime, Uuid};
+use rocket_db_pools::{mongodb::{options::{FindOneAndUpdateOptions, ReturnDocument}, Client, Collection}, Connection};
+use rocket::serde::{Deserialize, Serialize};
+use crate::db::{create_unique_index, get_main_db, is_duplicate_key, ShelfWatcherDatabase};
+
+use super::http_response::HttpResponse;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub enum LoginThrottleKind {
+    Account,
+    Ip
+}
+
+// Failed login counter for one account (by email) or one client IP. Lives in the database so all instances share it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct LoginThrottle {
+    #[serde(rename = "_id")]
+    pub id: Uuid,
+    pub kind: LoginThrottleKind,
+    pub subject: String,
+    pub failures: i64,
+    // Set once `failures` reached the limit, as opposed to the short backoff in between
+    pub locked: bool,
+    #[serde(rename = "blockedUntil")]
+    pub blocked_until: Option<DateTime>,
+    #[serde(rename = "lastFailureAt")]
+    pub last_failure_at: DateTime,
+}
+
+impl LoginThrottle {
+    pub const COLLECTION_NAME: &'static str = "login_throttles";
+    pub const MAX_BACKOFF_SECONDS: i64 = 60;
+
+    pub fn new(kind: LoginThrottleKind, subject: &str) -> Self {
+        Self {
+            id: Uuid::new(),
+            kind,
+            subject: subject.to_lowercase(),
+            failures: 0,
+            locked: false,
+            blocked_until: None,
+            last_failure_at: DateTime::now(),
+        }
+    }
+
+    // Keeps concurrent first failures of a subject from upserting a counter each
+    pub async fn create_indexes(client: &Client) -> Result<(), HttpResponse<()>> {
+        create_unique_index(Self::COLLECTION_NAME, doc! { "kind": 1, "subject": 1 }, None, client).await
+    }
+
+    pub fn is_blocked(&self) -> bool {
+        matches!(self.blocked_until, Some(blocked_until) if blocked_until > DateTime::now())
+    }
+
+    pub fn retry_after_seconds(&self) -> i64 {
+        match self.blocked_until {
+            Some(blocked_until) => ((blocked_until.timestamp_millis() - DateTime::now().timestamp_millis()) / 1000).max(1),
+            None => 0
+        }
+    }
+
+    // Counts a failed attempt and blocks further attempts for an exponentially growing delay. The counter is incremented
+    // in the database, so parallel attempts can't slip past the limits. Returns the counter and whether this failure locked the subject out.
+    pub async fn register_failure(kind: LoginThrottleKind, subject: &str, max_failures: i64, lockout_minutes: i64, connection: &Connection<ShelfWatcherDatabase>) -> Result<(Self, bool), HttpResponse<()>> {
+        let db = Self::get_collection(connection);
+        let now = DateTime::now().timestamp_millis();
+
+        let filter = doc! {
+            "kind": to_bson(&kind).unwrap(),
+            "subject": subject.to_lowercase()
+        };
+
+        // Failures older than a lockout period are forgotten
+        let mut stale_filter = filter.clone();
+        stale_filter.insert("lastFailureAt", doc! { "$lt": DateTime::from_millis(now - lockout_minutes * 60 * 1000) });
+        let reset = doc! {
+            "$set": {
+                "failures": 0_i64,
+                "locked": false
+            }
+        };
+        if let Err(err) = db.update_one(stale_filter, reset, None).await {
+            return Err(HttpResponse {
+                status: 500,
+                message: format!("Error resetting login throttle: {:?}", err),
+                data: None
+            });
+        }
+
+        let update = doc! {
+            "$inc": {
+                "failures": 1_i64
+            },
+            "$set": {
+                "lastFailureAt": DateTime::from_millis(now)
+            },
+            "$setOnInsert": {
+                "_id": Uuid::new(),
+                "locked": false,
+                "blockedUntil": null
+            }
+        };
+        let options = FindOneAndUpdateOptions::builder().upsert(true).return_document(ReturnDocument::After).build();
+        let mut result = db.find_one_and_update(filter.clone(), update.clone(), options.clone()).await;
+        // A concurrent first failure can win the upsert, the unique index then rejects this one and the retry counts on its document
+        if matches!(&result, Err(err) if is_duplicate_key(err)) {
+            result = db.find_one_and_update(filter, update, options).await;
+        }
+        let mut throttle = match result {
+            Ok(Some(throttle)) => throttle,
+            Ok(None) => return Err(HttpResponse {
+                status: 500,
+                message: "Error saving login throttle".to_string(),
+                data: None
+            }),
+            Err(err) => return Err(HttpResponse {
+                status: 500,
+                message: format!("Error saving login throttle: {:?}", err),
+                data: None
+            })
+        };
+
+        let locks = throttle.failures >= max_failures;
+        let blocked_until = match locks {
+            true => DateTime::from_millis(now + lockout_minutes * 60 * 1000),
+            false => DateTime::from_millis(now + (1_i64 << (throttle.failures - 1).min(16)).min(Self::MAX_BACKOFF_SECONDS) * 1000)
+        };
+
+        // Only the failure that flips `locked` reports the lockout, and a parallel failure never shortens the block
+        let mut newly_locked = false;
+        if locks {
+            let lock_filter = doc! {
+                "_id": throttle.id,
+                "locked": false
+            };
+            match db.update_one(lock_filter, doc! { "$set": { "locked": true } }, None).await {
+                Ok(result) => newly_locked = result.modified_count == 1,
+                Err(err) => return Err(HttpResponse {
+                    status: 500,
+                    message: format!("Error locking login throttle: {:?}", err),
+                    data: None
+                })
+            }
+        }
+        if let Err(err) = db.update_one(doc! { "_id": throttle.id }, doc! { "$max": { "blockedUntil": blocked_until } }, None).await {
+            return Err(HttpResponse {
+                status: 500,
+                message: format!("Error saving login throttle: {:?}", err),
+                data: None
+            });
+        }
+
+        throttle.locked = throttle.locked || locks;
+        throttle.blocked_until = Some(throttle.blocked_until.map_or(blocked_until, |until| until.max(blocked_until)));
+        Ok((throttle, newly_locked))
+    }
+
+    // Returns a fresh counter if the subject has no failures on record
+    #[allow(unused)]
+    pub async fn get(kind: LoginThrottleKind, subject: &str, connection: &Connection<ShelfWatcherDatabase>) -> Result<Self, HttpResponse<()>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "kind": to_bson(&kind).unwrap(),
+            "subject": subject.to_lowercase()
+        };
+        match db.find_one(filter, None).await {
+            Ok(Some(throttle)) => Ok(throttle),
+            Ok(None) => Ok(Self::new(kind, subject)),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error fetching login throttle: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn clear(kind: LoginThrottleKind, subject: &str, connection: &Connection<ShelfWatcherDatabase>) -> Result<(), HttpResponse<()>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "kind": to_bson(&kind).unwrap(),
+            "subject": subject.to_lowercase()
+        };
+        match db.delete_one(filter, None).await {
+            Ok(_) => Ok(()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error clearing login throttle: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    fn get_collection(connection: &Connection<ShelfWatcherDatabase>) -> Collection<Self> {
+        let db = get_main_db(connection);
+        db.collection(Self::COLLECTION_NAME)
+    }
+}
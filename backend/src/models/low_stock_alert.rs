@@ -0,0 +1,238 @@
+use anyhow::Result;
+use mongodb::bson::{doc, DateTime, Uuid};
+use rocket_db_pools::mongodb::{options::{FindOneAndUpdateOptions, ReturnDocument}, Client, Collection};
+use rocket::{futures::StreamExt, serde::{Deserialize, Serialize}};
+use crate::db::get_main_db;
+
+use super::{http_response::HttpResponse, reorder_point::ReorderPoint, stock_movement::StockMovement, transfer::Transfer};
+
+// Raised while a product's stock in a location is below its reorder point. An alert stays open,
+// with its figures kept current, until the stock recovers, a later shortage raises a new alert.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct LowStockAlert {
+    #[serde(rename = "_id")]
+    pub id: Uuid,
+    #[serde(rename = "tenantId")]
+    pub tenant_id: Uuid,
+    #[serde(rename = "productId")]
+    pub product_id: Uuid,
+    #[serde(rename = "locationId")]
+    pub location_id: Uuid,
+    #[serde(rename = "onHand")]
+    pub on_hand: f64,
+    // Quantity of shipped transfers on their way to the location
+    pub inbound: f64,
+    #[serde(rename = "reorderPoint")]
+    pub reorder_point: f64,
+    // Below the minimum level
+    pub critical: bool,
+    // Fills the stock up to the maximum level
+    #[serde(rename = "suggestedQuantity")]
+    pub suggested_quantity: f64,
+    pub acknowledged: bool,
+    #[serde(rename = "acknowledgedBy")]
+    pub acknowledged_by: Option<Uuid>,
+    #[serde(rename = "acknowledgedAt")]
+    pub acknowledged_at: Option<String>,
+    pub resolved: bool,
+    #[serde(rename = "resolvedAt")]
+    pub resolved_at: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+}
+
+impl LowStockAlert {
+    pub const COLLECTION_NAME: &'static str = "low_stock_alerts";
+
+    // Compares stock on hand plus inbound transfers with every reorder point of the tenant, raising,
+    // updating and resolving alerts. Returns the open alerts.
+    pub async fn evaluate(tenant_id: Uuid, client: &Client) -> Result<Vec<Self>, HttpResponse<Vec<Self>>> {
+        let reorder_points = ReorderPoint::get_all_from_tenant(tenant_id, None, None, client).await.map_err(|err| HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })?;
+
+        let levels = StockMovement::on_hand(tenant_id, None, None, None, client).await.map_err(|err| HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })?;
+
+        let in_transit = Transfer::get_in_transit(tenant_id, None, None, client).await.map_err(|err| HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })?;
+
+        let mut alerts = Self::get_open(tenant_id, client).await?;
+        let mut open = Vec::new();
+
+        for reorder_point in &reorder_points {
+            let on_hand: f64 = levels
+                .iter()
+                .filter(|level| level.product_id == reorder_point.product_id && level.location_id == reorder_point.location_id)
+                .map(|level| level.quantity)
+                .sum();
+            let inbound: f64 = in_transit
+                .iter()
+                .filter(|line| line.product_id == reorder_point.product_id && line.destination_location_id == reorder_point.location_id)
+                .map(|line| line.quantity)
+                .sum();
+
+            if on_hand + inbound >= reorder_point.reorder_point {
+                continue;
+            }
+            alerts.retain(|alert| alert.product_id != reorder_point.product_id || alert.location_id != reorder_point.location_id);
+
+            let critical = on_hand + inbound < reorder_point.min_level;
+            let suggested_quantity = (reorder_point.max_level - on_hand - inbound).max(0.0);
+            open.push(Self::raise(reorder_point, on_hand, inbound, critical, suggested_quantity, client).await?);
+        }
+
+        // Whatever is left recovered, or lost its reorder point
+        for alert in alerts {
+            alert.resolve(client).await?;
+        }
+
+        Ok(open)
+    }
+
+    // Updates the figures of the open alert of the reorder point, or raises a new one. Only the computed fields are written,
+    // so acknowledgements made in the meantime are kept.
+    async fn raise(reorder_point: &ReorderPoint, on_hand: f64, inbound: f64, critical: bool, suggested_quantity: f64, client: &Client) -> Result<Self, HttpResponse<Vec<Self>>> {
+        let db = Self::get_collection(client);
+
+        let filter = doc! {
+            "tenantId": reorder_point.tenant_id,
+            "productId": reorder_point.product_id,
+            "locationId": reorder_point.location_id,
+            "resolved": false
+        };
+        let update = doc! {
+            "$set": {
+                "onHand": on_hand,
+                "inbound": inbound,
+                "reorderPoint": reorder_point.reorder_point,
+                "critical": critical,
+                "suggestedQuantity": suggested_quantity
+            },
+            "$setOnInsert": {
+                "_id": Uuid::new(),
+                "acknowledged": false,
+                "acknowledgedBy": null,
+                "acknowledgedAt": null,
+                "resolvedAt": null,
+                "createdAt": DateTime::now().to_string()
+            }
+        };
+        let options = FindOneAndUpdateOptions::builder().upsert(true).return_document(ReturnDocument::After).build();
+        match db.find_one_and_update(filter, update, options).await {
+            Ok(Some(alert)) => Ok(alert),
+            Ok(None) => Err(HttpResponse {
+                status: 500,
+                message: "Error saving low stock alert".to_string(),
+                data: None
+            }),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error saving low stock alert: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    async fn resolve<T>(&self, client: &Client) -> Result<(), HttpResponse<T>> {
+        let db = Self::get_collection(client);
+
+        let filter = doc! {
+            "_id": self.id,
+            "resolved": false
+        };
+        let update = doc! {
+            "$set": {
+                "resolved": true,
+                "resolvedAt": DateTime::now().to_string()
+            }
+        };
+        match db.update_one(filter, update, None).await {
+            Ok(_) => Ok(()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error resolving low stock alert: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn get_from_tenant(id: Uuid, tenant_id: Uuid, client: &Client) -> Result<Self, HttpResponse<Self>> {
+        let db = Self::get_collection(client);
+
+        let filter = doc! {
+            "_id": id,
+            "tenantId": tenant_id
+        };
+        match db.find_one(filter, None).await.unwrap() {
+            Some(alert) => Ok(alert),
+            None => Err(HttpResponse {
+                status: 404,
+                message: "Alert not found".to_string(),
+                data: None
+            })
+        }
+    }
+
+    pub async fn get_open(tenant_id: Uuid, client: &Client) -> Result<Vec<Self>, HttpResponse<Vec<Self>>> {
+        let db = Self::get_collection(client);
+
+        let filter = doc! {
+            "tenantId": tenant_id,
+            "resolved": false
+        };
+        match db.find(filter, None).await {
+            Ok(cursor) => {
+                let alerts = cursor.map(|doc| doc.unwrap()).collect::<Vec<Self>>().await;
+                Ok(alerts)
+            },
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error fetching low stock alerts: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    // Only sets the acknowledgement, the figures may have been updated by an evaluation in the meantime.
+    // Returns the alert as it is now, or None if someone else acknowledged it first.
+    pub async fn acknowledge(&self, author_id: Uuid, client: &Client) -> Result<Option<Self>, HttpResponse<Self>> {
+        let db = Self::get_collection(client);
+
+        let filter = doc! {
+            "_id": self.id,
+            "acknowledged": false
+        };
+        let update = doc! {
+            "$set": {
+                "acknowledged": true,
+                "acknowledgedBy": author_id,
+                "acknowledgedAt": DateTime::now().to_string()
+            }
+        };
+        let options = FindOneAndUpdateOptions::builder().return_document(ReturnDocument::After).build();
+        match db.find_one_and_update(filter, update, options).await {
+            Ok(alert) => Ok(alert),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error acknowledging low stock alert: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    fn get_collection(client: &Client) -> Collection<Self> {
+        let db = get_main_db(client);
+        db.collection(Self::COLLECTION_NAME)
+    }
+}
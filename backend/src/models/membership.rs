@@ -0,0 +1,85 @@
+use mongodb::bson::{DateTime, Uuid};
+use rocket::serde::{Deserialize, Serialize};
+
+use super::http_response::HttpResponse;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Membership {
+    #[serde(rename = "tenantId")]
+    pub tenant_id: Uuid,
+    pub role: TenantRole,
+    #[serde(rename = "joinedAt")]
+    pub joined_at: String,
+}
+
+impl Membership {
+    pub fn new(tenant_id: Uuid, role: TenantRole) -> Self {
+        Self {
+            tenant_id,
+            role,
+            joined_at: DateTime::now().to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub enum TenantRole {
+    Owner,
+    Manager,
+    Clerk,
+    Viewer
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+#[allow(unused)]
+pub enum TenantPermission {
+    ViewTenant,
+    UpdateTenant,
+    DeleteTenant,
+    ManageMembers,
+    ManageApiKeys,
+    ManageLocations,
+    ManageCatalog,
+    MoveStock,
+    // Seeing expected quantities of blind stocktakes and approving their results
+    ApproveStocktakes
+}
+
+#[allow(unused)]
+impl TenantRole {
+    pub fn from_string<T>(role: &str) -> Result<Self, HttpResponse<T>> {
+        match role.to_uppercase().as_str() {
+            "OWNER" => Ok(TenantRole::Owner),
+            "MANAGER" => Ok(TenantRole::Manager),
+            "CLERK" => Ok(TenantRole::Clerk),
+            "VIEWER" => Ok(TenantRole::Viewer),
+            _ => Err(HttpResponse { status: 400, message: "Invalid tenant role provided".to_string(), data: None })
+        }
+    }
+
+    // Owners manage everyone below them, managers only clerks and viewers. Nobody manages an owner.
+    pub fn can_manage(&self, other: TenantRole) -> bool {
+        match self {
+            TenantRole::Owner => other != TenantRole::Owner,
+            TenantRole::Manager => matches!(other, TenantRole::Clerk | TenantRole::Viewer),
+            _ => false
+        }
+    }
+
+    pub fn has_permission(&self, permission: TenantPermission) -> bool {
+        match permission {
+            TenantPermission::ViewTenant => true,
+            TenantPermission::MoveStock => matches!(self, TenantRole::Owner | TenantRole::Manager | TenantRole::Clerk),
+            TenantPermission::UpdateTenant
+            | TenantPermission::ManageMembers
+            | TenantPermission::ManageApiKeys
+            | TenantPermission::ManageLocations
+            | TenantPermission::ManageCatalog
+            | TenantPermission::ApproveStocktakes => matches!(self, TenantRole::Owner | TenantRole::Manager),
+            TenantPermission::DeleteTenant => matches!(self, TenantRole::Owner)
+        }
+    }
+}
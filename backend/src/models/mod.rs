@@ -2,4 +2,25 @@ pub mod http_response;
 pub mod user;
 pub mod audit_log;
 pub mod tenant;
-pub mod location;
\ No newline at end of file
+pub mod location;
+pub mod session;
+pub mod membership;
+pub mod invitation;
+pub mod user_token;
+pub mod api_key;
+pub mod login_throttle;
+pub mod product_group;
+pub mod product;
+pub mod product_batch;
+pub mod item;
+pub mod stock_movement;
+pub mod stock_snapshot;
+pub mod stock_lock;
+pub mod transfer;
+pub mod stocktake;
+pub mod reorder_point;
+pub mod low_stock_alert;
+pub mod storage_area;
+pub mod tenant_plan;
+pub mod tenant_settings;
+pub mod tenant_archive;
\ No newline at end of file
@@ -0,0 +1,319 @@
+use anyhow::Result;
+use mongodb::bson::{doc, to_bson, DateTime, Uuid};
+use rocket_db_pools::{mongodb::{Client, Collection}, Connection};
+use rocket::{futures::StreamExt, serde::{Deserialize, Serialize}};
+use crate::db::{count_documents, create_unique_index, get_main_db, is_duplicate_key, ShelfWatcherDatabase};
+
+use super::{http_response::HttpResponse, item::Item, product_batch::ProductBatch, reorder_point::ReorderPoint, stock_movement::StockMovement, stocktake::{Stocktake, StocktakeStatus}, transfer::{Transfer, TransferStatus}};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub enum UnitOfMeasure {
+    Piece,
+    Pack,
+    Box,
+    Pallet,
+    Gram,
+    Kilogram,
+    Milliliter,
+    Liter,
+    Centimeter,
+    Meter
+}
+
+impl UnitOfMeasure {
+    pub fn from_string<T>(unit: &str) -> Result<Self, HttpResponse<T>> {
+        match unit.to_uppercase().as_str() {
+            "PIECE" => Ok(UnitOfMeasure::Piece),
+            "PACK" => Ok(UnitOfMeasure::Pack),
+            "BOX" => Ok(UnitOfMeasure::Box),
+            "PALLET" => Ok(UnitOfMeasure::Pallet),
+            "GRAM" => Ok(UnitOfMeasure::Gram),
+            "KILOGRAM" => Ok(UnitOfMeasure::Kilogram),
+            "MILLILITER" => Ok(UnitOfMeasure::Milliliter),
+            "LITER" => Ok(UnitOfMeasure::Liter),
+            "CENTIMETER" => Ok(UnitOfMeasure::Centimeter),
+            "METER" => Ok(UnitOfMeasure::Meter),
+            _ => Err(HttpResponse { status: 400, message: "Invalid unit of measure provided".to_string(), data: None })
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Product {
+    #[serde(rename = "_id")]
+    pub id: Uuid,
+    #[serde(rename = "tenantId")]
+    pub tenant_id: Uuid,
+    pub name: String,
+    // Unique per tenant
+    pub sku: String,
+    // GTIN-8/12/13/14 codes, each resolves to exactly one product of the tenant
+    pub barcodes: Vec<String>,
+    pub unit: UnitOfMeasure,
+    #[serde(rename = "productGroupId")]
+    pub product_group_id: Option<Uuid>,
+    #[serde(rename = "imageUrl")]
+    pub image_url: Option<String>,
+    pub active: bool,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+}
+
+impl Product {
+    pub const COLLECTION_NAME: &'static str = "products";
+
+    pub fn new(tenant_id: Uuid, name: String, sku: String, barcodes: Vec<String>, unit: UnitOfMeasure, product_group_id: Option<Uuid>, image_url: Option<String>) -> Self {
+        Self {
+            id: Uuid::new(),
+            tenant_id,
+            name,
+            sku,
+            barcodes,
+            unit,
+            product_group_id,
+            image_url,
+            active: true,
+            created_at: DateTime::now().to_string(),
+        }
+    }
+
+    // Accepts GTIN-8, GTIN-12 (UPC-A), GTIN-13 (EAN-13) and GTIN-14 codes with a valid check digit
+    pub fn is_valid_barcode(code: &str) -> bool {
+        if !matches!(code.len(), 8 | 12 | 13 | 14) || !code.bytes().all(|byte| byte.is_ascii_digit()) {
+            return false;
+        }
+
+        let digits: Vec<u32> = code.bytes().map(|byte| (byte - b'0') as u32).collect();
+        let (check_digit, payload) = digits.split_last().unwrap();
+
+        // Weights alternate 3, 1, 3, ... starting from the digit next to the check digit
+        let sum: u32 = payload.iter().rev().enumerate().map(|(i, digit)| if i % 2 == 0 { digit * 3 } else { *digit }).sum();
+
+        (10 - sum % 10) % 10 == *check_digit
+    }
+
+    // Trims, validates and deduplicates the barcodes of a create or update request
+    pub fn normalize_barcodes<T>(barcodes: Vec<String>) -> Result<Vec<String>, HttpResponse<T>> {
+        let mut normalized: Vec<String> = Vec::new();
+
+        for barcode in barcodes {
+            let barcode = barcode.trim().to_string();
+            if !Self::is_valid_barcode(&barcode) {
+                return Err(HttpResponse {
+                    status: 400,
+                    message: format!("Invalid GTIN/EAN barcode: {}", barcode),
+                    data: None
+                });
+            }
+            if !normalized.contains(&barcode) {
+                normalized.push(barcode);
+            }
+        }
+
+        Ok(normalized)
+    }
+
+    // Backs `check_unique` against concurrent writes. Products without barcodes are left out of the barcode index.
+    pub async fn create_indexes(client: &Client) -> Result<(), HttpResponse<()>> {
+        create_unique_index(Self::COLLECTION_NAME, doc! { "tenantId": 1, "sku": 1 }, None, client).await?;
+        create_unique_index(Self::COLLECTION_NAME, doc! { "tenantId": 1, "barcodes": 1 }, Some(doc! { "barcodes": { "$type": "string" } }), client).await
+    }
+
+    // Checks that neither the SKU nor any of the barcodes is used by another product of the tenant
+    #[allow(unused)]
+    pub async fn check_unique(&self, connection: &Connection<ShelfWatcherDatabase>) -> Result<(), HttpResponse<Self>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "tenantId": self.tenant_id,
+            "_id": { "$ne": self.id },
+            "$or": [
+                { "sku": &self.sku },
+                { "barcodes": { "$in": &self.barcodes } }
+            ]
+        };
+        match db.find_one(filter, None).await {
+            Ok(Some(other)) if other.sku == self.sku => Err(HttpResponse {
+                status: 400,
+                message: format!("SKU {} is already used by another product", self.sku),
+                data: None
+            }),
+            Ok(Some(other)) => Err(HttpResponse {
+                status: 400,
+                message: format!("Barcode is already used by product {}", other.sku),
+                data: None
+            }),
+            Ok(None) => Ok(()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error checking product uniqueness: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn get_from_tenant(id: Uuid, tenant_id: Uuid, connection: &Connection<ShelfWatcherDatabase>) -> Result<Self, HttpResponse<Self>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "_id": id,
+            "tenantId": tenant_id
+        };
+        match db.find_one(filter, None).await.unwrap() {
+            Some(product) => Ok(product),
+            None => Err(HttpResponse {
+                status: 404,
+                message: "Product not found".to_string(),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn get_by_barcode(code: &str, tenant_id: Uuid, connection: &Connection<ShelfWatcherDatabase>) -> Result<Self, HttpResponse<Self>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "tenantId": tenant_id,
+            "barcodes": code
+        };
+        match db.find_one(filter, None).await.unwrap() {
+            Some(product) => Ok(product),
+            None => Err(HttpResponse {
+                status: 404,
+                message: "No product with this barcode".to_string(),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn get_all_from_tenant(tenant_id: Uuid, product_group_id: Option<Uuid>, connection: &Connection<ShelfWatcherDatabase>) -> Result<Vec<Self>, HttpResponse<Vec<Self>>> {
+        let db = Self::get_collection(connection);
+
+        let mut filter = doc! {
+            "tenantId": tenant_id
+        };
+        if let Some(product_group_id) = product_group_id {
+            filter.insert("productGroupId", product_group_id);
+        }
+        match db.find(filter, None).await {
+            Ok(cursor) => {
+                let products = cursor.map(|doc| doc.unwrap()).collect::<Vec<Self>>().await;
+                Ok(products)
+            },
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error fetching products from tenant: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn exists_in_group(product_group_id: Uuid, connection: &Connection<ShelfWatcherDatabase>) -> Result<bool, HttpResponse<()>> {
+        let db = Self::get_collection(connection);
+
+        match db.count_documents(doc! { "productGroupId": product_group_id }, None).await {
+            Ok(count) => Ok(count > 0),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error counting products in product group: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn insert(&self, connection: &Connection<ShelfWatcherDatabase>) -> Result<Self, HttpResponse<Self>> {
+        let db = Self::get_collection(connection);
+
+        match db.insert_one(self.clone(), None).await {
+            Ok(_) => Ok(self.clone()),
+            Err(err) if is_duplicate_key(&err) => Err(Self::duplicate()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error inserting product: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn update(&self, connection: &Connection<ShelfWatcherDatabase>) -> Result<Self, HttpResponse<Self>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "_id": self.id
+        };
+        match db.replace_one(filter, self.clone(), None).await {
+            Ok(_) => Ok(self.clone()),
+            Err(err) if is_duplicate_key(&err) => Err(Self::duplicate()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error updating product: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    // What still refers to the product and keeps it from being deleted, None once nothing does
+    pub async fn get_delete_blocker(&self, client: &Client) -> Result<Option<&'static str>, HttpResponse<()>> {
+        let references = [
+            (ProductBatch::COLLECTION_NAME, doc! { "productId": self.id }, "batches"),
+            (Item::COLLECTION_NAME, doc! { "productId": self.id }, "items"),
+            (ReorderPoint::COLLECTION_NAME, doc! { "productId": self.id }, "reorder points"),
+            (Transfer::COLLECTION_NAME, doc! {
+                "lines.productId": self.id,
+                "status": { "$in": [to_bson(&TransferStatus::Draft).unwrap(), to_bson(&TransferStatus::Shipped).unwrap()] }
+            }, "open transfers"),
+            (Stocktake::COLLECTION_NAME, doc! { "counts.productId": self.id, "status": to_bson(&StocktakeStatus::Open).unwrap() }, "open stocktakes")
+        ];
+        for (collection, filter, name) in references {
+            if count_documents(collection, filter, client).await? > 0 {
+                return Ok(Some(name));
+            }
+        }
+
+        if !StockMovement::on_hand(self.tenant_id, Some(self.id), None, None, client).await?.is_empty() {
+            return Ok(Some("stock on hand"));
+        }
+
+        Ok(None)
+    }
+
+    #[allow(unused)]
+    pub async fn delete(&self, connection: &Connection<ShelfWatcherDatabase>) -> Result<Self, HttpResponse<()>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "_id": self.id
+        };
+        match db.delete_one(filter, None).await {
+            Ok(_) => Ok(self.clone()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error deleting product: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    // A concurrent write took the SKU or a barcode after `check_unique` passed
+    fn duplicate() -> HttpResponse<Self> {
+        HttpResponse {
+            status: 400,
+            message: "SKU or barcode is already used by another product".to_string(),
+            data: None
+        }
+    }
+
+    fn get_collection(connection: &Connection<ShelfWatcherDatabase>) -> Collection<Self> {
+        let db = get_main_db(connection);
+        db.collection(Self::COLLECTION_NAME)
+    }
+}
@@ -0,0 +1,348 @@
+use anyhow::Result;
+use mongodb::bson::{doc, DateTime, Document, Uuid};
+use rocket_db_pools::{mongodb::Collection, Connection};
+use rocket::{futures::StreamExt, serde::{Deserialize, Serialize}};
+use crate::db::{get_main_db, ShelfWatcherDatabase};
+
+use super::{http_response::HttpResponse, stock_movement::StockMovement, tenant_settings::TenantSettings};
+
+// One delivery of a product into a location. Dates are `YYYY-MM-DD` strings, which compare correctly as text.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ProductBatch {
+    #[serde(rename = "_id")]
+    pub id: Uuid,
+    #[serde(rename = "tenantId")]
+    pub tenant_id: Uuid,
+    #[serde(rename = "productId")]
+    pub product_id: Uuid,
+    #[serde(rename = "locationId")]
+    pub location_id: Uuid,
+    #[serde(rename = "lotNumber")]
+    pub lot_number: String,
+    #[serde(rename = "receivedDate")]
+    pub received_date: String,
+    #[serde(rename = "expiryDate")]
+    pub expiry_date: Option<String>,
+    // Quantity received, the stock on hand is kept in the stock ledger
+    pub quantity: f64,
+    #[serde(rename = "unitCost")]
+    pub unit_cost: f64,
+    // The tenant's currency when the batch was received
+    #[serde(default = "TenantSettings::default_currency")]
+    pub currency: String,
+    pub closed: bool,
+    #[serde(rename = "closedAt")]
+    pub closed_at: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+}
+
+// One batch to pick from when issuing stock
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct PickSuggestion {
+    #[serde(rename = "batchId")]
+    pub batch_id: Uuid,
+    #[serde(rename = "lotNumber")]
+    pub lot_number: String,
+    #[serde(rename = "expiryDate")]
+    pub expiry_date: Option<String>,
+    pub available: f64,
+    pub quantity: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct PickList {
+    pub picks: Vec<PickSuggestion>,
+    // What the unexpired batches can't cover
+    pub shortfall: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ExpiringBatch {
+    #[serde(rename = "batchId")]
+    pub batch_id: Uuid,
+    #[serde(rename = "productId")]
+    pub product_id: Uuid,
+    #[serde(rename = "lotNumber")]
+    pub lot_number: String,
+    #[serde(rename = "expiryDate")]
+    pub expiry_date: String,
+    pub quantity: f64,
+    // Value of the quantity on hand at the batch's unit cost
+    pub value: f64,
+    pub currency: String,
+    pub expired: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct LocationExpiries {
+    #[serde(rename = "locationId")]
+    pub location_id: Uuid,
+    pub batches: Vec<ExpiringBatch>,
+}
+
+impl ProductBatch {
+    pub const COLLECTION_NAME: &'static str = "product_batches";
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(tenant_id: Uuid, product_id: Uuid, location_id: Uuid, lot_number: String, received_date: String, expiry_date: Option<String>, quantity: f64, unit_cost: f64, currency: String) -> Self {
+        Self {
+            id: Uuid::new(),
+            tenant_id,
+            product_id,
+            location_id,
+            lot_number,
+            received_date,
+            expiry_date,
+            quantity,
+            unit_cost,
+            currency,
+            closed: false,
+            closed_at: None,
+            created_at: DateTime::now().to_string(),
+        }
+    }
+
+    pub fn date_in_days(days: i64) -> String {
+        let date = DateTime::from_millis(DateTime::now().timestamp_millis() + days * 24 * 60 * 60 * 1000);
+        date.try_to_rfc3339_string().unwrap()[..10].to_string()
+    }
+
+    // A batch can be used until the end of its expiry date, `today` is the date in the tenant's timezone
+    pub fn is_expired(&self, today: &str) -> bool {
+        self.expiry_date.as_ref().is_some_and(|expiry_date| expiry_date.as_str() < today)
+    }
+
+    // Expired batches may still be written off or adjusted, but not issued or transferred
+    pub fn require_not_expired<T>(&self, today: &str) -> Result<(), HttpResponse<T>> {
+        if self.is_expired(today) {
+            return Err(HttpResponse {
+                status: 400,
+                message: format!("Batch {} expired on {} and is blocked from issuing", self.lot_number, self.expiry_date.clone().unwrap_or_default()),
+                data: None
+            });
+        }
+
+        Ok(())
+    }
+
+    // Picks `quantity` from the unexpired open batches of a product in a location, first expired first out.
+    // Batches without expiry date come last, ties go to the oldest receipt.
+    #[allow(unused)]
+    pub async fn suggest_picks(tenant_id: Uuid, product_id: Uuid, location_id: Uuid, quantity: f64, today: &str, connection: &Connection<ShelfWatcherDatabase>) -> Result<PickList, HttpResponse<PickList>> {
+        let levels = match StockMovement::on_hand(tenant_id, Some(product_id), None, Some(location_id), connection).await {
+            Ok(levels) => levels,
+            Err(err) => return Err(HttpResponse {
+                status: err.status,
+                message: err.message,
+                data: None
+            })
+        };
+
+        let batch_ids: Vec<Uuid> = levels.iter().filter(|level| level.quantity > 0.0).filter_map(|level| level.batch_id).collect();
+        let mut batches: Vec<Self> = match Self::find(doc! { "_id": { "$in": &batch_ids }, "closed": false }, connection).await {
+            Ok(batches) => batches.into_iter().filter(|batch| !batch.is_expired(today)).collect(),
+            Err(err) => return Err(HttpResponse {
+                status: err.status,
+                message: err.message,
+                data: None
+            })
+        };
+        batches.sort_by(|a, b| match (&a.expiry_date, &b.expiry_date) {
+            (Some(a_expiry), Some(b_expiry)) => a_expiry.cmp(b_expiry),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal
+        }.then_with(|| a.received_date.cmp(&b.received_date)));
+
+        let mut remaining = quantity;
+        let mut picks = Vec::new();
+        for batch in batches {
+            if remaining <= 0.0 {
+                break;
+            }
+
+            let available: f64 = levels.iter().filter(|level| level.batch_id == Some(batch.id)).map(|level| level.quantity).sum();
+            let pick = available.min(remaining);
+            remaining -= pick;
+
+            picks.push(PickSuggestion {
+                batch_id: batch.id,
+                lot_number: batch.lot_number,
+                expiry_date: batch.expiry_date,
+                available,
+                quantity: pick
+            });
+        }
+
+        Ok(PickList {
+            picks,
+            shortfall: remaining.max(0.0)
+        })
+    }
+
+    // Batches in stock that expire on or before `until`, expired ones included, grouped by location
+    #[allow(unused)]
+    pub async fn get_expiring(tenant_id: Uuid, today: &str, until: &str, connection: &Connection<ShelfWatcherDatabase>) -> Result<Vec<LocationExpiries>, HttpResponse<Vec<LocationExpiries>>> {
+        let filter = doc! {
+            "tenantId": tenant_id,
+            "closed": false,
+            "expiryDate": { "$ne": null, "$lte": until }
+        };
+        let batches = match Self::find(filter, connection).await {
+            Ok(batches) => batches,
+            Err(err) => return Err(HttpResponse {
+                status: err.status,
+                message: err.message,
+                data: None
+            })
+        };
+
+        let levels = match StockMovement::on_hand(tenant_id, None, None, None, connection).await {
+            Ok(levels) => levels,
+            Err(err) => return Err(HttpResponse {
+                status: err.status,
+                message: err.message,
+                data: None
+            })
+        };
+
+        let mut expiries: Vec<LocationExpiries> = Vec::new();
+        for level in levels.iter().filter(|level| level.quantity > 0.0) {
+            let Some(batch) = batches.iter().find(|batch| Some(batch.id) == level.batch_id) else { continue };
+
+            let entry = ExpiringBatch {
+                batch_id: batch.id,
+                product_id: batch.product_id,
+                lot_number: batch.lot_number.clone(),
+                expiry_date: batch.expiry_date.clone().unwrap_or_default(),
+                quantity: level.quantity,
+                value: level.quantity * batch.unit_cost,
+                currency: batch.currency.clone(),
+                expired: batch.is_expired(today)
+            };
+            match expiries.iter_mut().find(|expiry| expiry.location_id == level.location_id) {
+                Some(expiry) => expiry.batches.push(entry),
+                None => expiries.push(LocationExpiries {
+                    location_id: level.location_id,
+                    batches: vec![entry]
+                })
+            }
+        }
+
+        for expiry in expiries.iter_mut() {
+            expiry.batches.sort_by(|a, b| a.expiry_date.cmp(&b.expiry_date));
+        }
+
+        Ok(expiries)
+    }
+
+    // Validates a `YYYY-MM-DD` date coming from a request
+    pub fn parse_date<T>(value: &str, field: &str) -> Result<String, HttpResponse<T>> {
+        let value = value.trim();
+
+        if value.len() != 10 || DateTime::parse_rfc3339_str(format!("{}T00:00:00Z", value)).is_err() {
+            return Err(HttpResponse {
+                status: 400,
+                message: format!("Invalid {}, expected YYYY-MM-DD", field),
+                data: None
+            });
+        }
+
+        Ok(value.to_string())
+    }
+
+    #[allow(unused)]
+    pub async fn get_from_tenant(id: Uuid, tenant_id: Uuid, connection: &Connection<ShelfWatcherDatabase>) -> Result<Self, HttpResponse<Self>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "_id": id,
+            "tenantId": tenant_id
+        };
+        match db.find_one(filter, None).await.unwrap() {
+            Some(batch) => Ok(batch),
+            None => Err(HttpResponse {
+                status: 404,
+                message: "Batch not found".to_string(),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn get_all_from_tenant(tenant_id: Uuid, product_id: Option<Uuid>, location_id: Option<Uuid>, include_closed: bool, connection: &Connection<ShelfWatcherDatabase>) -> Result<Vec<Self>, HttpResponse<Vec<Self>>> {
+        let mut filter = doc! {
+            "tenantId": tenant_id
+        };
+        if let Some(product_id) = product_id {
+            filter.insert("productId", product_id);
+        }
+        if let Some(location_id) = location_id {
+            filter.insert("locationId", location_id);
+        }
+        if !include_closed {
+            filter.insert("closed", false);
+        }
+        Self::find(filter, connection).await
+    }
+
+    #[allow(unused)]
+    pub async fn find(filter: Document, connection: &Connection<ShelfWatcherDatabase>) -> Result<Vec<Self>, HttpResponse<Vec<Self>>> {
+        let db = Self::get_collection(connection);
+
+        match db.find(filter, None).await {
+            Ok(cursor) => {
+                let batches = cursor.map(|doc| doc.unwrap()).collect::<Vec<Self>>().await;
+                Ok(batches)
+            },
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error fetching batches: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn insert(&self, connection: &Connection<ShelfWatcherDatabase>) -> Result<Self, HttpResponse<Self>> {
+        let db = Self::get_collection(connection);
+
+        match db.insert_one(self.clone(), None).await {
+            Ok(_) => Ok(self.clone()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error inserting batch: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn update(&self, connection: &Connection<ShelfWatcherDatabase>) -> Result<Self, HttpResponse<Self>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "_id": self.id
+        };
+        match db.replace_one(filter, self.clone(), None).await {
+            Ok(_) => Ok(self.clone()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error updating batch: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    fn get_collection(connection: &Connection<ShelfWatcherDatabase>) -> Collection<Self> {
+        let db = get_main_db(connection);
+        db.collection(Self::COLLECTION_NAME)
+    }
+}
@@ -0,0 +1,168 @@
+use anyhow::Result;
+use mongodb::bson::{doc, DateTime, Uuid};
+use rocket_db_pools::{mongodb::Collection, Connection};
+use rocket::{futures::StreamExt, serde::{Deserialize, Serialize}};
+use crate::db::{get_main_db, ShelfWatcherDatabase};
+
+use super::http_response::HttpResponse;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ProductGroup {
+    #[serde(rename = "_id")]
+    pub id: Uuid,
+    #[serde(rename = "tenantId")]
+    pub tenant_id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    // Groups without a parent sit at the top level of the tenant's catalog
+    #[serde(rename = "parentId")]
+    pub parent_id: Option<Uuid>,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+}
+
+impl ProductGroup {
+    pub const COLLECTION_NAME: &'static str = "product_groups";
+
+    pub fn new(tenant_id: Uuid, name: String, description: Option<String>, parent_id: Option<Uuid>) -> Self {
+        Self {
+            id: Uuid::new(),
+            tenant_id,
+            name,
+            description,
+            parent_id,
+            created_at: DateTime::now().to_string(),
+        }
+    }
+
+    // Checks that `parent_id` exists in the tenant and that `group_id` is not one of its ancestors
+    #[allow(unused)]
+    pub async fn validate_parent(group_id: Option<Uuid>, parent_id: Uuid, tenant_id: Uuid, connection: &Connection<ShelfWatcherDatabase>) -> Result<(), HttpResponse<Self>> {
+        let mut current = Some(parent_id);
+
+        while let Some(id) = current {
+            if Some(id) == group_id {
+                return Err(HttpResponse {
+                    status: 400,
+                    message: "A product group can't be nested inside itself".to_string(),
+                    data: None
+                });
+            }
+
+            current = match Self::get_from_tenant(id, tenant_id, connection).await {
+                Ok(group) => group.parent_id,
+                Err(_) => return Err(HttpResponse {
+                    status: 400,
+                    message: "Parent product group not found".to_string(),
+                    data: None
+                })
+            };
+        }
+
+        Ok(())
+    }
+
+    #[allow(unused)]
+    pub async fn get_from_tenant(id: Uuid, tenant_id: Uuid, connection: &Connection<ShelfWatcherDatabase>) -> Result<Self, HttpResponse<Self>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "_id": id,
+            "tenantId": tenant_id
+        };
+        match db.find_one(filter, None).await.unwrap() {
+            Some(group) => Ok(group),
+            None => Err(HttpResponse {
+                status: 404,
+                message: "Product group not found".to_string(),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn get_all_from_tenant(tenant_id: Uuid, connection: &Connection<ShelfWatcherDatabase>) -> Result<Vec<Self>, HttpResponse<Vec<Self>>> {
+        let db = Self::get_collection(connection);
+
+        match db.find(doc! { "tenantId": tenant_id }, None).await {
+            Ok(cursor) => {
+                let groups = cursor.map(|doc| doc.unwrap()).collect::<Vec<Self>>().await;
+                Ok(groups)
+            },
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error fetching product groups from tenant: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn has_children(&self, connection: &Connection<ShelfWatcherDatabase>) -> Result<bool, HttpResponse<()>> {
+        let db = Self::get_collection(connection);
+
+        match db.count_documents(doc! { "parentId": self.id }, None).await {
+            Ok(count) => Ok(count > 0),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error counting child product groups: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn insert(&self, connection: &Connection<ShelfWatcherDatabase>) -> Result<Self, HttpResponse<Self>> {
+        let db = Self::get_collection(connection);
+
+        match db.insert_one(self.clone(), None).await {
+            Ok(_) => Ok(self.clone()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error inserting product group: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn update(&self, connection: &Connection<ShelfWatcherDatabase>) -> Result<Self, HttpResponse<Self>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "_id": self.id
+        };
+        match db.replace_one(filter, self.clone(), None).await {
+            Ok(_) => Ok(self.clone()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error updating product group: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn delete(&self, connection: &Connection<ShelfWatcherDatabase>) -> Result<Self, HttpResponse<()>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "_id": self.id
+        };
+        match db.delete_one(filter, None).await {
+            Ok(_) => Ok(self.clone()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error deleting product group: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    fn get_collection(connection: &Connection<ShelfWatcherDatabase>) -> Collection<Self> {
+        let db = get_main_db(connection);
+        db.collection(Self::COLLECTION_NAME)
+    }
+}
@@ -0,0 +1,205 @@
+use anyhow::Result;
+use mongodb::bson::{doc, Bson, DateTime, Document, Uuid};
+use rocket_db_pools::mongodb::{Client, Collection};
+use rocket::{futures::StreamExt, serde::{Deserialize, Serialize}};
+use crate::db::get_main_db;
+
+use super::http_response::HttpResponse;
+
+// Stock levels to keep a product at in one location. Stock on hand plus inbound transfers below
+// `reorder_point` raises a low stock alert, below `min_level` the alert is critical.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReorderPoint {
+    #[serde(rename = "_id")]
+    pub id: Uuid,
+    #[serde(rename = "tenantId")]
+    pub tenant_id: Uuid,
+    #[serde(rename = "productId")]
+    pub product_id: Uuid,
+    #[serde(rename = "locationId")]
+    pub location_id: Uuid,
+    #[serde(rename = "minLevel")]
+    pub min_level: f64,
+    #[serde(rename = "reorderPoint")]
+    pub reorder_point: f64,
+    // Reorder suggestions fill the stock up to this level
+    #[serde(rename = "maxLevel")]
+    pub max_level: f64,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+}
+
+impl ReorderPoint {
+    pub const COLLECTION_NAME: &'static str = "reorder_points";
+
+    pub fn new(tenant_id: Uuid, product_id: Uuid, location_id: Uuid, min_level: f64, reorder_point: f64, max_level: f64) -> Self {
+        Self {
+            id: Uuid::new(),
+            tenant_id,
+            product_id,
+            location_id,
+            min_level,
+            reorder_point,
+            max_level,
+            created_at: DateTime::now().to_string(),
+        }
+    }
+
+    pub fn validate_levels<T>(&self) -> Result<(), HttpResponse<T>> {
+        if self.min_level < 0.0 || !(self.min_level <= self.reorder_point && self.reorder_point <= self.max_level) {
+            return Err(HttpResponse {
+                status: 400,
+                message: "Levels must satisfy 0 <= minLevel <= reorderPoint <= maxLevel".to_string(),
+                data: None
+            });
+        }
+
+        if self.max_level <= 0.0 {
+            return Err(HttpResponse {
+                status: 400,
+                message: "maxLevel must be greater than zero".to_string(),
+                data: None
+            });
+        }
+
+        Ok(())
+    }
+
+    #[allow(unused)]
+    pub async fn get_from_tenant(id: Uuid, tenant_id: Uuid, client: &Client) -> Result<Self, HttpResponse<Self>> {
+        let db = Self::get_collection(client);
+
+        let filter = doc! {
+            "_id": id,
+            "tenantId": tenant_id
+        };
+        match db.find_one(filter, None).await.unwrap() {
+            Some(reorder_point) => Ok(reorder_point),
+            None => Err(HttpResponse {
+                status: 404,
+                message: "Reorder point not found".to_string(),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn exists(tenant_id: Uuid, product_id: Uuid, location_id: Uuid, client: &Client) -> Result<bool, HttpResponse<()>> {
+        let db = Self::get_collection(client);
+
+        let filter = doc! {
+            "tenantId": tenant_id,
+            "productId": product_id,
+            "locationId": location_id
+        };
+        match db.count_documents(filter, None).await {
+            Ok(count) => Ok(count > 0),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error counting reorder points: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn get_all_from_tenant(tenant_id: Uuid, product_id: Option<Uuid>, location_id: Option<Uuid>, client: &Client) -> Result<Vec<Self>, HttpResponse<Vec<Self>>> {
+        let mut filter = doc! {
+            "tenantId": tenant_id
+        };
+        if let Some(product_id) = product_id {
+            filter.insert("productId", product_id);
+        }
+        if let Some(location_id) = location_id {
+            filter.insert("locationId", location_id);
+        }
+        Self::find(filter, client).await
+    }
+
+    #[allow(unused)]
+    pub async fn get_tenant_ids(client: &Client) -> Result<Vec<Uuid>, HttpResponse<()>> {
+        let db = Self::get_collection(client);
+
+        match db.distinct("tenantId", None, None).await {
+            Ok(values) => Ok(values.into_iter().filter_map(|value| match value {
+                Bson::Binary(binary) => binary.to_uuid().ok(),
+                _ => None
+            }).collect()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error fetching tenants with reorder points: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    async fn find(filter: Document, client: &Client) -> Result<Vec<Self>, HttpResponse<Vec<Self>>> {
+        let db = Self::get_collection(client);
+
+        match db.find(filter, None).await {
+            Ok(cursor) => {
+                let reorder_points = cursor.map(|doc| doc.unwrap()).collect::<Vec<Self>>().await;
+                Ok(reorder_points)
+            },
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error fetching reorder points: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn insert(&self, client: &Client) -> Result<Self, HttpResponse<Self>> {
+        let db = Self::get_collection(client);
+
+        match db.insert_one(self.clone(), None).await {
+            Ok(_) => Ok(self.clone()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error inserting reorder point: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn update(&self, client: &Client) -> Result<Self, HttpResponse<Self>> {
+        let db = Self::get_collection(client);
+
+        let filter = doc! {
+            "_id": self.id
+        };
+        match db.replace_one(filter, self.clone(), None).await {
+            Ok(_) => Ok(self.clone()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error updating reorder point: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn delete(&self, client: &Client) -> Result<Self, HttpResponse<()>> {
+        let db = Self::get_collection(client);
+
+        let filter = doc! {
+            "_id": self.id
+        };
+        match db.delete_one(filter, None).await {
+            Ok(_) => Ok(self.clone()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error deleting reorder point: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    fn get_collection(client: &Client) -> Collection<Self> {
+        let db = get_main_db(client);
+        db.collection(Self::COLLECTION_NAME)
+    }
+}
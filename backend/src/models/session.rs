@@ -0,0 +1,121 @@
+use anyhow::Result;
+use mongodb::bson::{doc, DateTime, Uuid};
+use rocket_db_pools::{mongodb::Collection, Connection};
+use rocket::serde::{Deserialize, Serialize};
+use crate::db::{get_main_db, ShelfWatcherDatabase};
+
+use super::http_response::HttpResponse;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Session {
+    #[serde(rename = "_id")]
+    pub id: Uuid,
+    #[serde(rename = "userId")]
+    pub user_id: Uuid,
+    pub revoked: bool,
+    #[serde(rename = "expiresAt")]
+    pub expires_at: DateTime,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+}
+
+impl Session {
+    pub const COLLECTION_NAME: &'static str = "sessions";
+
+    pub fn new(user_id: Uuid, lifetime_hours: i64) -> Self {
+        Self {
+            id: Uuid::new(),
+            user_id,
+            revoked: false,
+            expires_at: DateTime::from_millis(DateTime::now().timestamp_millis() + lifetime_hours * 60 * 60 * 1000),
+            created_at: DateTime::now().to_string(),
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        !self.revoked && self.expires_at > DateTime::now()
+    }
+
+    #[allow(unused)]
+    pub async fn get_by_id(id: Uuid, connection: &Connection<ShelfWatcherDatabase>) -> Result<Self, HttpResponse<Self>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "_id": id
+        };
+        match db.find_one(filter, None).await.unwrap() {
+            Some(session) => Ok(session),
+            None => Err(HttpResponse {
+                status: 404,
+                message: "Session not found".to_string(),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn insert(&self, connection: &Connection<ShelfWatcherDatabase>) -> Result<Self, HttpResponse<Self>> {
+        let db = Self::get_collection(connection);
+
+        match db.insert_one(self.clone(), None).await {
+            Ok(_) => Ok(self.clone()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error inserting session: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn revoke(&self, connection: &Connection<ShelfWatcherDatabase>) -> Result<(), HttpResponse<()>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "_id": self.id
+        };
+        let update = doc! {
+            "$set": {
+                "revoked": true
+            }
+        };
+        match db.update_one(filter, update, None).await {
+            Ok(_) => Ok(()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error revoking session: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn revoke_all_for_user(user_id: Uuid, connection: &Connection<ShelfWatcherDatabase>) -> Result<(), HttpResponse<()>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "userId": user_id,
+            "revoked": false
+        };
+        let update = doc! {
+            "$set": {
+                "revoked": true
+            }
+        };
+        match db.update_many(filter, update, None).await {
+            Ok(_) => Ok(()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error revoking sessions: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    fn get_collection(connection: &Connection<ShelfWatcherDatabase>) -> Collection<Self> {
+        let db = get_main_db(connection);
+        db.collection(Self::COLLECTION_NAME)
+    }
+}
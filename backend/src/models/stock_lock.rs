@@ -0,0 +1,119 @@
+use anyhow::Result;
+use std::time::Duration;
+use mongodb::bson::{doc, DateTime, Uuid};
+use rocket_db_pools::mongodb::{options::UpdateOptions, Client, Collection};
+use rocket::{serde::{Deserialize, Serialize}, tokio};
+use crate::db::{get_main_db, is_duplicate_key};
+
+use super::http_response::HttpResponse;
+
+// Held while stock of a product leaving a location is checked and booked, so concurrent movements can't both pass the check
+// and overdraw it. A lock outlives a crashed holder by at most `TIMEOUT_MILLIS`. A holder that takes longer than that loses
+// the lock to the next movement, so a check and booking slower than the timeout isn't protected.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct StockLock {
+    // Tenant, product and location the lock is for
+    #[serde(rename = "_id")]
+    pub key: String,
+    // Tells the holder's lock apart from one taken over after it expired
+    pub holder: Uuid,
+    #[serde(rename = "expiresAt")]
+    pub expires_at: DateTime,
+}
+
+impl StockLock {
+    pub const COLLECTION_NAME: &'static str = "stock_locks";
+
+    const TIMEOUT_MILLIS: i64 = 10_000;
+    const WAIT_MILLIS: i64 = 5_000;
+    const RETRY_MILLIS: u64 = 25;
+
+    // Waits until the lock of the product at the location is free and takes it
+    pub async fn acquire(tenant_id: Uuid, product_id: Uuid, location_id: Uuid, client: &Client) -> Result<Self, HttpResponse<()>> {
+        let db = Self::get_collection(client);
+
+        let key = format!("{}:{}:{}", tenant_id, product_id, location_id);
+        let holder = Uuid::new();
+        let give_up_at = DateTime::now().timestamp_millis() + Self::WAIT_MILLIS;
+
+        loop {
+            let now = DateTime::now();
+            let expires_at = DateTime::from_millis(now.timestamp_millis() + Self::TIMEOUT_MILLIS);
+
+            // Takes over an expired lock, or creates it if there is none. A held lock makes the upsert collide with it.
+            let filter = doc! {
+                "_id": &key,
+                "expiresAt": { "$lte": now }
+            };
+            let update = doc! {
+                "$set": {
+                    "holder": holder,
+                    "expiresAt": expires_at
+                }
+            };
+            let options = UpdateOptions::builder().upsert(true).build();
+            match db.update_one(filter, update, options).await {
+                Ok(_) => return Ok(Self { key, holder, expires_at }),
+                Err(err) if is_duplicate_key(&err) => (),
+                Err(err) => return Err(HttpResponse {
+                    status: 500,
+                    message: format!("Error locking stock: {:?}", err),
+                    data: None
+                })
+            }
+
+            if DateTime::now().timestamp_millis() >= give_up_at {
+                return Err(HttpResponse {
+                    status: 503,
+                    message: "Stock of the product at this location is being booked, try again".to_string(),
+                    data: None
+                });
+            }
+            tokio::time::sleep(Duration::from_millis(Self::RETRY_MILLIS)).await;
+        }
+    }
+
+    // Locks every product/location pair once, in a fixed order so two callers can't wait on each other
+    pub async fn acquire_all(tenant_id: Uuid, mut keys: Vec<(Uuid, Uuid)>, client: &Client) -> Result<Vec<Self>, HttpResponse<()>> {
+        keys.sort_by_key(|(product_id, location_id)| (product_id.bytes(), location_id.bytes()));
+        keys.dedup();
+
+        let mut locks = Vec::new();
+        for (product_id, location_id) in keys {
+            match Self::acquire(tenant_id, product_id, location_id, client).await {
+                Ok(lock) => locks.push(lock),
+                Err(err) => {
+                    Self::release_all(locks, client).await;
+                    return Err(err);
+                }
+            }
+        }
+
+        Ok(locks)
+    }
+
+    // Failing to release only delays the next movement until the lock expires, so errors are logged
+    pub async fn release(self, client: &Client) {
+        let db = Self::get_collection(client);
+
+        let filter = doc! {
+            "_id": &self.key,
+            "holder": self.holder
+        };
+        if let Err(err) = db.delete_one(filter, None).await {
+            rocket::error!("Error releasing stock lock {}: {:?}", self.key, err);
+        }
+    }
+
+    pub async fn release_all(locks: Vec<Self>, client: &Client) {
+        for lock in locks {
+            lock.release(client).await;
+        }
+    }
+
+    fn get_collection(client: &Client) -> Collection<Self> {
+        let db = get_main_db(client);
+        db.collection(Self::COLLECTION_NAME)
+    }
+}
@@ -0,0 +1,444 @@
+use anyhow::Result;
+use mongodb::bson::{doc, Bson, DateTime, Document, Uuid};
+use rocket_db_pools::mongodb::{options::FindOptions, Client, Collection};
+use rocket::{futures::StreamExt, serde::{Deserialize, Serialize}};
+use crate::db::get_main_db;
+
+use super::{audit_log::{AuditLogAuthor, AuditLogAuthorType}, http_response::HttpResponse, stock_lock::StockLock, stock_snapshot::StockSnapshot};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub enum StockMovementType {
+    Receipt,
+    Issue,
+    Transfer,
+    Adjustment,
+    WriteOff
+}
+
+impl StockMovementType {
+    pub fn from_string<T>(movement_type: &str) -> Result<Self, HttpResponse<T>> {
+        match movement_type.to_uppercase().as_str() {
+            "RECEIPT" => Ok(StockMovementType::Receipt),
+            "ISSUE" => Ok(StockMovementType::Issue),
+            "TRANSFER" => Ok(StockMovementType::Transfer),
+            "ADJUSTMENT" => Ok(StockMovementType::Adjustment),
+            "WRITE_OFF" => Ok(StockMovementType::WriteOff),
+            _ => Err(HttpResponse { status: 400, message: "Invalid stock movement type provided".to_string(), data: None })
+        }
+    }
+}
+
+// Quantity of a product (and batch) at a location, and optionally in a bin of it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct StockLevel {
+    #[serde(rename = "productId")]
+    pub product_id: Uuid,
+    #[serde(rename = "batchId")]
+    pub batch_id: Option<Uuid>,
+    #[serde(rename = "locationId")]
+    pub location_id: Uuid,
+    // Stock that hasn't been put away into a bin yet has no area
+    #[serde(rename = "areaId")]
+    pub area_id: Option<Uuid>,
+    pub quantity: f64,
+}
+
+// Immutable ledger entry. Stock leaves `from_location_id` and enters `to_location_id`.
+// The quantity is negative when stock only leaves a location, positive otherwise.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct StockMovement {
+    #[serde(rename = "_id")]
+    pub id: Uuid,
+    #[serde(rename = "tenantId")]
+    pub tenant_id: Uuid,
+    #[serde(rename = "type")]
+    pub movement_type: StockMovementType,
+    #[serde(rename = "productId")]
+    pub product_id: Uuid,
+    #[serde(rename = "batchId")]
+    pub batch_id: Option<Uuid>,
+    #[serde(rename = "fromLocationId")]
+    pub from_location_id: Option<Uuid>,
+    #[serde(rename = "toLocationId")]
+    pub to_location_id: Option<Uuid>,
+    // Bins inside the locations, a transfer within one location moves stock between bins
+    #[serde(rename = "fromAreaId")]
+    pub from_area_id: Option<Uuid>,
+    #[serde(rename = "toAreaId")]
+    pub to_area_id: Option<Uuid>,
+    pub quantity: f64,
+    #[serde(rename = "reasonCode")]
+    pub reason_code: String,
+    // The transfer, stocktake or batch that caused the movement
+    #[serde(rename = "referenceId")]
+    pub reference_id: Option<Uuid>,
+    #[serde(rename = "authorId")]
+    pub author_id: Uuid,
+    #[serde(rename = "authorType")]
+    pub author_type: AuditLogAuthorType,
+    // Milliseconds since the epoch, orders the ledger and separates it from snapshots
+    pub timestamp: i64,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+}
+
+impl StockMovement {
+    pub const COLLECTION_NAME: &'static str = "stock_movements";
+
+    // Checks that the locations fit the movement type and derives the sign from them. `amount` must be positive.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new<T>(tenant_id: Uuid, movement_type: StockMovementType, product_id: Uuid, batch_id: Option<Uuid>, from_location_id: Option<Uuid>, to_location_id: Option<Uuid>, from_area_id: Option<Uuid>, to_area_id: Option<Uuid>, amount: f64, reason_code: &str, reference_id: Option<Uuid>, author: AuditLogAuthor) -> Result<Self, HttpResponse<T>> {
+        if amount <= 0.0 || !amount.is_finite() {
+            return Err(HttpResponse {
+                status: 400,
+                message: "Quantity must be greater than zero".to_string(),
+                data: None
+            });
+        }
+
+        let reason_code = reason_code.trim().to_uppercase();
+        if reason_code.is_empty() {
+            return Err(HttpResponse {
+                status: 400,
+                message: "A reason code is required".to_string(),
+                data: None
+            });
+        }
+
+        if (from_area_id.is_some() && from_location_id.is_none()) || (to_area_id.is_some() && to_location_id.is_none()) {
+            return Err(HttpResponse {
+                status: 400,
+                message: "A bin requires its location".to_string(),
+                data: None
+            });
+        }
+
+        let locations_valid = match movement_type {
+            StockMovementType::Receipt => from_location_id.is_none() && to_location_id.is_some(),
+            StockMovementType::Issue | StockMovementType::WriteOff => from_location_id.is_some() && to_location_id.is_none(),
+            // Transfers between locations can be booked in one go, or as separate shipment and receipt with the goods in transit between.
+            // Within a location they put stock away into a bin or move it between bins.
+            StockMovementType::Transfer => (from_location_id.is_some() || to_location_id.is_some()) && (from_location_id != to_location_id || from_area_id != to_area_id),
+            StockMovementType::Adjustment => from_location_id.is_some() != to_location_id.is_some()
+        };
+        if !locations_valid {
+            return Err(HttpResponse {
+                status: 400,
+                message: format!("Invalid locations for a {:?} movement", movement_type),
+                data: None
+            });
+        }
+
+        let quantity = match (from_location_id, to_location_id) {
+            (Some(_), None) => -amount,
+            _ => amount
+        };
+
+        let now = DateTime::now();
+        Ok(Self {
+            id: Uuid::new(),
+            tenant_id,
+            movement_type,
+            product_id,
+            batch_id,
+            from_location_id,
+            to_location_id,
+            from_area_id,
+            to_area_id,
+            quantity,
+            reason_code,
+            reference_id,
+            author_id: author.id(),
+            author_type: author.author_type(),
+            timestamp: now.timestamp_millis(),
+            created_at: now.to_string(),
+        })
+    }
+
+    // Adds the effect of the movement to `levels`. Stock leaving a location without a source bin is taken from the unbinned
+    // stock first and then from the bins in order of their ids, so every replay of the ledger ends up with the same bins.
+    pub fn apply(&self, levels: &mut Vec<StockLevel>) {
+        let amount = self.quantity.abs();
+
+        if let Some(location_id) = self.from_location_id {
+            let draws = if self.draws_from_bins() { self.draw(levels, location_id, amount) } else { vec![(self.from_area_id, amount)] };
+            for (area_id, taken) in draws {
+                self.add_to(levels, location_id, area_id, -taken);
+            }
+        }
+        if let Some(location_id) = self.to_location_id {
+            self.add_to(levels, location_id, self.to_area_id, amount);
+        }
+    }
+
+    // Putting stock away within a location moves only unbinned stock, everything else leaving without a bin may come out of any bin
+    fn draws_from_bins(&self) -> bool {
+        self.from_area_id.is_none() && self.from_location_id != self.to_location_id
+    }
+
+    // Splits `amount` over the unbinned stock and the bins of the location. What they can't cover stays on the unbinned stock.
+    fn draw(&self, levels: &[StockLevel], location_id: Uuid, amount: f64) -> Vec<(Option<Uuid>, f64)> {
+        let mut sources: Vec<&StockLevel> = levels
+            .iter()
+            .filter(|level| level.product_id == self.product_id && level.batch_id == self.batch_id && level.location_id == location_id && level.quantity > 0.0)
+            .collect();
+        sources.sort_by_key(|level| level.area_id.map(|area_id| area_id.bytes()));
+
+        let mut remaining = amount;
+        let mut draws = Vec::new();
+        for level in sources {
+            if remaining <= 0.0 {
+                break;
+            }
+            let taken = level.quantity.min(remaining);
+            draws.push((level.area_id, taken));
+            remaining -= taken;
+        }
+        if remaining > 0.0 {
+            draws.push((None, remaining));
+        }
+
+        draws
+    }
+
+    fn add_to(&self, levels: &mut Vec<StockLevel>, location_id: Uuid, area_id: Option<Uuid>, delta: f64) {
+        match levels.iter_mut().find(|level| level.product_id == self.product_id && level.batch_id == self.batch_id && level.location_id == location_id && level.area_id == area_id) {
+            Some(level) => level.quantity += delta,
+            None => levels.push(StockLevel {
+                product_id: self.product_id,
+                batch_id: self.batch_id,
+                location_id,
+                area_id,
+                quantity: delta
+            })
+        }
+    }
+
+    // Fails if the source doesn't hold enough stock for the movement. That is the source bin if there is one, the unbinned stock
+    // when putting stock away within a location, and the whole location otherwise.
+    pub async fn check_available<T>(&self, client: &Client) -> Result<(), HttpResponse<T>> {
+        let Some(from_location_id) = self.from_location_id else { return Ok(()) };
+
+        let available = Self::by_area(self.tenant_id, Some(self.product_id), self.batch_id, Some(from_location_id), client).await.map(|levels| {
+            levels
+                .iter()
+                .filter(|level| level.batch_id == self.batch_id && (self.draws_from_bins() || level.area_id == self.from_area_id))
+                .map(|level| level.quantity)
+                .sum::<f64>()
+        });
+        let available = match available {
+            Ok(quantity) => quantity,
+            Err(err) => return Err(HttpResponse {
+                status: err.status,
+                message: err.message,
+                data: None
+            })
+        };
+
+        if available < self.quantity.abs() {
+            let source = match (self.from_area_id, self.draws_from_bins()) {
+                (Some(_), _) => "bin",
+                (None, true) => "location",
+                (None, false) => "location outside of its bins"
+            };
+            return Err(HttpResponse {
+                status: 400,
+                message: format!("Not enough stock at the source {} ({} available)", source, available),
+                data: None
+            });
+        }
+
+        Ok(())
+    }
+
+    // Locks the product at the source location, hold it from `check_available` until the movement is inserted.
+    // None for movements that don't take stock out of a location.
+    pub async fn lock_source<T>(&self, client: &Client) -> Result<Option<StockLock>, HttpResponse<T>> {
+        let Some(from_location_id) = self.from_location_id else { return Ok(None) };
+
+        match StockLock::acquire(self.tenant_id, self.product_id, from_location_id, client).await {
+            Ok(lock) => Ok(Some(lock)),
+            Err(err) => Err(HttpResponse {
+                status: err.status,
+                message: err.message,
+                data: None
+            })
+        }
+    }
+
+    // Current stock per location, summed over its bins
+    pub async fn on_hand(tenant_id: Uuid, product_id: Option<Uuid>, batch_id: Option<Uuid>, location_id: Option<Uuid>, client: &Client) -> Result<Vec<StockLevel>, HttpResponse<()>> {
+        let mut levels: Vec<StockLevel> = Vec::new();
+
+        for level in Self::by_area(tenant_id, product_id, batch_id, location_id, client).await? {
+            match levels.iter_mut().find(|existing| existing.product_id == level.product_id && existing.batch_id == level.batch_id && existing.location_id == level.location_id) {
+                Some(existing) => existing.quantity += level.quantity,
+                None => levels.push(StockLevel { area_id: None, ..level })
+            }
+        }
+        levels.retain(|level| level.quantity != 0.0);
+
+        Ok(levels)
+    }
+
+    // Current stock per bin, taken from the latest snapshot plus all movements after it. Zero levels are left out.
+    pub async fn by_area(tenant_id: Uuid, product_id: Option<Uuid>, batch_id: Option<Uuid>, location_id: Option<Uuid>, client: &Client) -> Result<Vec<StockLevel>, HttpResponse<()>> {
+        let snapshot = StockSnapshot::latest(tenant_id, client).await?;
+        let since = snapshot.as_ref().map(|snapshot| snapshot.as_of).unwrap_or(0);
+
+        let matches = |level_product_id: Uuid, level_batch_id: Option<Uuid>| {
+            product_id.is_none_or(|product_id| product_id == level_product_id) && (batch_id.is_none() || batch_id == level_batch_id)
+        };
+
+        let mut levels: Vec<StockLevel> = snapshot
+            .map(|snapshot| snapshot.lines)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|level| matches(level.product_id, level.batch_id))
+            .collect();
+
+        let mut filter = doc! {
+            "tenantId": tenant_id,
+            "timestamp": { "$gt": since }
+        };
+        if let Some(product_id) = product_id {
+            filter.insert("productId", product_id);
+        }
+        if let Some(batch_id) = batch_id {
+            filter.insert("batchId", batch_id);
+        }
+        for movement in Self::find(filter, client).await? {
+            movement.apply(&mut levels);
+        }
+
+        Ok(levels
+            .into_iter()
+            .filter(|level| level.quantity != 0.0 && location_id.is_none_or(|location_id| location_id == level.location_id))
+            .collect())
+    }
+
+    // Stock of exactly this product/batch combination at a location. Movements without batch are a separate bucket.
+    pub async fn quantity_at(tenant_id: Uuid, product_id: Uuid, batch_id: Option<Uuid>, location_id: Uuid, client: &Client) -> Result<f64, HttpResponse<()>> {
+        let levels = Self::on_hand(tenant_id, Some(product_id), batch_id, Some(location_id), client).await?;
+
+        Ok(levels
+            .iter()
+            .filter(|level| level.batch_id == batch_id)
+            .map(|level| level.quantity)
+            .sum())
+    }
+
+    #[allow(unused)]
+    pub async fn get_all_from_tenant(tenant_id: Uuid, product_id: Option<Uuid>, batch_id: Option<Uuid>, location_id: Option<Uuid>, client: &Client) -> Result<Vec<Self>, HttpResponse<()>> {
+        let mut filter = doc! {
+            "tenantId": tenant_id
+        };
+        if let Some(product_id) = product_id {
+            filter.insert("productId", product_id);
+        }
+        if let Some(batch_id) = batch_id {
+            filter.insert("batchId", batch_id);
+        }
+        if let Some(location_id) = location_id {
+            filter.insert("$or", vec![
+                Bson::Document(doc! { "fromLocationId": location_id }),
+                Bson::Document(doc! { "toLocationId": location_id })
+            ]);
+        }
+        Self::find(filter, client).await
+    }
+
+    // Movements of the tenant in the time range (`after`, `until`]
+    #[allow(unused)]
+    pub async fn get_between(tenant_id: Uuid, after: i64, until: i64, client: &Client) -> Result<Vec<Self>, HttpResponse<()>> {
+        let filter = doc! {
+            "tenantId": tenant_id,
+            "timestamp": { "$gt": after, "$lte": until }
+        };
+        Self::find(filter, client).await
+    }
+
+    #[allow(unused)]
+    pub async fn get_tenant_ids(client: &Client) -> Result<Vec<Uuid>, HttpResponse<()>> {
+        let db = Self::get_collection(client);
+
+        match db.distinct("tenantId", None, None).await {
+            Ok(values) => Ok(values.into_iter().filter_map(|value| match value {
+                Bson::Binary(binary) => binary.to_uuid().ok(),
+                _ => None
+            }).collect()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error fetching tenants with stock movements: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    // The ledger is append-only, there is deliberately no update or delete
+    #[allow(unused)]
+    pub async fn insert(&self, client: &Client) -> Result<Self, HttpResponse<()>> {
+        let db = Self::get_collection(client);
+
+        match db.insert_one(self.clone(), None).await {
+            Ok(_) => Ok(self.clone()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error inserting stock movement: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    // Books movements that only make sense together. If the insert fails partway, the ones that made it in are taken out
+    // again, the only time anything is deleted from the ledger.
+    pub async fn insert_all(movements: &[Self], client: &Client) -> Result<(), HttpResponse<()>> {
+        if movements.is_empty() {
+            return Ok(());
+        }
+
+        let db = Self::get_collection(client);
+
+        let err = match db.insert_many(movements, None).await {
+            Ok(_) => return Ok(()),
+            Err(err) => err
+        };
+
+        let ids: Vec<Uuid> = movements.iter().map(|movement| movement.id).collect();
+        if let Err(delete_err) = db.delete_many(doc! { "_id": { "$in": ids } }, None).await {
+            rocket::error!("Error removing partly inserted stock movements: {:?}", delete_err);
+        }
+
+        Err(HttpResponse {
+            status: 500,
+            message: format!("Error inserting stock movements: {:?}", err),
+            data: None
+        })
+    }
+
+    async fn find(filter: Document, client: &Client) -> Result<Vec<Self>, HttpResponse<()>> {
+        let db = Self::get_collection(client);
+
+        // Ties are broken by id, bins drawn from depend on the order movements are applied in
+        let options = FindOptions::builder().sort(doc! { "timestamp": 1, "_id": 1 }).build();
+        match db.find(filter, options).await {
+            Ok(cursor) => {
+                let movements = cursor.map(|doc| doc.unwrap()).collect::<Vec<Self>>().await;
+                Ok(movements)
+            },
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error fetching stock movements: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    fn get_collection(client: &Client) -> Collection<Self> {
+        let db = get_main_db(client);
+        db.collection(Self::COLLECTION_NAME)
+    }
+}
@@ -0,0 +1,118 @@
+use anyhow::Result;
+use mongodb::bson::{doc, DateTime, Uuid};
+use rocket_db_pools::mongodb::{options::FindOneOptions, Client, Collection};
+use rocket::serde::{Deserialize, Serialize};
+use crate::db::get_main_db;
+
+use super::{http_response::HttpResponse, stock_movement::{StockLevel, StockMovement}};
+
+// On-hand stock of a tenant folded from the ledger up to `as_of`, so reads don't replay every movement
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct StockSnapshot {
+    #[serde(rename = "_id")]
+    pub id: Uuid,
+    #[serde(rename = "tenantId")]
+    pub tenant_id: Uuid,
+    // Milliseconds since the epoch, every movement with a timestamp up to this is included
+    #[serde(rename = "asOf")]
+    pub as_of: i64,
+    pub lines: Vec<StockLevel>,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+}
+
+impl StockSnapshot {
+    pub const COLLECTION_NAME: &'static str = "stock_snapshots";
+
+    // Movements younger than this are left for the next snapshot, so writes still in flight aren't skipped
+    const SETTLE_MILLIS: i64 = 60_000;
+
+    // Snapshots kept per tenant, older ones are deleted after a new one is taken
+    const KEEP: usize = 3;
+
+    // Folds the movements since the previous snapshot into a new one
+    pub async fn take(tenant_id: Uuid, client: &Client) -> Result<Self, HttpResponse<()>> {
+        let previous = Self::latest(tenant_id, client).await?;
+        let after = previous.as_ref().map(|snapshot| snapshot.as_of).unwrap_or(0);
+        let as_of = DateTime::now().timestamp_millis() - Self::SETTLE_MILLIS;
+
+        let mut lines = previous.map(|snapshot| snapshot.lines).unwrap_or_default();
+        for movement in StockMovement::get_between(tenant_id, after, as_of, client).await? {
+            movement.apply(&mut lines);
+        }
+        lines.retain(|line| line.quantity != 0.0);
+
+        let snapshot = Self {
+            id: Uuid::new(),
+            tenant_id,
+            as_of: as_of.max(after),
+            lines,
+            created_at: DateTime::now().to_string(),
+        };
+        snapshot.insert(client).await?;
+        snapshot.delete_outdated(client).await?;
+
+        Ok(snapshot)
+    }
+
+    pub async fn latest(tenant_id: Uuid, client: &Client) -> Result<Option<Self>, HttpResponse<()>> {
+        let db = Self::get_collection(client);
+
+        let options = FindOneOptions::builder().sort(doc! { "asOf": -1 }).build();
+        match db.find_one(doc! { "tenantId": tenant_id }, options).await {
+            Ok(snapshot) => Ok(snapshot),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error fetching stock snapshot: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    async fn insert(&self, client: &Client) -> Result<(), HttpResponse<()>> {
+        let db = Self::get_collection(client);
+
+        match db.insert_one(self.clone(), None).await {
+            Ok(_) => Ok(()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error inserting stock snapshot: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    async fn delete_outdated(&self, client: &Client) -> Result<(), HttpResponse<()>> {
+        let db = Self::get_collection(client);
+
+        let options = FindOneOptions::builder().sort(doc! { "asOf": -1 }).skip(Self::KEEP as u64 - 1).build();
+        let oldest_kept = match db.find_one(doc! { "tenantId": self.tenant_id }, options).await {
+            Ok(Some(snapshot)) => snapshot,
+            Ok(None) => return Ok(()),
+            Err(err) => return Err(HttpResponse {
+                status: 500,
+                message: format!("Error fetching stock snapshots: {:?}", err),
+                data: None
+            })
+        };
+
+        let filter = doc! {
+            "tenantId": self.tenant_id,
+            "asOf": { "$lt": oldest_kept.as_of }
+        };
+        match db.delete_many(filter, None).await {
+            Ok(_) => Ok(()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error deleting old stock snapshots: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    fn get_collection(client: &Client) -> Collection<Self> {
+        let db = get_main_db(client);
+        db.collection(Self::COLLECTION_NAME)
+    }
+}
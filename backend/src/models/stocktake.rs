@@ -0,0 +1,425 @@
+use anyhow::Result;
+use mongodb::bson::{doc, to_bson, DateTime, Uuid};
+use rocket_db_pools::{mongodb::Collection, Connection};
+use rocket::{futures::StreamExt, serde::{Deserialize, Serialize}};
+use crate::db::{get_main_db, ShelfWatcherDatabase};
+
+use super::{audit_log::{AuditLogAuthor, AuditLogAuthorType}, http_response::HttpResponse, product::Product, product_group::ProductGroup, stock_movement::{StockMovement, StockMovementType}};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub enum StocktakeStatus {
+    Open,
+    Approved,
+    Cancelled
+}
+
+impl StocktakeStatus {
+    pub fn from_string<T>(status: &str) -> Result<Self, HttpResponse<T>> {
+        match status.to_uppercase().as_str() {
+            "OPEN" => Ok(StocktakeStatus::Open),
+            "APPROVED" => Ok(StocktakeStatus::Approved),
+            "CANCELLED" => Ok(StocktakeStatus::Cancelled),
+            _ => Err(HttpResponse { status: 400, message: "Invalid stocktake status provided".to_string(), data: None })
+        }
+    }
+}
+
+// What one counter counted of a product (and batch). Counters split the location between them,
+// so the counts of different counters add up, while a counter's recount replaces their previous count.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct StocktakeCount {
+    #[serde(rename = "productId")]
+    pub product_id: Uuid,
+    #[serde(rename = "batchId")]
+    pub batch_id: Option<Uuid>,
+    pub quantity: f64,
+    #[serde(rename = "counterId")]
+    pub counter_id: Uuid,
+    #[serde(rename = "counterType")]
+    pub counter_type: AuditLogAuthorType,
+    #[serde(rename = "countedAt")]
+    pub counted_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct VarianceLine {
+    #[serde(rename = "productId")]
+    pub product_id: Uuid,
+    #[serde(rename = "batchId")]
+    pub batch_id: Option<Uuid>,
+    #[serde(rename = "systemQuantity")]
+    pub system_quantity: f64,
+    // None if nobody counted the line
+    #[serde(rename = "countedQuantity")]
+    pub counted_quantity: Option<f64>,
+    pub variance: Option<f64>,
+}
+
+// A physical count of a location, either of all products or of one product group and its subgroups
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Stocktake {
+    #[serde(rename = "_id")]
+    pub id: Uuid,
+    #[serde(rename = "tenantId")]
+    pub tenant_id: Uuid,
+    #[serde(rename = "locationId")]
+    pub location_id: Uuid,
+    #[serde(rename = "productGroupId")]
+    pub product_group_id: Option<Uuid>,
+    // Counters without the ApproveStocktakes permission don't get to see system quantities while the count is open
+    pub blind: bool,
+    pub status: StocktakeStatus,
+    pub counts: Vec<StocktakeCount>,
+    // The variance report as it was booked
+    #[serde(rename = "approvedVariance")]
+    pub approved_variance: Option<Vec<VarianceLine>>,
+    #[serde(rename = "approvedAt")]
+    pub approved_at: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+}
+
+impl Stocktake {
+    pub const COLLECTION_NAME: &'static str = "stocktakes";
+
+    pub fn new(tenant_id: Uuid, location_id: Uuid, product_group_id: Option<Uuid>, blind: bool) -> Self {
+        Self {
+            id: Uuid::new(),
+            tenant_id,
+            location_id,
+            product_group_id,
+            blind,
+            status: StocktakeStatus::Open,
+            counts: Vec::new(),
+            approved_variance: None,
+            approved_at: None,
+            created_at: DateTime::now().to_string(),
+        }
+    }
+
+    // Ids of the products the count covers, None if it covers all of them
+    pub async fn scope(&self, connection: &Connection<ShelfWatcherDatabase>) -> Result<Option<Vec<Uuid>>, HttpResponse<Self>> {
+        let Some(product_group_id) = self.product_group_id else { return Ok(None) };
+
+        let groups = match ProductGroup::get_all_from_tenant(self.tenant_id, connection).await {
+            Ok(groups) => groups,
+            Err(err) => return Err(HttpResponse {
+                status: err.status,
+                message: err.message,
+                data: None
+            })
+        };
+
+        // The group and everything nested below it
+        let mut group_ids = vec![product_group_id];
+        let mut i = 0;
+        while i < group_ids.len() {
+            let parent_id = group_ids[i];
+            group_ids.extend(groups.iter().filter(|group| group.parent_id == Some(parent_id)).map(|group| group.id));
+            i += 1;
+        }
+
+        let products = match Product::get_all_from_tenant(self.tenant_id, None, connection).await {
+            Ok(products) => products,
+            Err(err) => return Err(HttpResponse {
+                status: err.status,
+                message: err.message,
+                data: None
+            })
+        };
+
+        Ok(Some(products
+            .into_iter()
+            .filter(|product| product.product_group_id.is_some_and(|group_id| group_ids.contains(&group_id)))
+            .map(|product| product.id)
+            .collect()))
+    }
+
+    // Replaces the counter's previous count of the product/batch. Removing the old count and appending the new one is a single
+    // write that only applies while the stocktake is still open, so concurrent recounts and approvals see one count or the other.
+    pub async fn record_count(&self, product_id: Uuid, batch_id: Option<Uuid>, quantity: f64, counter: AuditLogAuthor, connection: &Connection<ShelfWatcherDatabase>) -> Result<(), HttpResponse<Self>> {
+        let db = Self::get_collection(connection);
+        let counter_id = counter.id();
+        let count = StocktakeCount {
+            product_id,
+            batch_id,
+            quantity,
+            counter_id,
+            counter_type: counter.author_type(),
+            counted_at: DateTime::now().to_string()
+        };
+
+        let filter = doc! {
+            "_id": self.id,
+            "status": to_bson(&StocktakeStatus::Open).unwrap()
+        };
+        let update = vec![doc! {
+            "$set": {
+                "counts": {
+                    "$concatArrays": [
+                        {
+                            "$filter": {
+                                "input": { "$ifNull": ["$counts", []] },
+                                "as": "count",
+                                "cond": {
+                                    "$not": [{
+                                        "$and": [
+                                            { "$eq": ["$$count.productId", product_id] },
+                                            { "$eq": ["$$count.batchId", batch_id] },
+                                            { "$eq": ["$$count.counterId", counter_id] }
+                                        ]
+                                    }]
+                                }
+                            }
+                        },
+                        [{ "$literal": to_bson(&count).unwrap() }]
+                    ]
+                }
+            }
+        }];
+
+        match db.update_one(filter, update, None).await {
+            Ok(result) if result.matched_count == 0 => Err(HttpResponse {
+                status: 400,
+                message: "Stocktake is not open".to_string(),
+                data: None
+            }),
+            Ok(_) => Ok(()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error recording count: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    // Compares the counts with the current stock on hand of the location
+    pub async fn variance(&self, connection: &Connection<ShelfWatcherDatabase>) -> Result<Vec<VarianceLine>, HttpResponse<Self>> {
+        let scope = self.scope(connection).await?;
+
+        let levels = match StockMovement::on_hand(self.tenant_id, None, None, Some(self.location_id), connection).await {
+            Ok(levels) => levels,
+            Err(err) => return Err(HttpResponse {
+                status: err.status,
+                message: err.message,
+                data: None
+            })
+        };
+
+        let mut lines: Vec<VarianceLine> = levels
+            .into_iter()
+            .filter(|level| scope.as_ref().is_none_or(|scope| scope.contains(&level.product_id)))
+            .map(|level| VarianceLine {
+                product_id: level.product_id,
+                batch_id: level.batch_id,
+                system_quantity: level.quantity,
+                counted_quantity: None,
+                variance: None
+            })
+            .collect();
+
+        for count in &self.counts {
+            let line = match lines.iter_mut().find(|line| line.product_id == count.product_id && line.batch_id == count.batch_id) {
+                Some(line) => line,
+                None => {
+                    lines.push(VarianceLine {
+                        product_id: count.product_id,
+                        batch_id: count.batch_id,
+                        system_quantity: 0.0,
+                        counted_quantity: None,
+                        variance: None
+                    });
+                    lines.last_mut().unwrap()
+                }
+            };
+
+            let counted_quantity = line.counted_quantity.unwrap_or_default() + count.quantity;
+            line.counted_quantity = Some(counted_quantity);
+            line.variance = Some(counted_quantity - line.system_quantity);
+        }
+
+        Ok(lines)
+    }
+
+    // Books the variance of every line as an adjustment. Uncounted lines are only booked down to zero if `zero_uncounted` is set.
+    pub async fn approve(&mut self, zero_uncounted: bool, author: AuditLogAuthor, connection: &Connection<ShelfWatcherDatabase>) -> Result<Vec<StockMovement>, HttpResponse<Self>> {
+        if self.status != StocktakeStatus::Open {
+            return Err(HttpResponse {
+                status: 400,
+                message: "Only open stocktakes can be approved".to_string(),
+                data: None
+            });
+        }
+
+        let lines = self.variance(connection).await?;
+
+        let uncounted = lines.iter().filter(|line| line.counted_quantity.is_none()).count();
+        if uncounted > 0 && !zero_uncounted {
+            return Err(HttpResponse {
+                status: 400,
+                message: format!("{} lines with stock on hand weren't counted, count them or approve with zeroUncounted", uncounted),
+                data: None
+            });
+        }
+
+        let mut movements = Vec::new();
+        for line in &lines {
+            let variance = line.variance.unwrap_or(-line.system_quantity);
+            if variance == 0.0 {
+                continue;
+            }
+
+            let (from_location_id, to_location_id) = if variance < 0.0 { (Some(self.location_id), None) } else { (None, Some(self.location_id)) };
+            movements.push(StockMovement::new::<Self>(self.tenant_id, StockMovementType::Adjustment, line.product_id, line.batch_id, from_location_id, to_location_id, None, None, variance.abs(), "STOCKTAKE", Some(self.id), author)?);
+        }
+
+        self.status = StocktakeStatus::Approved;
+        self.approved_variance = Some(lines);
+        self.approved_at = Some(DateTime::now().to_string());
+
+        // Claim the stocktake before booking, a concurrent approval or cancellation must not book the variance twice
+        if !self.set_status_if(StocktakeStatus::Open, connection).await? {
+            return Err(HttpResponse {
+                status: 400,
+                message: "Only open stocktakes can be approved".to_string(),
+                data: None
+            });
+        }
+
+        for movement in &movements {
+            if let Err(err) = movement.insert(connection).await {
+                return Err(HttpResponse {
+                    status: err.status,
+                    message: err.message,
+                    data: None
+                });
+            }
+        }
+
+        Ok(movements)
+    }
+
+    #[allow(unused)]
+    pub async fn get_from_tenant(id: Uuid, tenant_id: Uuid, connection: &Connection<ShelfWatcherDatabase>) -> Result<Self, HttpResponse<Self>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "_id": id,
+            "tenantId": tenant_id
+        };
+        match db.find_one(filter, None).await.unwrap() {
+            Some(stocktake) => Ok(stocktake),
+            None => Err(HttpResponse {
+                status: 404,
+                message: "Stocktake not found".to_string(),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn get_all_from_tenant(tenant_id: Uuid, status: Option<StocktakeStatus>, location_id: Option<Uuid>, connection: &Connection<ShelfWatcherDatabase>) -> Result<Vec<Self>, HttpResponse<Vec<Self>>> {
+        let db = Self::get_collection(connection);
+
+        let mut filter = doc! {
+            "tenantId": tenant_id
+        };
+        if let Some(status) = status {
+            filter.insert("status", to_bson(&status).unwrap());
+        }
+        if let Some(location_id) = location_id {
+            filter.insert("locationId", location_id);
+        }
+        match db.find(filter, None).await {
+            Ok(cursor) => {
+                let stocktakes = cursor.map(|doc| doc.unwrap()).collect::<Vec<Self>>().await;
+                Ok(stocktakes)
+            },
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error fetching stocktakes from tenant: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    // Locations with an open blind count, their stock is hidden from counters
+    #[allow(unused)]
+    pub async fn get_blind_locations(tenant_id: Uuid, connection: &Connection<ShelfWatcherDatabase>) -> Result<Vec<Uuid>, HttpResponse<()>> {
+        match Self::get_all_from_tenant(tenant_id, Some(StocktakeStatus::Open), None, connection).await {
+            Ok(stocktakes) => Ok(stocktakes.into_iter().filter(|stocktake| stocktake.blind).map(|stocktake| stocktake.location_id).collect()),
+            Err(err) => Err(HttpResponse {
+                status: err.status,
+                message: err.message,
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn insert(&self, connection: &Connection<ShelfWatcherDatabase>) -> Result<Self, HttpResponse<Self>> {
+        let db = Self::get_collection(connection);
+
+        match db.insert_one(self.clone(), None).await {
+            Ok(_) => Ok(self.clone()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error inserting stocktake: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn update(&self, connection: &Connection<ShelfWatcherDatabase>) -> Result<Self, HttpResponse<Self>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "_id": self.id
+        };
+        match db.replace_one(filter, self.clone(), None).await {
+            Ok(_) => Ok(self.clone()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error updating stocktake: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    // Writes the status and approval of the stocktake only while it still has `status` in the database, counts are left alone.
+    // Returns false if another request changed the status first.
+    pub async fn set_status_if(&self, status: StocktakeStatus, connection: &Connection<ShelfWatcherDatabase>) -> Result<bool, HttpResponse<Self>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "_id": self.id,
+            "status": to_bson(&status).unwrap()
+        };
+        let update = doc! {
+            "$set": {
+                "status": to_bson(&self.status).unwrap(),
+                "approvedVariance": to_bson(&self.approved_variance).unwrap(),
+                "approvedAt": to_bson(&self.approved_at).unwrap()
+            }
+        };
+        match db.update_one(filter, update, None).await {
+            Ok(result) => Ok(result.matched_count == 1),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error updating stocktake: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    fn get_collection(connection: &Connection<ShelfWatcherDatabase>) -> Collection<Self> {
+        let db = get_main_db(connection);
+        db.collection(Self::COLLECTION_NAME)
+    }
+}
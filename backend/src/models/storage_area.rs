@@ -0,0 +1,320 @@
+use anyhow::Result;
+use mongodb::bson::{doc, DateTime, Document, Uuid};
+use rocket_db_pools::mongodb::{Client, Collection};
+use rocket::{futures::StreamExt, serde::{Deserialize, Serialize}};
+use crate::db::get_main_db;
+
+use super::{http_response::HttpResponse, stock_movement::StockMovement};
+
+// Kinds of storage areas from coarse to fine. An area can only contain finer kinds, stock is kept in bins.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub enum StorageAreaKind {
+    Zone,
+    Aisle,
+    Shelf,
+    Bin
+}
+
+impl StorageAreaKind {
+    pub fn from_string<T>(kind: &str) -> Result<Self, HttpResponse<T>> {
+        match kind.to_uppercase().as_str() {
+            "ZONE" => Ok(StorageAreaKind::Zone),
+            "AISLE" => Ok(StorageAreaKind::Aisle),
+            "SHELF" => Ok(StorageAreaKind::Shelf),
+            "BIN" => Ok(StorageAreaKind::Bin),
+            _ => Err(HttpResponse { status: 400, message: "Invalid storage area kind provided".to_string(), data: None })
+        }
+    }
+}
+
+// Stock of a product (and batch) in a storage area of a location
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct AreaStock {
+    // Stock that hasn't been put away into a bin yet has no area
+    #[serde(rename = "areaId")]
+    pub area_id: Option<Uuid>,
+    // Codes from the top-level area down to this one, e.g. ["A", "A-3", "A-3-02"]
+    pub path: Vec<String>,
+    #[serde(rename = "productId")]
+    pub product_id: Uuid,
+    #[serde(rename = "batchId")]
+    pub batch_id: Option<Uuid>,
+    pub quantity: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct StorageArea {
+    #[serde(rename = "_id")]
+    pub id: Uuid,
+    #[serde(rename = "tenantId")]
+    pub tenant_id: Uuid,
+    #[serde(rename = "locationId")]
+    pub location_id: Uuid,
+    // Areas without a parent sit at the top level of the location
+    #[serde(rename = "parentId")]
+    pub parent_id: Option<Uuid>,
+    pub kind: StorageAreaKind,
+    // Unique within the location and printed on labels
+    pub code: String,
+    pub name: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+}
+
+impl StorageArea {
+    pub const COLLECTION_NAME: &'static str = "storage_areas";
+
+    const MAX_CODE_LENGTH: usize = 32;
+
+    pub fn new(tenant_id: Uuid, location_id: Uuid, parent_id: Option<Uuid>, kind: StorageAreaKind, code: String, name: Option<String>) -> Self {
+        Self {
+            id: Uuid::new(),
+            tenant_id,
+            location_id,
+            parent_id,
+            kind,
+            code,
+            name,
+            created_at: DateTime::now().to_string(),
+        }
+    }
+
+    // Uppercases the code and restricts it to characters every common label barcode can encode
+    pub fn normalize_code<T>(code: &str) -> Result<String, HttpResponse<T>> {
+        let code = code.trim().to_uppercase();
+
+        if code.is_empty() || code.len() > Self::MAX_CODE_LENGTH || !code.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.') {
+            return Err(HttpResponse {
+                status: 400,
+                message: format!("Storage area codes must be 1 to {} letters, digits, '-' or '.'", Self::MAX_CODE_LENGTH),
+                data: None
+            });
+        }
+
+        Ok(code)
+    }
+
+    // Checks that the parent is a coarser area of the same location and that this area is not one of its ancestors
+    pub async fn validate_parent(&self, parent_id: Uuid, client: &Client) -> Result<(), HttpResponse<Self>> {
+        let parent = match Self::get_from_location(parent_id, self.tenant_id, self.location_id, client).await {
+            Ok(parent) => parent,
+            Err(_) => return Err(HttpResponse {
+                status: 400,
+                message: "Parent storage area not found in this location".to_string(),
+                data: None
+            })
+        };
+
+        if parent.kind >= self.kind {
+            return Err(HttpResponse {
+                status: 400,
+                message: format!("A {:?} can't be placed inside a {:?}", self.kind, parent.kind),
+                data: None
+            });
+        }
+
+        let mut current = Some(parent);
+        while let Some(area) = current {
+            if area.id == self.id {
+                return Err(HttpResponse {
+                    status: 400,
+                    message: "A storage area can't be nested inside itself".to_string(),
+                    data: None
+                });
+            }
+
+            current = match area.parent_id {
+                Some(id) => Self::get_from_location(id, self.tenant_id, self.location_id, client).await.ok(),
+                None => None
+            };
+        }
+
+        Ok(())
+    }
+
+    // Stock may only be placed in bins of the given location
+    pub async fn require_bin<T>(id: Uuid, tenant_id: Uuid, location_id: Uuid, client: &Client) -> Result<Self, HttpResponse<T>> {
+        match Self::get_from_location(id, tenant_id, location_id, client).await {
+            Ok(area) if area.kind == StorageAreaKind::Bin => Ok(area),
+            Ok(area) => Err(HttpResponse {
+                status: 400,
+                message: format!("Storage area {} is not a bin", area.code),
+                data: None
+            }),
+            Err(err) => Err(HttpResponse {
+                status: err.status,
+                message: err.message,
+                data: None
+            })
+        }
+    }
+
+    // IDs of this area and every area nested inside it
+    pub fn subtree(&self, areas: &[Self]) -> Vec<Uuid> {
+        let mut ids = vec![self.id];
+        let mut i = 0;
+
+        while i < ids.len() {
+            let parent_id = ids[i];
+            ids.extend(areas.iter().filter(|area| area.parent_id == Some(parent_id)).map(|area| area.id));
+            i += 1;
+        }
+
+        ids
+    }
+
+    // Codes from the top-level area down to `area_id`
+    pub fn path(area_id: Uuid, areas: &[Self]) -> Vec<String> {
+        let mut path = Vec::new();
+        let mut current = areas.iter().find(|area| area.id == area_id);
+
+        while let Some(area) = current {
+            path.insert(0, area.code.clone());
+            current = area.parent_id.and_then(|parent_id| areas.iter().find(|area| area.id == parent_id));
+        }
+
+        path
+    }
+
+    // Stock in the location per area. With `area_ids` only stock in those areas is returned, otherwise
+    // all stock of the location including what hasn't been put away yet.
+    pub async fn get_stock(tenant_id: Uuid, location_id: Uuid, product_id: Option<Uuid>, area_ids: Option<&[Uuid]>, client: &Client) -> Result<Vec<AreaStock>, HttpResponse<Vec<AreaStock>>> {
+        let areas = Self::get_all_from_location(tenant_id, location_id, client).await.map_err(|err| HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })?;
+
+        let levels = StockMovement::by_area(tenant_id, product_id, None, Some(location_id), client).await.map_err(|err| HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })?;
+
+        Ok(levels
+            .into_iter()
+            .filter(|level| area_ids.is_none_or(|area_ids| level.area_id.is_some_and(|area_id| area_ids.contains(&area_id))))
+            .map(|level| AreaStock {
+                area_id: level.area_id,
+                path: level.area_id.map(|area_id| Self::path(area_id, &areas)).unwrap_or_default(),
+                product_id: level.product_id,
+                batch_id: level.batch_id,
+                quantity: level.quantity
+            })
+            .collect())
+    }
+
+    #[allow(unused)]
+    pub async fn get_from_location(id: Uuid, tenant_id: Uuid, location_id: Uuid, client: &Client) -> Result<Self, HttpResponse<Self>> {
+        Self::find_one(doc! { "_id": id, "tenantId": tenant_id, "locationId": location_id }, client).await
+    }
+
+    #[allow(unused)]
+    pub async fn get_by_code(code: &str, tenant_id: Uuid, location_id: Uuid, client: &Client) -> Result<Self, HttpResponse<Self>> {
+        Self::find_one(doc! { "code": code.trim().to_uppercase(), "tenantId": tenant_id, "locationId": location_id }, client).await
+    }
+
+    #[allow(unused)]
+    pub async fn get_all_from_location(tenant_id: Uuid, location_id: Uuid, client: &Client) -> Result<Vec<Self>, HttpResponse<Vec<Self>>> {
+        let db = Self::get_collection(client);
+
+        let filter = doc! {
+            "tenantId": tenant_id,
+            "locationId": location_id
+        };
+        match db.find(filter, None).await {
+            Ok(cursor) => {
+                let areas = cursor.map(|doc| doc.unwrap()).collect::<Vec<Self>>().await;
+                Ok(areas)
+            },
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error fetching storage areas from location: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn has_children(&self, client: &Client) -> Result<bool, HttpResponse<()>> {
+        let db = Self::get_collection(client);
+
+        match db.count_documents(doc! { "parentId": self.id }, None).await {
+            Ok(count) => Ok(count > 0),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error counting child storage areas: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    async fn find_one(filter: Document, client: &Client) -> Result<Self, HttpResponse<Self>> {
+        let db = Self::get_collection(client);
+
+        match db.find_one(filter, None).await.unwrap() {
+            Some(area) => Ok(area),
+            None => Err(HttpResponse {
+                status: 404,
+                message: "Storage area not found".to_string(),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn insert(&self, client: &Client) -> Result<Self, HttpResponse<Self>> {
+        let db = Self::get_collection(client);
+
+        match db.insert_one(self.clone(), None).await {
+            Ok(_) => Ok(self.clone()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error inserting storage area: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn update(&self, client: &Client) -> Result<Self, HttpResponse<Self>> {
+        let db = Self::get_collection(client);
+
+        let filter = doc! {
+            "_id": self.id
+        };
+        match db.replace_one(filter, self.clone(), None).await {
+            Ok(_) => Ok(self.clone()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error updating storage area: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn delete(&self, client: &Client) -> Result<Self, HttpResponse<()>> {
+        let db = Self::get_collection(client);
+
+        let filter = doc! {
+            "_id": self.id
+        };
+        match db.delete_one(filter, None).await {
+            Ok(_) => Ok(self.clone()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error deleting storage area: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    fn get_collection(client: &Client) -> Collection<Self> {
+        let db = get_main_db(client);
+        db.collection(Self::COLLECTION_NAME)
+    }
+}
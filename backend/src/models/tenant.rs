@@ -1,10 +1,11 @@
 use anyhow::Result;
-use mongodb::bson::{doc, DateTime, Uuid};
-use rocket_db_pools::{mongodb::Collection, Connection};
+use std::collections::HashMap;
+use mongodb::bson::{doc, DateTime, Document, Uuid};
+use rocket_db_pools::{mongodb::{Client, Collection}, Connection};
 use rocket::{futures::StreamExt, serde::{Deserialize, Serialize}};
 use crate::db::{get_main_db, ShelfWatcherDatabase};
 
-use super::http_response::HttpResponse;
+use super::{api_key::ApiKey, http_response::HttpResponse, invitation::Invitation, item::Item, location::Location, low_stock_alert::LowStockAlert, product::Product, product_batch::ProductBatch, product_group::ProductGroup, reorder_point::ReorderPoint, stock_movement::StockMovement, stock_snapshot::StockSnapshot, stocktake::Stocktake, storage_area::StorageArea, tenant_plan::{QuotaOverrides, QuotaResource, TenantPlan, TenantQuotas}, tenant_settings::TenantSettings, transfer::Transfer, user::User};
 
 #[derive(Debug, Clone, Serialize, Deserialize)] 
 #[serde(crate = "rocket::serde")] 
@@ -14,6 +15,26 @@ pub struct Tenant {
     pub name: String,
     #[serde(rename = "ownerId")]
     pub owner_id: Uuid,
+    // Member the owner offered the tenant to, ownership only moves once they accept
+    #[serde(rename = "pendingOwnerId", default)]
+    pub pending_owner_id: Option<Uuid>,
+    #[serde(rename = "requireTwoFactor", default)]
+    pub require_two_factor: bool,
+    #[serde(default)]
+    pub settings: TenantSettings,
+    // Only admins can change the plan and the overrides
+    #[serde(default)]
+    pub plan: TenantPlan,
+    #[serde(rename = "quotaOverrides", default)]
+    pub quota_overrides: QuotaOverrides,
+    // Deleted tenants can't be used anymore, but the owner or an admin can restore them until they are purged
+    #[serde(rename = "deletedAt", default)]
+    pub deleted_at: Option<String>,
+    #[serde(rename = "purgeAfter", default)]
+    pub purge_after: Option<DateTime>,
+    // Set once the purge job claimed the tenant, from then on it can't be restored anymore
+    #[serde(rename = "purgingSince", default)]
+    pub purging_since: Option<DateTime>,
     #[serde(rename = "createdAt")]
     pub created_at: String,
 }
@@ -21,18 +42,239 @@ pub struct Tenant {
 impl Tenant {
     pub const COLLECTION_NAME: &'static str = "tenants";
 
-    // TODO: Implement owner_id
-    pub fn new(name: String) -> Self {
+    pub fn new(name: String, owner_id: Uuid) -> Self {
         Self {
             id: Uuid::new(),
             name,
-            owner_id: Uuid::new(),
+            owner_id,
+            pending_owner_id: None,
+            require_two_factor: false,
+            settings: TenantSettings::default(),
+            plan: TenantPlan::default(),
+            quota_overrides: QuotaOverrides::default(),
+            deleted_at: None,
+            purge_after: None,
+            purging_since: None,
             created_at: DateTime::now().to_string(),
         }
     }
 
+    // Collections holding the tenant's data, emptied when the tenant is purged
+    const PURGED_COLLECTIONS: [&'static str; 14] = [
+        Location::COLLECTION_NAME,
+        StorageArea::COLLECTION_NAME,
+        ProductGroup::COLLECTION_NAME,
+        Product::COLLECTION_NAME,
+        ProductBatch::COLLECTION_NAME,
+        Item::COLLECTION_NAME,
+        StockMovement::COLLECTION_NAME,
+        StockSnapshot::COLLECTION_NAME,
+        Transfer::COLLECTION_NAME,
+        Stocktake::COLLECTION_NAME,
+        ReorderPoint::COLLECTION_NAME,
+        LowStockAlert::COLLECTION_NAME,
+        ApiKey::COLLECTION_NAME,
+        Invitation::COLLECTION_NAME
+    ];
+
+    pub fn is_deleted(&self) -> bool {
+        self.deleted_at.is_some()
+    }
+
+    // Deleted tenants behave as if they didn't exist, except for restoring them
+    pub fn require_active<T>(&self) -> Result<(), HttpResponse<T>> {
+        if self.is_deleted() {
+            return Err(HttpResponse {
+                status: 404,
+                message: "Tenant has been deleted".to_string(),
+                data: None
+            });
+        }
+
+        Ok(())
+    }
+
+    // Marks the tenant as deleted, it is purged once `grace_days` have passed
+    pub fn soft_delete(&mut self, grace_days: i64) {
+        let now = DateTime::now();
+        self.deleted_at = Some(now.to_string());
+        self.purge_after = Some(DateTime::from_millis(now.timestamp_millis() + grace_days * 24 * 60 * 60 * 1000));
+    }
+
+    // Undoes the deletion unless the tenant was restored or claimed for purging in the meantime. Returns whether it was restored.
+    pub async fn restore(&mut self, client: &Client) -> Result<bool, HttpResponse<Self>> {
+        let db = Self::get_collection(client);
+
+        let filter = doc! {
+            "_id": self.id,
+            "deletedAt": { "$ne": null },
+            "purgingSince": null
+        };
+        let update = doc! {
+            "$unset": { "deletedAt": "", "purgeAfter": "" }
+        };
+        match db.update_one(filter, update, None).await {
+            Ok(result) if result.modified_count == 1 => {
+                self.deleted_at = None;
+                self.purge_after = None;
+                Ok(true)
+            },
+            Ok(_) => Ok(false),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error restoring tenant: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    // Claims a deleted tenant whose grace period is over for purging, so a concurrent restore can't bring it back halfway.
+    // A tenant whose purge failed is claimed again on the next run. Returns whether the tenant is still due.
+    pub async fn claim_for_purge(&mut self, client: &Client) -> Result<bool, HttpResponse<()>> {
+        let db = Self::get_collection(client);
+
+        let now = DateTime::now();
+        let filter = doc! {
+            "_id": self.id,
+            "deletedAt": { "$ne": null },
+            "purgeAfter": { "$lte": now }
+        };
+        let update = doc! {
+            "$set": { "purgingSince": now }
+        };
+        match db.update_one(filter, update, None).await {
+            Ok(result) if result.matched_count == 1 => {
+                self.purging_since = Some(now);
+                Ok(true)
+            },
+            Ok(_) => Ok(false),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error claiming tenant for purging: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    // Replaces the pending ownership transfer, as long as the owner and the pending transfer are still the ones read.
+    // Returns false if another request changed either in the meantime.
+    pub async fn set_pending_owner(&mut self, pending_owner_id: Option<Uuid>, client: &Client) -> Result<bool, HttpResponse<Self>> {
+        let db = Self::get_collection(client);
+
+        let filter = doc! {
+            "_id": self.id,
+            "ownerId": self.owner_id,
+            "pendingOwnerId": self.pending_owner_id
+        };
+        let update = doc! {
+            "$set": { "pendingOwnerId": pending_owner_id }
+        };
+        match db.update_one(filter, update, None).await {
+            Ok(result) if result.matched_count == 1 => {
+                self.pending_owner_id = pending_owner_id;
+                Ok(true)
+            },
+            Ok(_) => Ok(false),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error updating ownership transfer: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    // Hands the tenant to `new_owner_id` only while the transfer to them is still pending from the current owner.
+    // Returns false if it was cancelled or replaced in the meantime.
+    pub async fn transfer_ownership(&mut self, new_owner_id: Uuid, client: &Client) -> Result<bool, HttpResponse<Self>> {
+        let db = Self::get_collection(client);
+
+        let filter = doc! {
+            "_id": self.id,
+            "ownerId": self.owner_id,
+            "pendingOwnerId": new_owner_id
+        };
+        let update = doc! {
+            "$set": { "ownerId": new_owner_id, "pendingOwnerId": null }
+        };
+        match db.update_one(filter, update, None).await {
+            Ok(result) if result.matched_count == 1 => {
+                self.owner_id = new_owner_id;
+                self.pending_owner_id = None;
+                Ok(true)
+            },
+            Ok(_) => Ok(false),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error transferring ownership: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    // Deletes all data of the tenant, its memberships and finally the tenant itself.
+    // Returns how many documents were deleted per collection.
+    pub async fn purge(&self, client: &Client) -> Result<HashMap<String, String>, HttpResponse<()>> {
+        let mut summary = HashMap::new();
+
+        for collection in Self::PURGED_COLLECTIONS {
+            let db = get_main_db(client).collection::<Document>(collection);
+
+            match db.delete_many(doc! { "tenantId": self.id }, None).await {
+                Ok(result) => summary.insert(collection.to_string(), result.deleted_count.to_string()),
+                Err(err) => return Err(HttpResponse {
+                    status: 500,
+                    message: format!("Error purging {} of tenant: {:?}", collection, err),
+                    data: None
+                })
+            };
+        }
+
+        let memberships = User::remove_membership_from_all(self.id, client).await?;
+        summary.insert("memberships".to_string(), memberships.to_string());
+
+        let db = Self::get_collection(client);
+        if let Err(err) = db.delete_one(doc! { "_id": self.id }, None).await {
+            return Err(HttpResponse {
+                status: 500,
+                message: format!("Error deleting tenant: {:?}", err),
+                data: None
+            });
+        }
+
+        Ok(summary)
+    }
+
+    pub fn quotas(&self) -> TenantQuotas {
+        self.quota_overrides.apply(self.plan.quotas())
+    }
+
+    // Fails once the tenant uses up its quota of `resource`, call it before creating one more
+    pub async fn require_quota<T>(&self, resource: QuotaResource, client: &Client) -> Result<(), HttpResponse<T>> {
+        self.require_quota_excluding(resource, 0, client).await
+    }
+
+    // Like `require_quota`, for a caller already counted `held` times, e.g. a pending invitation turning into a member
+    pub async fn require_quota_excluding<T>(&self, resource: QuotaResource, held: i64, client: &Client) -> Result<(), HttpResponse<T>> {
+        let limit = resource.limit(&self.quotas());
+        let used = resource.count(self, client).await.map_err(|err| HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })? - held;
+
+        if used >= limit {
+            return Err(HttpResponse {
+                status: 403,
+                message: format!("Quota exceeded: the tenant may have at most {} {} ({} in use)", limit, resource.name(), used),
+                data: None
+            });
+        }
+
+        Ok(())
+    }
+
     #[allow(unused)]
-    pub async fn get_by_id(id: Uuid, connection: &Connection<ShelfWatcherDatabase>) -> Result<Self, HttpResponse<Self>> {
+    pub async fn get_by_id(id: Uuid, connection: &Client) -> Result<Self, HttpResponse<Self>> {
         let db = Self::get_collection(connection);
 
         let filter = doc! {
@@ -48,8 +290,28 @@ impl Tenant {
         }
     }
 
+    // Deleted tenants whose grace period is over
+    pub async fn get_due_for_purge(client: &Client) -> Result<Vec<Self>, HttpResponse<Vec<Self>>> {
+        let db = Self::get_collection(client);
+
+        let filter = doc! {
+            "purgeAfter": { "$lte": DateTime::now() }
+        };
+        match db.find(filter, None).await {
+            Ok(cursor) => {
+                let tenants = cursor.map(|doc| doc.unwrap()).collect::<Vec<Self>>().await;
+                Ok(tenants)
+            },
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error fetching tenants to purge: {:?}", err),
+                data: None
+            })
+        }
+    }
+
     #[allow(unused)]
-    pub async fn get_by_name(name: String, connection: &Connection<ShelfWatcherDatabase>) -> Result<Self, HttpResponse<Self>> {
+    pub async fn get_by_name(name: String, connection: &Client) -> Result<Self, HttpResponse<Self>> {
         let db = Self::get_collection(connection);
 
         let filter = doc! {
@@ -66,7 +328,7 @@ impl Tenant {
     }
 
     #[allow(unused)]
-    pub async fn get_all(connection: &Connection<ShelfWatcherDatabase>) -> Result<Vec<Self>, HttpResponse<Vec<Self>>> {
+    pub async fn get_all(connection: &Client) -> Result<Vec<Self>, HttpResponse<Vec<Self>>> {
         let db = Self::get_collection(connection);
 
         match db.find(None, None).await {
@@ -83,7 +345,7 @@ impl Tenant {
     }
 
     #[allow(unused)]
-    pub async fn insert(&self, connection: &Connection<ShelfWatcherDatabase>) -> Result<Self, HttpResponse<Self>> {
+    pub async fn insert(&self, connection: &Client) -> Result<Self, HttpResponse<Self>> {
         let db = Self::get_collection(connection);
 
         match db.insert_one(self.clone(), None).await {
@@ -96,6 +358,28 @@ impl Tenant {
         }
     }
 
+    // Tenants saved before the settings existed keep the expiry warning window at the top level, this moves it into
+    // the settings unless they already have one. Returns how many tenants were migrated.
+    pub async fn migrate_legacy_settings(client: &Client) -> Result<u64, HttpResponse<()>> {
+        let db = get_main_db(client).collection::<Document>(Self::COLLECTION_NAME);
+
+        let filter = doc! {
+            "expiryWarningDays": { "$exists": true }
+        };
+        let update = vec![
+            doc! { "$set": { "settings.expiryWarningDays": { "$ifNull": ["$settings.expiryWarningDays", "$expiryWarningDays"] } } },
+            doc! { "$unset": "expiryWarningDays" }
+        ];
+        match db.update_many(filter, update, None).await {
+            Ok(result) => Ok(result.modified_count),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error migrating tenant settings: {:?}", err),
+                data: None
+            })
+        }
+    }
+
     #[allow(unused)]
     pub async fn update(&self, connection: &Connection<ShelfWatcherDatabase>) -> Result<Self, HttpResponse<Self>> {
         let db = Self::get_collection(connection);
@@ -131,7 +415,7 @@ impl Tenant {
     }
 
     #[allow(unused)]
-    fn get_collection(connection: &Connection<ShelfWatcherDatabase>) -> Collection<Self> {
+    fn get_collection(connection: &Client) -> Collection<Self> {
         let db = get_main_db(connection);
         db.collection(Self::COLLECTION_NAME)
     }
@@ -0,0 +1,548 @@
+use std::{collections::{HashMap, HashSet}, io::Read};
+use mongodb::bson::{doc, from_bson, from_document, spec::BinarySubtype, to_bson, to_document, Bson, DateTime, Document, Uuid};
+use rocket_db_pools::mongodb::Client;
+use rocket::{futures::StreamExt, serde::{de::DeserializeOwned, Deserialize, Serialize}};
+use crate::db::{get_logs_db, get_main_db};
+
+use super::{audit_log::{AuditLog, AuditLogAction, AuditLogAuthor, AuditLogAuthorType, AuditLogEntityType}, http_response::HttpResponse, item::Item, location::Location, low_stock_alert::LowStockAlert, membership::{Membership, TenantRole}, product::Product, product_batch::ProductBatch, product_group::ProductGroup, reorder_point::ReorderPoint, stock_movement::StockMovement, stock_snapshot::StockSnapshot, stocktake::Stocktake, storage_area::StorageArea, tenant::Tenant, tenant_plan::QuotaResource, transfer::Transfer, user::User};
+
+type ShapeCheck = fn(Document) -> Result<(), String>;
+
+fn fits<T: DeserializeOwned>(document: Document) -> Result<(), String> {
+    from_document::<T>(document).map(|_| ()).map_err(|err| err.to_string())
+}
+
+// Collections holding the tenant's data. API keys and invitations carry secrets and stay behind.
+const ARCHIVED_COLLECTIONS: [(&str, ShapeCheck); 12] = [
+    (Location::COLLECTION_NAME, fits::<Location>),
+    (StorageArea::COLLECTION_NAME, fits::<StorageArea>),
+    (ProductGroup::COLLECTION_NAME, fits::<ProductGroup>),
+    (Product::COLLECTION_NAME, fits::<Product>),
+    (ProductBatch::COLLECTION_NAME, fits::<ProductBatch>),
+    (Item::COLLECTION_NAME, fits::<Item>),
+    (StockMovement::COLLECTION_NAME, fits::<StockMovement>),
+    (StockSnapshot::COLLECTION_NAME, fits::<StockSnapshot>),
+    (Transfer::COLLECTION_NAME, fits::<Transfer>),
+    (Stocktake::COLLECTION_NAME, fits::<Stocktake>),
+    (ReorderPoint::COLLECTION_NAME, fits::<ReorderPoint>),
+    (LowStockAlert::COLLECTION_NAME, fits::<LowStockAlert>)
+];
+
+// Audit log collections with the collection of the entities they are about
+const ARCHIVED_AUDIT_LOGS: [(&str, &str); 10] = [
+    (AuditLog::COLLECTION_NAME_TENANTS, Tenant::COLLECTION_NAME),
+    (AuditLog::COLLECTION_NAME_LOCATIONS, Location::COLLECTION_NAME),
+    (AuditLog::COLLECTION_NAME_STORAGE_AREAS, StorageArea::COLLECTION_NAME),
+    (AuditLog::COLLECTION_NAME_PRODUCT_GROUPS, ProductGroup::COLLECTION_NAME),
+    (AuditLog::COLLECTION_NAME_PRODUCTS, Product::COLLECTION_NAME),
+    (AuditLog::COLLECTION_NAME_PRODUCT_BATCHES, ProductBatch::COLLECTION_NAME),
+    (AuditLog::COLLECTION_NAME_ITEMS, Item::COLLECTION_NAME),
+    (AuditLog::COLLECTION_NAME_TRANSFERS, Transfer::COLLECTION_NAME),
+    (AuditLog::COLLECTION_NAME_STOCKTAKES, Stocktake::COLLECTION_NAME),
+    (AuditLog::COLLECTION_NAME_REORDER_POINTS, ReorderPoint::COLLECTION_NAME)
+];
+
+const LOCATIONS: &[&str] = &[Location::COLLECTION_NAME];
+const STORAGE_AREAS: &[&str] = &[StorageArea::COLLECTION_NAME];
+
+// Fields holding ids of archived documents, with the collections they may point into.
+// `parentId` points into the collection of its own document and `entityId` is checked against the audit log collection.
+const REFERENCES: [(&str, &[&str]); 14] = [
+    ("tenantId", &[Tenant::COLLECTION_NAME]),
+    ("locationId", LOCATIONS),
+    ("fromLocationId", LOCATIONS),
+    ("toLocationId", LOCATIONS),
+    ("sourceLocationId", LOCATIONS),
+    ("destinationLocationId", LOCATIONS),
+    ("areaId", STORAGE_AREAS),
+    ("fromAreaId", STORAGE_AREAS),
+    ("toAreaId", STORAGE_AREAS),
+    ("productGroupId", &[ProductGroup::COLLECTION_NAME]),
+    ("productId", &[Product::COLLECTION_NAME]),
+    ("batchId", &[ProductBatch::COLLECTION_NAME]),
+    ("transferId", &[Transfer::COLLECTION_NAME]),
+    ("referenceId", &[Transfer::COLLECTION_NAME, Stocktake::COLLECTION_NAME, ProductBatch::COLLECTION_NAME, Item::COLLECTION_NAME])
+];
+
+// Fields pointing at users, with the field holding the author type if there is one. Users aren't part of an archive,
+// so these keep their ids if the user exists, everything else becomes the system author. API keys stay behind as well.
+const USER_REFERENCES: [(&str, Option<&str>); 4] = [
+    ("userId", Some("authorType")),
+    ("authorId", Some("authorType")),
+    ("counterId", Some("counterType")),
+    ("acknowledgedBy", None)
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ArchiveFile {
+    pub path: String,
+    pub documents: u64,
+}
+
+// `manifest.json` of an archive, describing every other file in it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ArchiveManifest {
+    pub format: String,
+    pub version: u32,
+    #[serde(rename = "tenantId")]
+    pub tenant_id: String,
+    #[serde(rename = "tenantName")]
+    pub tenant_name: String,
+    #[serde(rename = "exportedAt")]
+    pub exported_at: String,
+    pub files: Vec<ArchiveFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ImportedTenant {
+    pub tenant: Tenant,
+    // Documents imported per file of the archive
+    pub documents: HashMap<String, u64>,
+}
+
+// A tar file with a manifest and one file per collection, each holding one document per line as canonical extended JSON
+pub struct TenantArchive;
+
+impl TenantArchive {
+    pub const FORMAT: &'static str = "shelfwatcher-tenant-archive";
+    pub const VERSION: u32 = 1;
+
+    // Documents an import may hold across all files, tenant and audit logs included
+    pub const MAX_DOCUMENTS: u64 = 1_000_000;
+
+    const MANIFEST_PATH: &'static str = "manifest.json";
+    const TENANT_PATH: &'static str = "tenant.jsonl";
+
+    fn data_path(collection: &str) -> String {
+        format!("data/{}.jsonl", collection)
+    }
+
+    fn audit_log_path(collection: &str) -> String {
+        format!("audit_logs/{}.jsonl", collection)
+    }
+
+    pub fn file_name(tenant: &Tenant) -> String {
+        format!("tenant-{}-{}.tar", tenant.id, &DateTime::now().try_to_rfc3339_string().unwrap()[..10])
+    }
+
+    pub async fn export(tenant: &Tenant, client: &Client) -> Result<Vec<u8>, HttpResponse<()>> {
+        let mut files: Vec<(String, Vec<Document>)> = Vec::new();
+
+        let tenant_document = to_document(tenant).map_err(|err| internal_error(format!("Error serializing tenant: {:?}", err)))?;
+        files.push((Self::TENANT_PATH.to_string(), vec![tenant_document]));
+
+        let mut entity_ids: HashMap<&str, Vec<Uuid>> = HashMap::from([(Tenant::COLLECTION_NAME, vec![tenant.id])]);
+        for (collection, _) in ARCHIVED_COLLECTIONS {
+            let documents = find(get_main_db(client).collection(collection), doc! { "tenantId": tenant.id }).await?;
+            entity_ids.insert(collection, documents.iter().filter_map(document_id).collect());
+            files.push((Self::data_path(collection), documents));
+        }
+
+        // Logs of entities that no longer exist are left out, like the audit retention does
+        for (collection, entity_collection) in ARCHIVED_AUDIT_LOGS {
+            let ids = entity_ids.get(entity_collection).cloned().unwrap_or_default();
+            let documents = find(get_logs_db(client).collection(collection), doc! { "entityId": { "$in": ids } }).await?;
+            files.push((Self::audit_log_path(collection), documents));
+        }
+
+        let manifest = ArchiveManifest {
+            format: Self::FORMAT.to_string(),
+            version: Self::VERSION,
+            tenant_id: tenant.id.to_string(),
+            tenant_name: tenant.name.clone(),
+            exported_at: DateTime::now().to_string(),
+            files: files.iter().map(|(path, documents)| ArchiveFile { path: path.clone(), documents: documents.len() as u64 }).collect()
+        };
+
+        let mut builder = tar::Builder::new(Vec::new());
+        let manifest = rocket::serde::json::to_pretty_string(&manifest).map_err(|err| internal_error(format!("Error serializing manifest: {:?}", err)))?;
+        append(&mut builder, Self::MANIFEST_PATH, manifest.as_bytes())?;
+
+        for (path, documents) in files {
+            let mut lines = String::new();
+            for document in documents {
+                lines.push_str(&Bson::Document(document).into_canonical_extjson().to_string());
+                lines.push('\n');
+            }
+            append(&mut builder, &path, lines.as_bytes())?;
+        }
+
+        builder.into_inner().map_err(|err| internal_error(format!("Error writing archive: {:?}", err)))
+    }
+
+    // Restores an archive into a new tenant owned by `owner`. Every document gets a new id and all references are
+    // rewritten, a reference to anything that isn't part of the archive rejects the whole import before anything is written.
+    // The plan and quota overrides are only kept with `keep_plan`, otherwise the tenant starts on the default plan.
+    pub async fn import(archive: &[u8], name: Option<String>, owner: &User, keep_plan: bool, author: AuditLogAuthor, client: &Client) -> Result<ImportedTenant, HttpResponse<()>> {
+        let mut entries = read_entries(archive)?;
+
+        let manifest: ArchiveManifest = match entries.remove(Self::MANIFEST_PATH) {
+            Some(manifest) => rocket::serde::json::from_slice(&manifest).map_err(|err| bad_request(format!("Invalid manifest: {}", err)))?,
+            None => return Err(bad_request("Archive has no manifest".to_string()))
+        };
+
+        if manifest.format != Self::FORMAT {
+            return Err(bad_request("Not a tenant archive".to_string()));
+        }
+        if manifest.version > Self::VERSION {
+            return Err(bad_request(format!("Archive version {} is newer than the supported version {}", manifest.version, Self::VERSION)));
+        }
+
+        // Batches, items, movements and audit logs have no quota of their own, the archive as a whole is capped instead
+        let total: u64 = manifest.files.iter().map(|file| file.documents).sum();
+        if total > Self::MAX_DOCUMENTS {
+            return Err(HttpResponse {
+                status: 403,
+                message: format!("Archive holds {} documents, at most {} can be imported", total, Self::MAX_DOCUMENTS),
+                data: None
+            });
+        }
+
+        let mut files: HashMap<String, Vec<Document>> = HashMap::new();
+        for file in &manifest.files {
+            let Some(content) = entries.remove(&file.path) else {
+                return Err(bad_request(format!("{} is listed in the manifest but missing", file.path)));
+            };
+
+            let documents = parse_lines(&file.path, &content)?;
+            if documents.len() as u64 != file.documents {
+                return Err(bad_request(format!("{} holds {} documents, the manifest lists {}", file.path, documents.len(), file.documents)));
+            }
+            files.insert(file.path.clone(), documents);
+        }
+        if let Some(path) = entries.keys().next() {
+            return Err(bad_request(format!("{} isn't listed in the manifest", path)));
+        }
+
+        let archived_tenant: Tenant = match files.remove(Self::TENANT_PATH).as_deref() {
+            Some([document]) => from_document(document.clone()).map_err(|err| bad_request(format!("Invalid tenant: {}", err)))?,
+            _ => return Err(bad_request(format!("{} must hold exactly one tenant", Self::TENANT_PATH)))
+        };
+        if archived_tenant.id.to_string() != manifest.tenant_id {
+            return Err(bad_request("Tenant doesn't match the manifest".to_string()));
+        }
+
+        let mut tenant = Tenant::new(name.unwrap_or(archived_tenant.name.clone()), owner.id);
+        tenant.require_two_factor = archived_tenant.require_two_factor;
+        tenant.settings = archived_tenant.settings.clone();
+        if keep_plan {
+            tenant.plan = archived_tenant.plan;
+            tenant.quota_overrides = archived_tenant.quota_overrides.clone();
+        }
+
+        if Tenant::get_by_name(tenant.name.clone(), client).await.is_ok() {
+            return Err(bad_request("Tenant already exists".to_string()));
+        }
+
+        // New ids for every document, along with the collection it belongs to
+        let mut ids: HashMap<Uuid, (&str, Uuid)> = HashMap::from([(archived_tenant.id, (Tenant::COLLECTION_NAME, tenant.id))]);
+        let mut data: Vec<(&str, Vec<Document>)> = Vec::new();
+        for (collection, check) in ARCHIVED_COLLECTIONS {
+            let path = Self::data_path(collection);
+            let documents = files.remove(&path).unwrap_or_default();
+
+            for document in &documents {
+                let id = document_id(document).ok_or_else(|| bad_request(format!("A document in {} has no id", path)))?;
+                check(document.clone()).map_err(|err| bad_request(format!("Invalid document {} in {}: {}", id, path, err)))?;
+
+                if document.get("tenantId") != Some(&Bson::from(archived_tenant.id)) {
+                    return Err(bad_request(format!("Document {} in {} belongs to another tenant", id, path)));
+                }
+                if ids.insert(id, (collection, Uuid::new())).is_some() {
+                    return Err(bad_request(format!("Document {} appears more than once", id)));
+                }
+            }
+            data.push((collection, documents));
+        }
+
+        let mut audit_logs: Vec<(&str, Vec<Document>)> = Vec::new();
+        for (collection, entity_collection) in ARCHIVED_AUDIT_LOGS {
+            let path = Self::audit_log_path(collection);
+            let documents = files.remove(&path).unwrap_or_default();
+
+            for document in &documents {
+                let id = document_id(document).ok_or_else(|| bad_request(format!("A document in {} has no id", path)))?;
+                fits::<AuditLog>(document.clone()).map_err(|err| bad_request(format!("Invalid document {} in {}: {}", id, path, err)))?;
+
+                let entity = match document.get("entityId") {
+                    Some(Bson::Binary(binary)) => binary.to_uuid().ok().and_then(|entity_id| ids.get(&entity_id)),
+                    _ => None
+                };
+                match entity {
+                    Some((collection, _)) if *collection == entity_collection => (),
+                    _ => return Err(bad_request(format!("Audit log {} in {} is about an entity that isn't part of the archive", id, path)))
+                }
+            }
+            audit_logs.push((collection, documents));
+        }
+
+        let mut user_ids = HashSet::new();
+        for (_, documents) in data.iter().chain(audit_logs.iter()) {
+            for document in documents {
+                collect_users(document, &mut user_ids);
+            }
+        }
+        let users: HashSet<Uuid> = find(get_main_db(client).collection(User::COLLECTION_NAME), doc! { "_id": { "$in": user_ids.into_iter().collect::<Vec<Uuid>>() } })
+            .await?
+            .iter()
+            .filter_map(document_id)
+            .collect();
+
+        // Checked after the ids are known, so references in any direction resolve
+        for (collection, documents) in data.iter_mut().chain(audit_logs.iter_mut()) {
+            let entity_collection = ARCHIVED_AUDIT_LOGS.iter().find(|(log_collection, _)| log_collection == collection).map(|(_, entity_collection)| *entity_collection);
+
+            for document in documents.iter_mut() {
+                let Some(id) = document_id(document) else { continue };
+                // Audit logs aren't referenced by anything, they only need a fresh id
+                let new_id = match ids.get(&id) {
+                    Some((_, new_id)) => *new_id,
+                    None => Uuid::new()
+                };
+
+                document.remove("_id");
+                remap(document, entity_collection.unwrap_or(collection), &ids, &users).map_err(|err| bad_request(format!("Document {} in {} references {}", id, collection, err)))?;
+                document.insert("_id", new_id);
+            }
+        }
+
+        let archived = |name: &str| data.iter().find(|(collection, _)| *collection == name).map(|(_, documents)| documents.len()).unwrap_or(0);
+        for resource in QuotaResource::ALL {
+            // The owner is the only member of an imported tenant, API keys stay behind
+            let count = match resource {
+                QuotaResource::Locations => archived(Location::COLLECTION_NAME),
+                QuotaResource::Members => 1,
+                QuotaResource::Products => archived(Product::COLLECTION_NAME),
+                QuotaResource::ApiKeys => 0
+            };
+            let limit = resource.limit(&tenant.quotas());
+            if count as i64 > limit {
+                return Err(HttpResponse {
+                    status: 403,
+                    message: format!("Quota exceeded: the tenant may have at most {} {} ({} in the archive)", limit, resource.name(), count),
+                    data: None
+                });
+            }
+        }
+
+        if let Err(err) = tenant.insert(client).await {
+            return Err(internal_error(err.message));
+        }
+
+        let mut documents = HashMap::new();
+        let mut inserted_logs: Vec<(&str, Vec<Uuid>)> = Vec::new();
+        let written: Result<(), HttpResponse<()>> = async {
+            for (collection, batch) in &data {
+                insert(get_main_db(client).collection(collection), batch).await?;
+                documents.insert(Self::data_path(collection), batch.len() as u64);
+            }
+
+            for (collection, batch) in &audit_logs {
+                inserted_logs.push((collection, batch.iter().filter_map(document_id).collect()));
+                insert(get_logs_db(client).collection(collection), batch).await?;
+                documents.insert(Self::audit_log_path(collection), batch.len() as u64);
+            }
+
+            owner.add_membership(&Membership::new(tenant.id, TenantRole::Owner), client).await.map(|_| ()).map_err(|err| internal_error(err.message))
+        }.await;
+
+        // A failed import leaves nothing behind
+        if let Err(err) = written {
+            if let Err(cleanup_err) = tenant.purge(client).await {
+                rocket::error!("{}", cleanup_err.message);
+            }
+            for (collection, log_ids) in inserted_logs {
+                if let Err(cleanup_err) = get_logs_db(client).collection::<Document>(collection).delete_many(doc! { "_id": { "$in": log_ids } }, None).await {
+                    rocket::error!("Error deleting imported audit logs: {:?}", cleanup_err);
+                }
+            }
+            return Err(err);
+        }
+
+        let mut new_values: HashMap<String, String> = documents.iter().map(|(path, count)| (path.clone(), count.to_string())).collect();
+        new_values.insert("sourceTenantId".to_string(), archived_tenant.id.to_string());
+        match AuditLog::new(tenant.id, AuditLogEntityType::Tenant, AuditLogAction::Create, "Tenant imported.".to_string(), author, None, Some(new_values)).insert(client).await {
+            Ok(_) => (),
+            Err(err) => rocket::error!("{}", err)
+        }
+
+        Ok(ImportedTenant { tenant, documents })
+    }
+}
+
+// Replaces archived ids inside `document` with their new ids. Every id has to sit in a known reference field and point into
+// one of the collections the field allows, `collection` is the collection of the top level document.
+fn remap(document: &mut Document, collection: &str, ids: &HashMap<Uuid, (&str, Uuid)>, users: &HashSet<Uuid>) -> Result<(), String> {
+    for (key, value) in document.iter_mut() {
+        if USER_REFERENCES.iter().any(|(user_key, _)| user_key == key) {
+            continue;
+        }
+
+        let values = match value {
+            Bson::Array(values) => values.iter_mut().collect::<Vec<_>>(),
+            value => vec![value]
+        };
+        for value in values {
+            let replacement = match value {
+                Bson::Document(document) => {
+                    remap(document, collection, ids, users)?;
+                    None
+                },
+                Bson::Binary(binary) if binary.subtype == BinarySubtype::Uuid => {
+                    let id = binary.to_uuid().map_err(|err| format!("an invalid {}: {}", key, err))?;
+                    let targets = match key.as_str() {
+                        "parentId" | "entityId" => vec![collection],
+                        key => REFERENCES.iter().find(|(reference, _)| *reference == key).map(|(_, targets)| targets.to_vec()).unwrap_or_default()
+                    };
+
+                    if targets.is_empty() {
+                        return Err(format!("{} {} in a field that doesn't hold references", key, id));
+                    }
+
+                    match ids.get(&id) {
+                        Some((target, new_id)) if targets.contains(target) => Some(Bson::from(*new_id)),
+                        Some((target, _)) => return Err(format!("{} {}, which is in {} instead of {}", key, id, target, targets.join(" or "))),
+                        None => return Err(format!("{} {}, which isn't part of the archive", key, id))
+                    }
+                },
+                // Audit log values keep ids as text, those that aren't known are left as they are
+                Bson::String(text) => Uuid::parse_str(text.as_str()).ok().and_then(|id| ids.get(&id)).map(|(_, new_id)| Bson::String(new_id.to_string())),
+                _ => None
+            };
+
+            if let Some(replacement) = replacement {
+                *value = replacement;
+            }
+        }
+    }
+
+    for (key, type_key) in USER_REFERENCES {
+        let id = match document.get(key) {
+            Some(Bson::Binary(binary)) if binary.subtype == BinarySubtype::Uuid => binary.to_uuid().map_err(|err| format!("an invalid {}: {}", key, err))?,
+            Some(Bson::Null) | None => continue,
+            Some(_) => return Err(format!("an invalid {}", key))
+        };
+        let author_type = match type_key.and_then(|type_key| document.get(type_key)) {
+            Some(author_type) => from_bson(author_type.clone()).map_err(|err| format!("an invalid {}: {}", key, err))?,
+            None => AuditLogAuthorType::User
+        };
+
+        let keeps_author = match author_type {
+            AuditLogAuthorType::User => users.contains(&id),
+            AuditLogAuthorType::System => id == AuditLogAuthor::SYSTEM_AUTHOR_ID,
+            AuditLogAuthorType::ApiKey => false
+        };
+        if !keeps_author {
+            document.insert(key, AuditLogAuthor::SYSTEM_AUTHOR_ID);
+            if let Some(type_key) = type_key {
+                document.insert(type_key, to_bson(&AuditLogAuthorType::System).unwrap());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Ids of all users referenced anywhere in `document`
+fn collect_users(document: &Document, users: &mut HashSet<Uuid>) {
+    for (key, value) in document {
+        match value {
+            Bson::Document(document) => collect_users(document, users),
+            Bson::Array(values) => values.iter().filter_map(Bson::as_document).for_each(|document| collect_users(document, users)),
+            Bson::Binary(binary) if USER_REFERENCES.iter().any(|(user_key, _)| user_key == key) => {
+                if let Ok(id) = binary.to_uuid() {
+                    users.insert(id);
+                }
+            },
+            _ => ()
+        }
+    }
+}
+
+fn document_id(document: &Document) -> Option<Uuid> {
+    match document.get("_id") {
+        Some(Bson::Binary(binary)) => binary.to_uuid().ok(),
+        _ => None
+    }
+}
+
+fn read_entries(archive: &[u8]) -> Result<HashMap<String, Vec<u8>>, HttpResponse<()>> {
+    let mut entries = HashMap::new();
+    let invalid = |err: std::io::Error| bad_request(format!("Invalid archive: {}", err));
+
+    for entry in tar::Archive::new(archive).entries().map_err(invalid)? {
+        let mut entry = entry.map_err(invalid)?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+
+        let path = entry.path().map_err(invalid)?.to_string_lossy().to_string();
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content).map_err(invalid)?;
+        entries.insert(path, content);
+    }
+
+    Ok(entries)
+}
+
+fn parse_lines(path: &str, content: &[u8]) -> Result<Vec<Document>, HttpResponse<()>> {
+    let content = std::str::from_utf8(content).map_err(|_| bad_request(format!("{} isn't valid UTF-8", path)))?;
+
+    let mut documents = Vec::new();
+    for (i, line) in content.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+        let invalid = |err: String| bad_request(format!("Invalid document on line {} of {}: {}", i + 1, path, err));
+
+        let value: rocket::serde::json::Value = rocket::serde::json::from_str(line).map_err(|err| invalid(err.to_string()))?;
+        match Bson::try_from(value) {
+            Ok(Bson::Document(document)) => documents.push(document),
+            Ok(_) => return Err(invalid("not an object".to_string())),
+            Err(err) => return Err(invalid(err.to_string()))
+        }
+    }
+
+    Ok(documents)
+}
+
+fn append(builder: &mut tar::Builder<Vec<u8>>, path: &str, content: &[u8]) -> Result<(), HttpResponse<()>> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(content.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime((DateTime::now().timestamp_millis() / 1000) as u64);
+    header.set_cksum();
+
+    builder.append_data(&mut header, path, content).map_err(|err| internal_error(format!("Error writing archive: {:?}", err)))
+}
+
+async fn find(db: rocket_db_pools::mongodb::Collection<Document>, filter: Document) -> Result<Vec<Document>, HttpResponse<()>> {
+    match db.find(filter, None).await {
+        Ok(cursor) => cursor
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<Vec<Document>, _>>()
+            .map_err(|err| internal_error(format!("Error reading {}: {:?}", db.name(), err))),
+        Err(err) => Err(internal_error(format!("Error fetching {}: {:?}", db.name(), err)))
+    }
+}
+
+async fn insert(db: rocket_db_pools::mongodb::Collection<Document>, documents: &[Document]) -> Result<(), HttpResponse<()>> {
+    if documents.is_empty() {
+        return Ok(());
+    }
+
+    match db.insert_many(documents, None).await {
+        Ok(_) => Ok(()),
+        Err(err) => Err(internal_error(format!("Error importing {}: {:?}", db.name(), err)))
+    }
+}
+
+fn bad_request(message: String) -> HttpResponse<()> {
+    HttpResponse { status: 400, message, data: None }
+}
+
+fn internal_error(message: String) -> HttpResponse<()> {
+    HttpResponse { status: 500, message, data: None }
+}
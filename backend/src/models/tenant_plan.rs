@@ -0,0 +1,236 @@
+use anyhow::Result;
+use mongodb::bson::{doc, DateTime};
+use rocket_db_pools::mongodb::Client;
+use rocket::serde::{Deserialize, Serialize};
+use crate::db::count_documents;
+
+use super::{api_key::ApiKey, http_response::HttpResponse, invitation::Invitation, location::Location, product::Product, tenant::Tenant, user::User};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub enum TenantPlan {
+    #[default]
+    Free,
+    Standard,
+    Enterprise
+}
+
+impl TenantPlan {
+    pub fn from_string<T>(plan: &str) -> Result<Self, HttpResponse<T>> {
+        match plan.to_uppercase().as_str() {
+            "FREE" => Ok(TenantPlan::Free),
+            "STANDARD" => Ok(TenantPlan::Standard),
+            "ENTERPRISE" => Ok(TenantPlan::Enterprise),
+            _ => Err(HttpResponse { status: 400, message: "Invalid plan provided".to_string(), data: None })
+        }
+    }
+
+    pub fn quotas(&self) -> TenantQuotas {
+        match self {
+            TenantPlan::Free => TenantQuotas {
+                max_locations: 3,
+                max_members: 5,
+                max_products: 250,
+                max_api_keys: 2,
+                audit_retention_days: 90
+            },
+            TenantPlan::Standard => TenantQuotas {
+                max_locations: 10,
+                max_members: 25,
+                max_products: 5_000,
+                max_api_keys: 10,
+                audit_retention_days: 365
+            },
+            TenantPlan::Enterprise => TenantQuotas {
+                max_locations: 100,
+                max_members: 250,
+                max_products: 100_000,
+                max_api_keys: 50,
+                audit_retention_days: 2_555
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct TenantQuotas {
+    #[serde(rename = "maxLocations")]
+    pub max_locations: i64,
+    #[serde(rename = "maxMembers")]
+    pub max_members: i64,
+    #[serde(rename = "maxProducts")]
+    pub max_products: i64,
+    #[serde(rename = "maxApiKeys")]
+    pub max_api_keys: i64,
+    // Audit logs of the tenant's entities older than this are deleted
+    #[serde(rename = "auditRetentionDays")]
+    pub audit_retention_days: i64,
+}
+
+// Limits an admin set for a single tenant, they take precedence over the plan
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct QuotaOverrides {
+    #[serde(rename = "maxLocations")]
+    pub max_locations: Option<i64>,
+    #[serde(rename = "maxMembers")]
+    pub max_members: Option<i64>,
+    #[serde(rename = "maxProducts")]
+    pub max_products: Option<i64>,
+    #[serde(rename = "maxApiKeys")]
+    pub max_api_keys: Option<i64>,
+    #[serde(rename = "auditRetentionDays")]
+    pub audit_retention_days: Option<i64>,
+}
+
+impl QuotaOverrides {
+    pub fn apply(&self, quotas: TenantQuotas) -> TenantQuotas {
+        TenantQuotas {
+            max_locations: self.max_locations.unwrap_or(quotas.max_locations),
+            max_members: self.max_members.unwrap_or(quotas.max_members),
+            max_products: self.max_products.unwrap_or(quotas.max_products),
+            max_api_keys: self.max_api_keys.unwrap_or(quotas.max_api_keys),
+            audit_retention_days: self.audit_retention_days.unwrap_or(quotas.audit_retention_days)
+        }
+    }
+
+    pub fn validate<T>(&self) -> Result<(), HttpResponse<T>> {
+        let limits = [self.max_locations, self.max_members, self.max_products, self.max_api_keys];
+        if limits.into_iter().flatten().any(|limit| limit < 0) {
+            return Err(HttpResponse {
+                status: 400,
+                message: "Quota limits must not be negative".to_string(),
+                data: None
+            });
+        }
+
+        if self.audit_retention_days.is_some_and(|days| days < 1) {
+            return Err(HttpResponse {
+                status: 400,
+                message: "Audit retention must be at least one day".to_string(),
+                data: None
+            });
+        }
+
+        Ok(())
+    }
+
+    // Entries for the audit log, overrides that aren't set are left empty
+    pub fn to_values(&self) -> Vec<(String, String)> {
+        let value = |limit: Option<i64>| limit.map(|limit| limit.to_string()).unwrap_or_default();
+
+        vec![
+            ("quotaOverrides.maxLocations".to_string(), value(self.max_locations)),
+            ("quotaOverrides.maxMembers".to_string(), value(self.max_members)),
+            ("quotaOverrides.maxProducts".to_string(), value(self.max_products)),
+            ("quotaOverrides.maxApiKeys".to_string(), value(self.max_api_keys)),
+            ("quotaOverrides.auditRetentionDays".to_string(), value(self.audit_retention_days))
+        ]
+    }
+}
+
+// Things a tenant can only create up to its quota of
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QuotaResource {
+    Locations,
+    // Pending invitations hold a seat until they are accepted, revoked or expire
+    Members,
+    Products,
+    // Revoked keys don't count
+    ApiKeys
+}
+
+impl QuotaResource {
+    pub const ALL: [QuotaResource; 4] = [QuotaResource::Locations, QuotaResource::Members, QuotaResource::Products, QuotaResource::ApiKeys];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            QuotaResource::Locations => "locations",
+            QuotaResource::Members => "members",
+            QuotaResource::Products => "products",
+            QuotaResource::ApiKeys => "API keys"
+        }
+    }
+
+    pub fn limit(&self, quotas: &TenantQuotas) -> i64 {
+        match self {
+            QuotaResource::Locations => quotas.max_locations,
+            QuotaResource::Members => quotas.max_members,
+            QuotaResource::Products => quotas.max_products,
+            QuotaResource::ApiKeys => quotas.max_api_keys
+        }
+    }
+
+    pub async fn count(&self, tenant: &Tenant, client: &Client) -> Result<i64, HttpResponse<()>> {
+        match self {
+            QuotaResource::Locations => count_documents(Location::COLLECTION_NAME, doc! { "tenantId": tenant.id }, client).await,
+            QuotaResource::Members => {
+                let members = count_documents(User::COLLECTION_NAME, doc! { "tenants.tenantId": tenant.id }, client).await?;
+                let invitations = count_documents(Invitation::COLLECTION_NAME, doc! {
+                    "tenantId": tenant.id,
+                    "acceptedAt": null,
+                    "revoked": false,
+                    "expiresAt": { "$gt": DateTime::now() }
+                }, client).await?;
+                Ok(members + invitations)
+            },
+            QuotaResource::Products => count_documents(Product::COLLECTION_NAME, doc! { "tenantId": tenant.id }, client).await,
+            QuotaResource::ApiKeys => count_documents(ApiKey::COLLECTION_NAME, doc! { "tenantId": tenant.id, "revoked": false }, client).await
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct QuotaUsage {
+    pub used: i64,
+    pub limit: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct TenantUsage {
+    pub plan: TenantPlan,
+    pub locations: QuotaUsage,
+    pub members: QuotaUsage,
+    pub products: QuotaUsage,
+    #[serde(rename = "apiKeys")]
+    pub api_keys: QuotaUsage,
+    #[serde(rename = "auditRetentionDays")]
+    pub audit_retention_days: i64,
+}
+
+impl TenantUsage {
+    pub async fn of(tenant: &Tenant, client: &Client) -> Result<Self, HttpResponse<Self>> {
+        let quotas = tenant.quotas();
+
+        let mut usage = Self {
+            plan: tenant.plan,
+            locations: QuotaUsage::default(),
+            members: QuotaUsage::default(),
+            products: QuotaUsage::default(),
+            api_keys: QuotaUsage::default(),
+            audit_retention_days: quotas.audit_retention_days
+        };
+        for resource in QuotaResource::ALL {
+            let used = resource.count(tenant, client).await.map_err(|err| HttpResponse {
+                status: err.status,
+                message: err.message,
+                data: None
+            })?;
+            *usage.of_resource(resource) = QuotaUsage { used, limit: resource.limit(&quotas) };
+        }
+
+        Ok(usage)
+    }
+
+    fn of_resource(&mut self, resource: QuotaResource) -> &mut QuotaUsage {
+        match resource {
+            QuotaResource::Locations => &mut self.locations,
+            QuotaResource::Members => &mut self.members,
+            QuotaResource::Products => &mut self.products,
+            QuotaResource::ApiKeys => &mut self.api_keys
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use std::str::FromStr;
+use chrono::{Days, Utc};
+use chrono_tz::Tz;
+use rocket::serde::{Deserialize, Serialize};
+
+use super::http_response::HttpResponse;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub enum MeasurementSystem {
+    #[default]
+    Metric,
+    Imperial
+}
+
+impl MeasurementSystem {
+    pub fn from_string<T>(system: &str) -> Result<Self, HttpResponse<T>> {
+        match system.to_uppercase().as_str() {
+            "METRIC" => Ok(MeasurementSystem::Metric),
+            "IMPERIAL" => Ok(MeasurementSystem::Imperial),
+            _ => Err(HttpResponse { status: 400, message: "Invalid measurement system provided".to_string(), data: None })
+        }
+    }
+}
+
+// How clients should display dates, the API itself always uses YYYY-MM-DD
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub enum DateFormat {
+    #[default]
+    #[serde(rename = "YYYY-MM-DD")]
+    Iso,
+    #[serde(rename = "DD.MM.YYYY")]
+    DayMonthYearDots,
+    #[serde(rename = "DD/MM/YYYY")]
+    DayMonthYearSlashes,
+    #[serde(rename = "MM/DD/YYYY")]
+    MonthDayYear
+}
+
+impl DateFormat {
+    pub fn from_string<T>(format: &str) -> Result<Self, HttpResponse<T>> {
+        match format.to_uppercase().as_str() {
+            "YYYY-MM-DD" => Ok(DateFormat::Iso),
+            "DD.MM.YYYY" => Ok(DateFormat::DayMonthYearDots),
+            "DD/MM/YYYY" => Ok(DateFormat::DayMonthYearSlashes),
+            "MM/DD/YYYY" => Ok(DateFormat::MonthDayYear),
+            _ => Err(HttpResponse { status: 400, message: "Invalid date format provided".to_string(), data: None })
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DateFormat::Iso => "YYYY-MM-DD",
+            DateFormat::DayMonthYearDots => "DD.MM.YYYY",
+            DateFormat::DayMonthYearSlashes => "DD/MM/YYYY",
+            DateFormat::MonthDayYear => "MM/DD/YYYY"
+        }
+    }
+}
+
+// Every field has its own default, so tenants created before a setting existed still load
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct TenantSettings {
+    // ISO 4217 code, monetary values of the tenant are in this currency
+    #[serde(default = "TenantSettings::default_currency")]
+    pub currency: String,
+    // IANA name, days in reports start and end at midnight in this timezone
+    #[serde(default = "TenantSettings::default_timezone")]
+    pub timezone: String,
+    #[serde(rename = "measurementSystem", default)]
+    pub measurement_system: MeasurementSystem,
+    // Batches expiring within this many days show up in the expiry report
+    #[serde(rename = "expiryWarningDays", default = "TenantSettings::default_expiry_warning_days")]
+    pub expiry_warning_days: i64,
+    #[serde(rename = "dateFormat", default)]
+    pub date_format: DateFormat,
+    // BCP 47 tag like `en` or `de-AT`
+    #[serde(default = "TenantSettings::default_language")]
+    pub language: String,
+}
+
+impl Default for TenantSettings {
+    fn default() -> Self {
+        Self {
+            currency: Self::default_currency(),
+            timezone: Self::default_timezone(),
+            measurement_system: MeasurementSystem::default(),
+            expiry_warning_days: Self::default_expiry_warning_days(),
+            date_format: DateFormat::default(),
+            language: Self::default_language()
+        }
+    }
+}
+
+impl TenantSettings {
+    pub fn default_currency() -> String {
+        "EUR".to_string()
+    }
+
+    fn default_timezone() -> String {
+        "UTC".to_string()
+    }
+
+    fn default_expiry_warning_days() -> i64 {
+        30
+    }
+
+    fn default_language() -> String {
+        "en".to_string()
+    }
+
+    pub fn normalize_currency<T>(currency: &str) -> Result<String, HttpResponse<T>> {
+        let currency = currency.trim().to_uppercase();
+
+        if currency.len() != 3 || !currency.chars().all(|c| c.is_ascii_uppercase()) {
+            return Err(HttpResponse {
+                status: 400,
+                message: "Currency must be a three letter ISO 4217 code".to_string(),
+                data: None
+            });
+        }
+
+        Ok(currency)
+    }
+
+    pub fn normalize_timezone<T>(timezone: &str) -> Result<String, HttpResponse<T>> {
+        match Tz::from_str(timezone.trim()) {
+            Ok(tz) => Ok(tz.name().to_string()),
+            Err(_) => Err(HttpResponse {
+                status: 400,
+                message: "Timezone must be an IANA timezone name like Europe/Vienna".to_string(),
+                data: None
+            })
+        }
+    }
+
+    pub fn validate_expiry_warning_days<T>(days: i64) -> Result<i64, HttpResponse<T>> {
+        if !(1..=365).contains(&days) {
+            return Err(HttpResponse {
+                status: 400,
+                message: "Expiry warning window must be between 1 and 365 days".to_string(),
+                data: None
+            });
+        }
+
+        Ok(days)
+    }
+
+    // Accepts a language with an optional region and brings it into the usual casing, e.g. `de-at` becomes `de-AT`
+    pub fn normalize_language<T>(language: &str) -> Result<String, HttpResponse<T>> {
+        let invalid = || HttpResponse {
+            status: 400,
+            message: "Language must be a tag like en or de-AT".to_string(),
+            data: None
+        };
+
+        let mut parts = language.trim().split(['-', '_']);
+        let primary = parts.next().unwrap_or_default();
+        if !(2..=3).contains(&primary.len()) || !primary.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(invalid());
+        }
+
+        let region = match parts.next() {
+            Some(region) if region.len() == 2 && region.chars().all(|c| c.is_ascii_alphabetic()) => Some(region.to_uppercase()),
+            Some(region) if region.len() == 3 && region.chars().all(|c| c.is_ascii_digit()) => Some(region.to_string()),
+            Some(_) => return Err(invalid()),
+            None => None
+        };
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+
+        Ok(match region {
+            Some(region) => format!("{}-{}", primary.to_lowercase(), region),
+            None => primary.to_lowercase()
+        })
+    }
+
+    fn tz(&self) -> Tz {
+        Tz::from_str(&self.timezone).unwrap_or(Tz::UTC)
+    }
+
+    // The current date in the tenant's timezone as `YYYY-MM-DD`
+    pub fn today(&self) -> String {
+        self.date_in_days(0)
+    }
+
+    // The date `days` from today in the tenant's timezone, negative values go back in time
+    pub fn date_in_days(&self, days: i64) -> String {
+        let today = Utc::now().with_timezone(&self.tz()).date_naive();
+        let date = match days >= 0 {
+            true => today.checked_add_days(Days::new(days as u64)),
+            false => today.checked_sub_days(Days::new(days.unsigned_abs()))
+        };
+        date.unwrap_or(today).format("%Y-%m-%d").to_string()
+    }
+}
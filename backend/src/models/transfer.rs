@@ -0,0 +1,466 @@
+use anyhow::Result;
+use mongodb::bson::{doc, to_bson, DateTime, Uuid};
+use rocket_db_pools::{mongodb::{Client, Collection}, Connection};
+use rocket::{futures::StreamExt, serde::{Deserialize, Serialize}};
+use crate::db::{get_main_db, ShelfWatcherDatabase};
+
+use super::{audit_log::AuditLogAuthor, http_response::HttpResponse, product::Product, product_batch::ProductBatch, stock_lock::StockLock, stock_movement::{StockMovement, StockMovementType}};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub enum TransferStatus {
+    Draft,
+    Shipped,
+    Received
+}
+
+impl TransferStatus {
+    pub fn from_string<T>(status: &str) -> Result<Self, HttpResponse<T>> {
+        match status.to_uppercase().as_str() {
+            "DRAFT" => Ok(TransferStatus::Draft),
+            "SHIPPED" => Ok(TransferStatus::Shipped),
+            "RECEIVED" => Ok(TransferStatus::Received),
+            _ => Err(HttpResponse { status: 400, message: "Invalid transfer status provided".to_string(), data: None })
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct TransferLine {
+    #[serde(rename = "productId")]
+    pub product_id: Uuid,
+    #[serde(rename = "batchId")]
+    pub batch_id: Option<Uuid>,
+    // Shipped quantity
+    pub quantity: f64,
+    // Set when the transfer is received
+    #[serde(rename = "receivedQuantity")]
+    pub received_quantity: Option<f64>,
+    // Received minus shipped quantity, negative when goods went missing on the way
+    pub discrepancy: Option<f64>,
+}
+
+// A line of a shipped transfer that hasn't arrived yet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct TransitLine {
+    #[serde(rename = "transferId")]
+    pub transfer_id: Uuid,
+    #[serde(rename = "sourceLocationId")]
+    pub source_location_id: Uuid,
+    #[serde(rename = "destinationLocationId")]
+    pub destination_location_id: Uuid,
+    #[serde(rename = "productId")]
+    pub product_id: Uuid,
+    #[serde(rename = "batchId")]
+    pub batch_id: Option<Uuid>,
+    pub quantity: f64,
+    #[serde(rename = "shippedAt")]
+    pub shipped_at: Option<String>,
+}
+
+// Moves stock from one location of a tenant to another. Shipping takes the goods out of the source,
+// receiving books them into the destination, in between they are in transit and in neither location's stock.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Transfer {
+    #[serde(rename = "_id")]
+    pub id: Uuid,
+    #[serde(rename = "tenantId")]
+    pub tenant_id: Uuid,
+    #[serde(rename = "sourceLocationId")]
+    pub source_location_id: Uuid,
+    #[serde(rename = "destinationLocationId")]
+    pub destination_location_id: Uuid,
+    pub lines: Vec<TransferLine>,
+    pub status: TransferStatus,
+    pub note: Option<String>,
+    #[serde(rename = "shippedAt")]
+    pub shipped_at: Option<String>,
+    #[serde(rename = "receivedAt")]
+    pub received_at: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+}
+
+impl Transfer {
+    pub const COLLECTION_NAME: &'static str = "transfers";
+
+    pub fn new(tenant_id: Uuid, source_location_id: Uuid, destination_location_id: Uuid, lines: Vec<TransferLine>, note: Option<String>) -> Self {
+        Self {
+            id: Uuid::new(),
+            tenant_id,
+            source_location_id,
+            destination_location_id,
+            lines,
+            status: TransferStatus::Draft,
+            note,
+            shipped_at: None,
+            received_at: None,
+            created_at: DateTime::now().to_string(),
+        }
+    }
+
+    // Checks the locations and that every line has a positive quantity of an existing product and matching open, unexpired batch.
+    // A product/batch combination may only appear once.
+    #[allow(unused)]
+    pub async fn validate(&self, today: &str, connection: &Connection<ShelfWatcherDatabase>) -> Result<(), HttpResponse<Self>> {
+        if self.source_location_id == self.destination_location_id {
+            return Err(HttpResponse {
+                status: 400,
+                message: "Source and destination location must differ".to_string(),
+                data: None
+            });
+        }
+
+        if self.lines.is_empty() {
+            return Err(HttpResponse {
+                status: 400,
+                message: "A transfer needs at least one line".to_string(),
+                data: None
+            });
+        }
+
+        for (i, line) in self.lines.iter().enumerate() {
+            if line.quantity <= 0.0 || !line.quantity.is_finite() {
+                return Err(HttpResponse {
+                    status: 400,
+                    message: "Line quantities must be greater than zero".to_string(),
+                    data: None
+                });
+            }
+
+            if self.lines[..i].iter().any(|other| other.product_id == line.product_id && other.batch_id == line.batch_id) {
+                return Err(HttpResponse {
+                    status: 400,
+                    message: "Each product and batch may only appear once".to_string(),
+                    data: None
+                });
+            }
+
+            if let Err(err) = Product::get_from_tenant(line.product_id, self.tenant_id, connection).await {
+                return Err(HttpResponse {
+                    status: err.status,
+                    message: err.message,
+                    data: None
+                });
+            }
+
+            if let Some(batch_id) = line.batch_id {
+                match ProductBatch::get_from_tenant(batch_id, self.tenant_id, connection).await {
+                    Ok(batch) if batch.product_id != line.product_id => return Err(HttpResponse {
+                        status: 400,
+                        message: "Batch belongs to another product".to_string(),
+                        data: None
+                    }),
+                    Ok(batch) if batch.closed => return Err(HttpResponse {
+                        status: 400,
+                        message: format!("Batch {} is closed", batch.lot_number),
+                        data: None
+                    }),
+                    Ok(batch) => batch.require_not_expired(today)?,
+                    Err(err) => return Err(HttpResponse {
+                        status: err.status,
+                        message: err.message,
+                        data: None
+                    })
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Takes the goods out of the source location. Fails without booking anything if any line isn't in stock.
+    // The status is claimed before the movements are booked, so a repeated or concurrent call can't book them twice.
+    pub async fn ship(&mut self, author: AuditLogAuthor, connection: &Connection<ShelfWatcherDatabase>) -> Result<(), HttpResponse<Self>> {
+        if self.status != TransferStatus::Draft {
+            return Err(HttpResponse {
+                status: 400,
+                message: "Only draft transfers can be shipped".to_string(),
+                data: None
+            });
+        }
+
+        let mut movements = Vec::new();
+        for line in &self.lines {
+            movements.push(StockMovement::new::<Self>(self.tenant_id, StockMovementType::Transfer, line.product_id, line.batch_id, Some(self.source_location_id), None, None, None, line.quantity, "TRANSFER_SHIPPED", Some(self.id), author)?);
+        }
+
+        // Every line's product stays locked at the source until all movements are booked, see `StockLock`
+        let keys = self.lines.iter().map(|line| (line.product_id, self.source_location_id)).collect();
+        let locks = match StockLock::acquire_all(self.tenant_id, keys, connection).await {
+            Ok(locks) => locks,
+            Err(err) => return Err(HttpResponse {
+                status: err.status,
+                message: err.message,
+                data: None
+            })
+        };
+
+        let shipped = self.book_shipment(movements, connection).await;
+        StockLock::release_all(locks, connection).await;
+        shipped
+    }
+
+    async fn book_shipment(&mut self, movements: Vec<StockMovement>, connection: &Connection<ShelfWatcherDatabase>) -> Result<(), HttpResponse<Self>> {
+        for movement in &movements {
+            movement.check_available::<Self>(connection).await?;
+        }
+
+        let draft = self.clone();
+        self.status = TransferStatus::Shipped;
+        self.shipped_at = Some(DateTime::now().to_string());
+        if !self.replace_if(TransferStatus::Draft, connection).await? {
+            return Err(HttpResponse {
+                status: 400,
+                message: "Only draft transfers can be shipped".to_string(),
+                data: None
+            });
+        }
+
+        self.book_or_roll_back(draft, &movements, connection).await
+    }
+
+    // Books the movements of a claimed status change, or puts the transfer back to `previous` if they can't all be booked
+    async fn book_or_roll_back(&mut self, previous: Self, movements: &[StockMovement], connection: &Connection<ShelfWatcherDatabase>) -> Result<(), HttpResponse<Self>> {
+        let err = match StockMovement::insert_all(movements, connection).await {
+            Ok(_) => return Ok(()),
+            Err(err) => err
+        };
+
+        match previous.replace_if(self.status, connection).await {
+            Ok(true) => (),
+            Ok(false) => rocket::error!("Transfer {} changed before its claim could be rolled back", self.id),
+            Err(err) => rocket::error!("{}", err.message)
+        }
+        *self = previous;
+
+        Err(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    }
+
+    // Books the received quantities into the destination. Lines missing from `received` arrived in full.
+    // Like shipping, the status is claimed before anything is booked.
+    pub async fn receive(&mut self, received: &[(Uuid, Option<Uuid>, f64)], author: AuditLogAuthor, connection: &Connection<ShelfWatcherDatabase>) -> Result<(), HttpResponse<Self>> {
+        if self.status != TransferStatus::Shipped {
+            return Err(HttpResponse {
+                status: 400,
+                message: "Only shipped transfers can be received".to_string(),
+                data: None
+            });
+        }
+
+        for (product_id, batch_id, quantity) in received {
+            if *quantity < 0.0 || !quantity.is_finite() {
+                return Err(HttpResponse {
+                    status: 400,
+                    message: "Received quantities must not be negative".to_string(),
+                    data: None
+                });
+            }
+            if !self.lines.iter().any(|line| line.product_id == *product_id && line.batch_id == *batch_id) {
+                return Err(HttpResponse {
+                    status: 400,
+                    message: format!("Product {} with this batch is not part of the transfer", product_id),
+                    data: None
+                });
+            }
+        }
+
+        let shipped = self.clone();
+        for line in self.lines.iter_mut() {
+            let received_quantity = received
+                .iter()
+                .find(|(product_id, batch_id, _)| *product_id == line.product_id && *batch_id == line.batch_id)
+                .map(|(_, _, quantity)| *quantity)
+                .unwrap_or(line.quantity);
+
+            line.received_quantity = Some(received_quantity);
+            line.discrepancy = Some(received_quantity - line.quantity);
+        }
+
+        let mut movements = Vec::new();
+        for line in &self.lines {
+            let received_quantity = line.received_quantity.unwrap_or_default();
+            if received_quantity == 0.0 {
+                continue;
+            }
+
+            movements.push(StockMovement::new::<Self>(self.tenant_id, StockMovementType::Transfer, line.product_id, line.batch_id, None, Some(self.destination_location_id), None, None, received_quantity, "TRANSFER_RECEIVED", Some(self.id), author)?);
+        }
+
+        self.status = TransferStatus::Received;
+        self.received_at = Some(DateTime::now().to_string());
+        if !self.replace_if(TransferStatus::Shipped, connection).await? {
+            return Err(HttpResponse {
+                status: 400,
+                message: "Only shipped transfers can be received".to_string(),
+                data: None
+            });
+        }
+
+        self.book_or_roll_back(shipped, &movements, connection).await
+    }
+
+    pub fn has_discrepancies(&self) -> bool {
+        self.lines.iter().any(|line| line.discrepancy.is_some_and(|discrepancy| discrepancy != 0.0))
+    }
+
+    #[allow(unused)]
+    pub async fn get_from_tenant(id: Uuid, tenant_id: Uuid, connection: &Connection<ShelfWatcherDatabase>) -> Result<Self, HttpResponse<Self>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "_id": id,
+            "tenantId": tenant_id
+        };
+        match db.find_one(filter, None).await.unwrap() {
+            Some(transfer) => Ok(transfer),
+            None => Err(HttpResponse {
+                status: 404,
+                message: "Transfer not found".to_string(),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn get_all_from_tenant(tenant_id: Uuid, status: Option<TransferStatus>, location_id: Option<Uuid>, client: &Client) -> Result<Vec<Self>, HttpResponse<Vec<Self>>> {
+        let db = Self::get_collection(client);
+
+        let mut filter = doc! {
+            "tenantId": tenant_id
+        };
+        if let Some(status) = status {
+            filter.insert("status", to_bson(&status).unwrap());
+        }
+        if let Some(location_id) = location_id {
+            filter.insert("$or", vec![
+                doc! { "sourceLocationId": location_id },
+                doc! { "destinationLocationId": location_id }
+            ]);
+        }
+        match db.find(filter, None).await {
+            Ok(cursor) => {
+                let transfers = cursor.map(|doc| doc.unwrap()).collect::<Vec<Self>>().await;
+                Ok(transfers)
+            },
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error fetching transfers from tenant: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    // Lines of all shipped but not yet received transfers
+    #[allow(unused)]
+    pub async fn get_in_transit(tenant_id: Uuid, product_id: Option<Uuid>, location_id: Option<Uuid>, client: &Client) -> Result<Vec<TransitLine>, HttpResponse<Vec<TransitLine>>> {
+        let transfers = Self::get_all_from_tenant(tenant_id, Some(TransferStatus::Shipped), location_id, client).await.map_err(|err| HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })?;
+
+        Ok(transfers
+            .into_iter()
+            .flat_map(|transfer| {
+                let Transfer { id, source_location_id, destination_location_id, shipped_at, lines, .. } = transfer;
+                lines.into_iter().map(move |line| TransitLine {
+                    transfer_id: id,
+                    source_location_id,
+                    destination_location_id,
+                    product_id: line.product_id,
+                    batch_id: line.batch_id,
+                    quantity: line.quantity,
+                    shipped_at: shipped_at.clone()
+                })
+            })
+            .filter(|line| product_id.is_none_or(|product_id| product_id == line.product_id))
+            .collect())
+    }
+
+    #[allow(unused)]
+    pub async fn insert(&self, connection: &Connection<ShelfWatcherDatabase>) -> Result<Self, HttpResponse<Self>> {
+        let db = Self::get_collection(connection);
+
+        match db.insert_one(self.clone(), None).await {
+            Ok(_) => Ok(self.clone()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error inserting transfer: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    pub async fn update(&self, connection: &Connection<ShelfWatcherDatabase>) -> Result<Self, HttpResponse<Self>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "_id": self.id
+        };
+        match db.replace_one(filter, self.clone(), None).await {
+            Ok(_) => Ok(self.clone()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error updating transfer: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    // Writes the transfer only while it still has `status` in the database. Returns false if another request changed it first.
+    pub async fn replace_if(&self, status: TransferStatus, connection: &Connection<ShelfWatcherDatabase>) -> Result<bool, HttpResponse<Self>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "_id": self.id,
+            "status": to_bson(&status).unwrap()
+        };
+        match db.replace_one(filter, self.clone(), None).await {
+            Ok(result) => Ok(result.matched_count == 1),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error updating transfer: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    // Only drafts can be deleted, a transfer shipped in the meantime stays
+    #[allow(unused)]
+    pub async fn delete(&self, connection: &Connection<ShelfWatcherDatabase>) -> Result<Self, HttpResponse<()>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "_id": self.id,
+            "status": to_bson(&TransferStatus::Draft).unwrap()
+        };
+        match db.delete_one(filter, None).await {
+            Ok(result) if result.deleted_count == 0 => Err(HttpResponse {
+                status: 400,
+                message: "Only draft transfers can be deleted".to_string(),
+                data: None
+            }),
+            Ok(_) => Ok(self.clone()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error deleting transfer: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    // Takes the client so the low stock job can read transfers too
+    fn get_collection(client: &Client) -> Collection<Self> {
+        let db = get_main_db(client);
+        db.collection(Self::COLLECTION_NAME)
+    }
+}
@@ -1,11 +1,12 @@
 use anyhow::Result;
-use mongodb::bson::{doc, DateTime, Uuid};
+use mongodb::bson::{doc, to_bson, Bson, DateTime, Document, Uuid};
 use pwhash::bcrypt;
-use rocket_db_pools::{mongodb::Collection, Connection};
+use rocket_db_pools::{mongodb::{options::{FindOneAndUpdateOptions, ReturnDocument}, Client, Collection}, Connection};
 use rocket::{futures::StreamExt, serde::{Deserialize, Serialize}};
-use crate::db::{get_main_db, ShelfWatcherDatabase};
+use totp_rs::{Algorithm, Secret, TOTP};
+use crate::{db::{get_main_db, ShelfWatcherDatabase}, middleware::auth::{generate_token, hash_token}};
 
-use super::http_response::HttpResponse;
+use super::{http_response::HttpResponse, membership::{Membership, TenantRole}, tenant::Tenant};
 
 #[derive(Debug, Clone, Serialize, Deserialize)] 
 #[serde(crate = "rocket::serde")] 
@@ -13,15 +14,21 @@ pub struct User {
     #[serde(rename = "_id")]
     pub id: Uuid,
     pub email: String,
+    #[serde(rename = "emailVerified", default)]
+    pub email_verified: bool,
     #[serde(rename = "passwordHash")]
     pub password_hash: String,
     #[serde(rename = "totpSecret")]
     pub totp_secret: Option<String>,
+    #[serde(rename = "totpPendingSecret", default)]
+    pub totp_pending_secret: Option<String>,
+    #[serde(rename = "recoveryCodes", default)]
+    pub recovery_codes: Vec<String>,
     #[serde(rename = "firstName")]
     pub first_name: String,
     #[serde(rename = "lastName")]
     pub last_name: String,
-    pub tenants: Vec<Uuid>,
+    pub tenants: Vec<Membership>,
     pub disabled: bool,
     #[serde(rename = "isAdmin")]
     pub is_admin: bool,
@@ -35,11 +42,15 @@ pub struct UserMinimal {
     #[serde(rename = "_id")]
     pub id: Uuid,
     pub email: String,
+    #[serde(rename = "emailVerified", default)]
+    pub email_verified: bool,
     #[serde(rename = "firstName")]
     pub first_name: String,
     #[serde(rename = "lastName")]
     pub last_name: String,
-    pub tenants: Vec<Uuid>,
+    pub tenants: Vec<Membership>,
+    #[serde(rename = "twoFactorEnabled", default)]
+    pub two_factor_enabled: bool,
     pub disabled: bool,
     #[serde(rename = "isAdmin")]
     pub is_admin: bool,
@@ -48,8 +59,12 @@ pub struct UserMinimal {
 }
 
 impl UserMinimal {
+    pub fn membership(&self, tenant_id: Uuid) -> Option<&Membership> {
+        self.tenants.iter().find(|membership| membership.tenant_id == tenant_id)
+    }
+
     pub async  fn to_full(&self, connection: &Connection<ShelfWatcherDatabase>) -> Result<User, HttpResponse<UserMinimal>> {
-        User::get_full_by_id(self.id.clone(), connection).await
+        User::get_full_by_id(self.id, connection).await
     }
 }
 
@@ -70,8 +85,11 @@ impl User {
         Ok(Self {
             id: Uuid::new(),
             email,
+            email_verified: false,
             password_hash,
             totp_secret: None,
+            totp_pending_secret: None,
+            recovery_codes: Vec::new(),
             first_name,
             last_name,
             tenants: Vec::new(),
@@ -83,20 +101,98 @@ impl User {
 
     pub fn to_minimal(&self) -> UserMinimal {
         UserMinimal {
-            id: self.id.clone(),
+            id: self.id,
             email: self.email.clone(),
+            email_verified: self.email_verified,
             first_name: self.first_name.clone(),
             last_name: self.last_name.clone(),
             tenants: self.tenants.clone(),
-            disabled: self.disabled.clone(),
-            is_admin: self.is_admin.clone(),
+            two_factor_enabled: self.totp_secret.is_some(),
+            disabled: self.disabled,
+            is_admin: self.is_admin,
             created_at: self.created_at.clone()
         }
     }
 
+    pub const TOTP_ISSUER: &'static str = "ShelfWatcher";
+    pub const RECOVERY_CODE_COUNT: usize = 10;
+
+    pub fn membership(&self, tenant_id: Uuid) -> Option<&Membership> {
+        self.tenants.iter().find(|membership| membership.tenant_id == tenant_id)
+    }
+
+    pub fn generate_totp_secret() -> String {
+        match Secret::generate_secret().to_encoded() {
+            Secret::Encoded(secret) => secret,
+            Secret::Raw(_) => unreachable!()
+        }
+    }
+
+    fn build_totp(&self, secret: &str) -> Option<TOTP> {
+        let bytes = Secret::Encoded(secret.to_string()).to_bytes().ok()?;
+        TOTP::new(Algorithm::SHA1, 6, 1, 30, bytes, Some(Self::TOTP_ISSUER.to_string()), self.email.clone()).ok()
+    }
+
+    pub fn totp_provisioning_uri(&self, secret: &str) -> Option<String> {
+        self.build_totp(secret).map(|totp| totp.get_url())
+    }
+
+    fn check_totp(&self, secret: &Option<String>, code: &str) -> bool {
+        match secret.as_ref().and_then(|secret| self.build_totp(secret)) {
+            Some(totp) => totp.check_current(code.trim()).unwrap_or(false),
+            None => false
+        }
+    }
+
+    pub fn verify_totp(&self, code: &str) -> bool {
+        self.check_totp(&self.totp_secret, code)
+    }
+
+    pub fn verify_pending_totp(&self, code: &str) -> bool {
+        self.check_totp(&self.totp_pending_secret, code)
+    }
+
+    // Replaces all recovery codes, only the hashes are kept. The plain codes are returned once.
+    pub fn generate_recovery_codes(&mut self) -> Vec<String> {
+        let codes: Vec<String> = (0..Self::RECOVERY_CODE_COUNT).map(|_| generate_token(10)).collect();
+        self.recovery_codes = codes.iter().map(|code| hash_token(code)).collect();
+        codes
+    }
+
+    // Removes the recovery code in a single write, so concurrent logins can't both use it. Returns whether it was unused.
+    pub async fn consume_recovery_code(&self, code: &str, connection: &Client) -> Result<bool, HttpResponse<UserMinimal>> {
+        let db = Self::get_collection(connection);
+
+        let hash = hash_token(code.trim());
+        let filter = doc! {
+            "_id": self.id,
+            "recoveryCodes": &hash
+        };
+        let update = doc! {
+            "$pull": { "recoveryCodes": &hash }
+        };
+        match db.update_one(filter, update, None).await {
+            Ok(result) => Ok(result.modified_count == 1),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error consuming recovery code: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    // Accepts either a current TOTP code or an unused recovery code, which is used up
+    pub async fn verify_second_factor(&self, totp_code: Option<&str>, recovery_code: Option<&str>, connection: &Client) -> Result<bool, HttpResponse<UserMinimal>> {
+        match (totp_code, recovery_code) {
+            (Some(code), _) => Ok(self.verify_totp(code)),
+            (None, Some(code)) => self.consume_recovery_code(code, connection).await,
+            (None, None) => Ok(false)
+        }
+    }
+
     // ONLY USE THIS INTERNALLY!
     #[allow(unused)]
-    pub async fn get_full_by_id(id: Uuid, connection: &Connection<ShelfWatcherDatabase>) -> Result<User, HttpResponse<UserMinimal>> {
+    pub async fn get_full_by_id(id: Uuid, connection: &Client) -> Result<User, HttpResponse<UserMinimal>> {
         let db = Self::get_collection(connection);
 
         let filter = doc! {
@@ -112,6 +208,24 @@ impl User {
         }
     }
 
+    // ONLY USE THIS INTERNALLY!
+    #[allow(unused)]
+    pub async fn get_full_by_email(email: &str, connection: &Connection<ShelfWatcherDatabase>) -> Result<User, HttpResponse<UserMinimal>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "email": email
+        };
+        match db.find_one(filter, None).await.unwrap() {
+            Some(user) => Ok(user),
+            None => Err(HttpResponse {
+                status: 404,
+                message: "User not found".to_string(),
+                data: None
+            })
+        }
+    }
+
     #[allow(unused)]
     pub async fn get_by_id(id: Uuid, connection: &Connection<ShelfWatcherDatabase>) -> Result<UserMinimal, HttpResponse<UserMinimal>> {
         let db = Self::get_collection(connection);
@@ -154,7 +268,7 @@ impl User {
             Ok(cursor) => {
                 let users = cursor.map(|doc| {
                     let user: User = doc.unwrap();
-                    return user.to_minimal();
+                    user.to_minimal()
                 }).collect::<Vec<UserMinimal>>().await;
                 Ok(users)
             },
@@ -166,6 +280,32 @@ impl User {
         }
     }
 
+    #[allow(unused)]
+    pub async fn get_all_from_tenant(tenant_id: Uuid, include_disabled: bool, connection: &Connection<ShelfWatcherDatabase>) -> Result<Vec<UserMinimal>, HttpResponse<Vec<UserMinimal>>> {
+        let db = Self::get_collection(connection);
+
+        let mut filter = doc! {
+            "tenants.tenantId": tenant_id
+        };
+        if !include_disabled {
+            filter.insert("disabled", false);
+        }
+        match db.find(filter, None).await {
+            Ok(cursor) => {
+                let users = cursor.map(|doc| {
+                    let user: User = doc.unwrap();
+                    user.to_minimal()
+                }).collect::<Vec<UserMinimal>>().await;
+                Ok(users)
+            },
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error fetching users from tenant: {:?}", err),
+                data: None
+            })
+        }
+    }
+
     #[allow(unused)]
     pub async fn insert(&self, connection: &Connection<ShelfWatcherDatabase>) -> Result<UserMinimal, HttpResponse<UserMinimal>> {
         let db = Self::get_collection(connection);
@@ -181,7 +321,7 @@ impl User {
     }
 
     #[allow(unused)]
-    pub async fn update(&self, connection: &Connection<ShelfWatcherDatabase>) -> Result<UserMinimal, HttpResponse<UserMinimal>> {
+    pub async fn update(&self, connection: &Client) -> Result<UserMinimal, HttpResponse<UserMinimal>> {
         let db = Self::get_collection(connection);
 
         let filter = doc! {
@@ -241,6 +381,237 @@ impl User {
         }
     }
 
+    // Sets only the given fields, so changes made to other fields meanwhile aren't undone. Returns the updated user.
+    pub async fn update_fields(&self, fields: Document, connection: &Client) -> Result<UserMinimal, HttpResponse<UserMinimal>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "_id": self.id
+        };
+        let update = doc! {
+            "$set": fields
+        };
+        let options = FindOneAndUpdateOptions::builder().return_document(ReturnDocument::After).build();
+        match db.find_one_and_update(filter, update, options).await {
+            Ok(Some(user)) => Ok(user.to_minimal()),
+            Ok(None) => Err(HttpResponse {
+                status: 404,
+                message: "User not found".to_string(),
+                data: None
+            }),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error updating user: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    // Writes only the two-factor fields, so changes made to the user meanwhile aren't undone
+    pub async fn update_two_factor(&self, connection: &Client) -> Result<UserMinimal, HttpResponse<UserMinimal>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "_id": self.id
+        };
+        let update = doc! {
+            "$set": {
+                "totpSecret": &self.totp_secret,
+                "totpPendingSecret": &self.totp_pending_secret,
+                "recoveryCodes": &self.recovery_codes
+            }
+        };
+        match db.update_one(filter, update, None).await {
+            Ok(_) => Ok(self.to_minimal()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error updating two-factor authentication: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    // Marks the address as verified, unless it was changed since `email` was sent a token
+    pub async fn mark_email_verified(&self, email: &str, connection: &Client) -> Result<(), HttpResponse<UserMinimal>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "_id": self.id,
+            "email": email
+        };
+        let update = doc! {
+            "$set": { "emailVerified": true }
+        };
+        match db.update_one(filter, update, None).await {
+            Ok(_) => Ok(()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error verifying email address: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    // Adds the membership unless the user already has one for its tenant. Returns the updated user, or None if it was a member already.
+    pub async fn add_membership(&self, membership: &Membership, connection: &Client) -> Result<Option<UserMinimal>, HttpResponse<UserMinimal>> {
+        let tenant_id = membership.tenant_id;
+        let membership = match to_bson(membership) {
+            Ok(membership) => membership,
+            Err(err) => return Err(HttpResponse {
+                status: 500,
+                message: format!("Error adding membership: {:?}", err),
+                data: None
+            })
+        };
+
+        let filter = doc! {
+            "_id": self.id,
+            "tenants.tenantId": { "$ne": tenant_id }
+        };
+        let update = doc! {
+            "$push": { "tenants": membership }
+        };
+        self.update_memberships(filter, update, connection).await
+    }
+
+    // Changes the role in the membership of the tenant, if the user still has `from` there (or any role if `from` is None).
+    // Returns the updated user, or None if the membership didn't match.
+    pub async fn set_membership_role(&self, tenant_id: Uuid, from: Option<TenantRole>, to: TenantRole, connection: &Client) -> Result<Option<UserMinimal>, HttpResponse<UserMinimal>> {
+        let mut membership = doc! { "tenantId": tenant_id };
+        if let Some(from) = from {
+            membership.insert("role", Self::role_bson(from)?);
+        }
+
+        let filter = doc! {
+            "_id": self.id,
+            "tenants": { "$elemMatch": membership }
+        };
+        let update = doc! {
+            "$set": { "tenants.$.role": Self::role_bson(to)? }
+        };
+        self.update_memberships(filter, update, connection).await
+    }
+
+    // Removes the membership of the tenant if the user still has `role` there. Returns the updated user, or None if it didn't match.
+    pub async fn remove_membership(&self, tenant_id: Uuid, role: TenantRole, connection: &Client) -> Result<Option<UserMinimal>, HttpResponse<UserMinimal>> {
+        let filter = doc! {
+            "_id": self.id,
+            "tenants": { "$elemMatch": { "tenantId": tenant_id, "role": Self::role_bson(role)? } }
+        };
+        let update = doc! {
+            "$pull": { "tenants": { "tenantId": tenant_id } }
+        };
+        self.update_memberships(filter, update, connection).await
+    }
+
+    // Memberships are only written with atomic updates, replacing the user could undo concurrent changes to other fields
+    async fn update_memberships(&self, filter: Document, update: Document, connection: &Client) -> Result<Option<UserMinimal>, HttpResponse<UserMinimal>> {
+        let db = Self::get_collection(connection);
+
+        let options = FindOneAndUpdateOptions::builder().return_document(ReturnDocument::After).build();
+        match db.find_one_and_update(filter, update, options).await {
+            Ok(user) => Ok(user.map(|user| user.to_minimal())),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error updating memberships: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    fn role_bson(role: TenantRole) -> Result<Bson, HttpResponse<UserMinimal>> {
+        to_bson(&role).map_err(|err| HttpResponse {
+            status: 500,
+            message: format!("Error serializing role: {:?}", err),
+            data: None
+        })
+    }
+
+    // Removes the tenant from every member's memberships, returns how many users were members
+    pub async fn remove_membership_from_all(tenant_id: Uuid, client: &Client) -> Result<u64, HttpResponse<()>> {
+        let db = Self::get_collection(client);
+
+        let filter = doc! {
+            "tenants.tenantId": tenant_id
+        };
+        let update = doc! {
+            "$pull": { "tenants": { "tenantId": tenant_id } }
+        };
+        match db.update_many(filter, update, None).await {
+            Ok(result) => Ok(result.modified_count),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error removing memberships: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    // Users used to store plain tenant ids. Those become memberships, as owner of the tenants they own and as clerk of the others,
+    // and ids of tenants that no longer exist are dropped. Returns how many users were migrated.
+    pub async fn migrate_legacy_memberships(client: &Client) -> Result<u64, HttpResponse<()>> {
+        let db = get_main_db(client).collection::<Document>(Self::COLLECTION_NAME);
+        let tenants = get_main_db(client).collection::<Document>(Tenant::COLLECTION_NAME);
+
+        let error = |err: rocket_db_pools::mongodb::error::Error| HttpResponse {
+            status: 500,
+            message: format!("Error migrating user memberships: {:?}", err),
+            data: None
+        };
+
+        let filter = doc! {
+            "tenants": { "$elemMatch": { "$type": "binData" } }
+        };
+        let mut cursor = db.find(filter, None).await.map_err(error)?;
+
+        let mut migrated = 0;
+        while let Some(user) = cursor.next().await {
+            let user = user.map_err(error)?;
+            let (Some(Bson::Binary(user_id)), Ok(legacy)) = (user.get("_id"), user.get_array("tenants")) else { continue };
+            let Ok(user_id) = user_id.to_uuid() else { continue };
+
+            let mut memberships = Vec::new();
+            for entry in legacy {
+                let tenant_id = match entry {
+                    Bson::Binary(binary) => match binary.to_uuid() {
+                        Ok(tenant_id) => tenant_id,
+                        Err(_) => continue
+                    },
+                    other => {
+                        memberships.push(other.clone());
+                        continue;
+                    }
+                };
+
+                let Some(tenant) = tenants.find_one(doc! { "_id": tenant_id }, None).await.map_err(error)? else { continue };
+                let role = match tenant.get("ownerId") {
+                    Some(Bson::Binary(owner_id)) if owner_id.to_uuid().is_ok_and(|owner_id| owner_id == user_id) => TenantRole::Owner,
+                    _ => TenantRole::Clerk
+                };
+                match to_bson(&Membership::new(tenant_id, role)) {
+                    Ok(membership) => memberships.push(membership),
+                    Err(err) => return Err(HttpResponse {
+                        status: 500,
+                        message: format!("Error migrating user memberships: {:?}", err),
+                        data: None
+                    })
+                }
+            }
+
+            // Only replaces the list it was derived from, a user changed in the meantime is picked up on the next start
+            let filter = doc! {
+                "_id": user_id,
+                "tenants": legacy.clone()
+            };
+            let update = doc! {
+                "$set": { "tenants": memberships }
+            };
+            migrated += db.update_one(filter, update, None).await.map_err(error)?.modified_count;
+        }
+
+        Ok(migrated)
+    }
+
     #[allow(unused)]
     pub async fn delete(&self, connection: &Connection<ShelfWatcherDatabase>) -> Result<UserMinimal, HttpResponse<()>> {
         let db = Self::get_collection(connection);
@@ -259,7 +630,7 @@ impl User {
     }
 
     #[allow(unused)]
-    fn get_collection(connection: &Connection<ShelfWatcherDatabase>) -> Collection<Self> {
+    fn get_collection(connection: &Client) -> Collection<Self> {
         let db = get_main_db(connection);
         db.collection(Self::COLLECTION_NAME)
     }
@@ -0,0 +1,142 @@
+use anyhow::Result;
+use mongodb::bson::{doc, to_bson, DateTime, Uuid};
+use rocket_db_pools::{mongodb::Collection, Connection};
+use rocket::serde::{Deserialize, Serialize};
+use crate::{db::{get_main_db, ShelfWatcherDatabase}, middleware::auth::{generate_token, hash_token}};
+
+use super::http_response::HttpResponse;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub enum UserTokenPurpose {
+    EmailVerification,
+    PasswordReset
+}
+
+impl UserTokenPurpose {
+    pub fn lifetime_hours(&self) -> i64 {
+        match self {
+            UserTokenPurpose::EmailVerification => 48,
+            UserTokenPurpose::PasswordReset => 1
+        }
+    }
+}
+
+// Single-use token mailed to a user. Only the hash is stored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct UserToken {
+    #[serde(rename = "_id")]
+    pub id: Uuid,
+    #[serde(rename = "userId")]
+    pub user_id: Uuid,
+    pub purpose: UserTokenPurpose,
+    // The address the token was sent to, a verification only counts for that address
+    pub email: String,
+    #[serde(rename = "tokenHash")]
+    pub token_hash: String,
+    #[serde(rename = "expiresAt")]
+    pub expires_at: DateTime,
+    #[serde(rename = "usedAt")]
+    pub used_at: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+}
+
+impl UserToken {
+    pub const COLLECTION_NAME: &'static str = "user_tokens";
+
+    // Returns the token document together with the plain token
+    pub fn new(user_id: Uuid, email: String, purpose: UserTokenPurpose) -> (Self, String) {
+        let token = generate_token(32);
+
+        let user_token = Self {
+            id: Uuid::new(),
+            user_id,
+            purpose,
+            email,
+            token_hash: hash_token(&token),
+            expires_at: DateTime::from_millis(DateTime::now().timestamp_millis() + purpose.lifetime_hours() * 60 * 60 * 1000),
+            used_at: None,
+            created_at: DateTime::now().to_string(),
+        };
+
+        (user_token, token)
+    }
+
+    #[allow(unused)]
+    pub async fn insert(&self, connection: &Connection<ShelfWatcherDatabase>) -> Result<Self, HttpResponse<()>> {
+        let db = Self::get_collection(connection);
+
+        match db.insert_one(self.clone(), None).await {
+            Ok(_) => Ok(self.clone()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error inserting user token: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    // Marks an unused, unexpired token as used and returns it
+    #[allow(unused)]
+    pub async fn consume(token: &str, purpose: UserTokenPurpose, connection: &Connection<ShelfWatcherDatabase>) -> Result<Self, HttpResponse<()>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "tokenHash": hash_token(token),
+            "purpose": to_bson(&purpose).unwrap(),
+            "usedAt": null,
+            "expiresAt": { "$gt": DateTime::now() }
+        };
+        let update = doc! {
+            "$set": {
+                "usedAt": DateTime::now().to_string()
+            }
+        };
+        match db.find_one_and_update(filter, update, None).await {
+            Ok(Some(user_token)) => Ok(user_token),
+            Ok(None) => Err(HttpResponse {
+                status: 400,
+                message: "Token is invalid or has expired".to_string(),
+                data: None
+            }),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error consuming user token: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    // Invalidates all open tokens of a user for `purpose`, e.g. older reset links once a new one was requested
+    #[allow(unused)]
+    pub async fn invalidate_all(user_id: Uuid, purpose: UserTokenPurpose, connection: &Connection<ShelfWatcherDatabase>) -> Result<(), HttpResponse<()>> {
+        let db = Self::get_collection(connection);
+
+        let filter = doc! {
+            "userId": user_id,
+            "purpose": to_bson(&purpose).unwrap(),
+            "usedAt": null
+        };
+        let update = doc! {
+            "$set": {
+                "usedAt": DateTime::now().to_string()
+            }
+        };
+        match db.update_many(filter, update, None).await {
+            Ok(_) => Ok(()),
+            Err(err) => Err(HttpResponse {
+                status: 500,
+                message: format!("Error invalidating user tokens: {:?}", err),
+                data: None
+            })
+        }
+    }
+
+    #[allow(unused)]
+    fn get_collection(connection: &Connection<ShelfWatcherDatabase>) -> Collection<Self> {
+        let db = get_main_db(connection);
+        db.collection(Self::COLLECTION_NAME)
+    }
+}
@@ -0,0 +1,58 @@
+use mongodb::bson::Uuid;
+use rocket::{post, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{http_response::HttpResponse, low_stock_alert::LowStockAlert, membership::TenantPermission}};
+
+#[allow(unused)]
+#[post("/tenants/<tenant_id>/alerts/<alert_id>/acknowledge", format = "json")]
+pub async fn acknowledge_alert(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, alert_id: &str) -> Json<HttpResponse<LowStockAlert>> {
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::MoveStock, &db).await {
+        return Json(err);
+    }
+
+    let alert_uuid = match Uuid::parse_str(alert_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid alert UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let alert = match LowStockAlert::get_from_tenant(alert_uuid, tenant_uuid, &db).await {
+        Ok(alert) => alert,
+        Err(err) => return Json(err)
+    };
+
+    if alert.acknowledged {
+        return Json(HttpResponse {
+            status: 400,
+            message: "Alert is already acknowledged".to_string(),
+            data: None
+        });
+    }
+
+    match alert.acknowledge(auth.author().id(), &db).await {
+        Ok(Some(alert)) => Json(HttpResponse {
+            status: 200,
+            message: "Alert acknowledged".to_string(),
+            data: Some(alert)
+        }),
+        Ok(None) => Json(HttpResponse {
+            status: 400,
+            message: "Alert is already acknowledged".to_string(),
+            data: None
+        }),
+        Err(err) => Json(err)
+    }
+}
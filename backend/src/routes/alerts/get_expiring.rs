@@ -0,0 +1,48 @@
+use mongodb::bson::Uuid;
+use rocket::{get, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{http_response::HttpResponse, membership::TenantPermission, product_batch::{LocationExpiries, ProductBatch}}};
+
+// Batches in stock that are expired or expire within `days`, which defaults to the tenant's expiry warning window.
+// Days are counted in the tenant's timezone. Locations under a blind count are left out.
+#[allow(unused)]
+#[get("/tenants/<tenant_id>/alerts/expiring?<days>", format = "json")]
+pub async fn get_expiring_batches(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, days: Option<i64>) -> Json<HttpResponse<Vec<LocationExpiries>>> {
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let tenant = match auth.require_permission(tenant_uuid, TenantPermission::ViewTenant, &db).await {
+        Ok(tenant) => tenant,
+        Err(err) => return Json(err)
+    };
+
+    let days = days.unwrap_or(tenant.settings.expiry_warning_days);
+    if !(0..=365).contains(&days) {
+        return Json(HttpResponse {
+            status: 400,
+            message: "Days must be between 0 and 365".to_string(),
+            data: None
+        });
+    }
+
+    let hidden_locations = match auth.hidden_stock_locations(tenant_uuid, &db).await {
+        Ok(locations) => locations,
+        Err(err) => return Json(err)
+    };
+
+    match ProductBatch::get_expiring(tenant.id, &tenant.settings.today(), &tenant.settings.date_in_days(days), &db).await {
+        Ok(expiries) => Json(HttpResponse {
+            status: 200,
+            message: "Successfully retrieved expiring batches".to_string(),
+            data: Some(expiries.into_iter().filter(|expiries| !hidden_locations.contains(&expiries.location_id)).collect())
+        }),
+        Err(err) => Json(err)
+    }
+}
@@ -0,0 +1,54 @@
+use mongodb::bson::Uuid;
+use rocket::{get, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{http_response::HttpResponse, low_stock_alert::LowStockAlert, membership::TenantPermission}};
+
+// Lists the open alerts as the background job last evaluated them. Alerts of locations under a blind count are left out.
+#[allow(unused)]
+#[get("/tenants/<tenant_id>/alerts/low-stock?<location_id>&<include_acknowledged>", format = "json")]
+pub async fn get_low_stock_alerts(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, location_id: Option<&str>, include_acknowledged: Option<bool>) -> Json<HttpResponse<Vec<LowStockAlert>>> {
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ViewTenant, &db).await {
+        return Json(err);
+    }
+
+    let location_uuid = match location_id.map(Uuid::parse_str) {
+        Some(Ok(uuid)) => Some(uuid),
+        Some(Err(err)) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid location UUID: {:?}", err),
+            data: None
+        }),
+        None => None
+    };
+
+    let include_acknowledged = include_acknowledged.unwrap_or(false);
+
+    let hidden_locations = match auth.hidden_stock_locations(tenant_uuid, &db).await {
+        Ok(locations) => locations,
+        Err(err) => return Json(err)
+    };
+
+    match LowStockAlert::get_open(tenant_uuid, &db).await {
+        Ok(alerts) => Json(HttpResponse {
+            status: 200,
+            message: "Successfully retrieved low stock alerts".to_string(),
+            data: Some(alerts
+                .into_iter()
+                .filter(|alert| location_uuid.is_none_or(|location_uuid| location_uuid == alert.location_id))
+                .filter(|alert| include_acknowledged || !alert.acknowledged)
+                .filter(|alert| !hidden_locations.contains(&alert.location_id))
+                .collect())
+        }),
+        Err(err) => Json(err)
+    }
+}
@@ -0,0 +1,3 @@
+pub mod get_low_stock;
+pub mod get_expiring;
+pub mod acknowledge;
\ No newline at end of file
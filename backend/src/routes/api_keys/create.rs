@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+use mongodb::bson::Uuid;
+use rocket::{error, post, serde::{json::Json, Deserialize, Serialize}};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::AuthenticatedUser, models::{api_key::{ApiKey, ApiKeyMinimal}, audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, membership::TenantPermission, tenant_plan::QuotaResource}};
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CreateApiKeyData {
+    name: String,
+    permissions: Vec<TenantPermission>
+}
+
+// The plain key is only part of this response, it can't be retrieved later
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ApiKeySecretResponse {
+    #[serde(rename = "apiKey")]
+    pub api_key: ApiKeyMinimal,
+    pub key: String,
+}
+
+#[allow(unused)]
+#[post("/tenants/<tenant_id>/api-keys", format = "json", data = "<data>")]
+pub async fn create_api_key(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser, tenant_id: &str, data: Json<CreateApiKeyData>) -> Json<HttpResponse<ApiKeySecretResponse>> {
+    let data = data.into_inner();
+
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let tenant = match auth.require_permission(tenant_uuid, TenantPermission::ManageApiKeys, &db).await {
+        Ok(tenant) => tenant,
+        Err(err) => return Json(err)
+    };
+
+    if let Err(err) = tenant.require_quota(QuotaResource::ApiKeys, &db).await {
+        return Json(err);
+    }
+
+    if data.name.trim().is_empty() {
+        return Json(HttpResponse {
+            status: 400,
+            message: "API key name must not be empty".to_string(),
+            data: None
+        });
+    }
+
+    let mut permissions: Vec<TenantPermission> = Vec::new();
+    for permission in data.permissions {
+        if !permissions.contains(&permission) {
+            permissions.push(permission);
+        }
+    }
+
+    if permissions.is_empty() {
+        return Json(HttpResponse {
+            status: 400,
+            message: "API key needs at least one permission".to_string(),
+            data: None
+        });
+    }
+
+    // A key can never do more than the member who created it
+    if !auth.is_admin() {
+        if let Some(role) = auth.role_in(&tenant) {
+            if let Some(permission) = permissions.iter().find(|permission| !role.has_permission(**permission)) {
+                return Json(HttpResponse {
+                    status: 403,
+                    message: format!("Your role {:?} is missing the {:?} permission", role, permission),
+                    data: None
+                });
+            }
+        }
+    }
+
+    let (api_key, key) = ApiKey::new(tenant.id, data.name, permissions, auth.user.id);
+
+    match api_key.insert(&db).await {
+        Ok(api_key) => {
+            let new_values = HashMap::from([
+                ("tenantId".to_string(), api_key.tenant_id.to_string()),
+                ("name".to_string(), api_key.name.clone()),
+                ("prefix".to_string(), api_key.prefix.clone()),
+                ("permissions".to_string(), format!("{:?}", api_key.permissions))
+            ]);
+            match AuditLog::new(api_key.id, AuditLogEntityType::ApiKey, AuditLogAction::Create, "API key created.".to_string(), auth.author(), None, Some(new_values)).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            Json(HttpResponse {
+                status: 201,
+                message: "API key created, store it now as it won't be shown again".to_string(),
+                data: Some(ApiKeySecretResponse { api_key, key })
+            })
+        },
+        Err(err) => Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    }
+}
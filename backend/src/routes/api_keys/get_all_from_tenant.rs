@@ -0,0 +1,31 @@
+use mongodb::bson::Uuid;
+use rocket::{get, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::AuthenticatedUser, models::{api_key::{ApiKey, ApiKeyMinimal}, http_response::HttpResponse, membership::TenantPermission}};
+
+#[allow(unused)]
+#[get("/tenants/<tenant_id>/api-keys", format = "json")]
+pub async fn get_all_api_keys_from_tenant(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser, tenant_id: &str) -> Json<HttpResponse<Vec<ApiKeyMinimal>>> {
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ManageApiKeys, &db).await {
+        return Json(err);
+    }
+
+    match ApiKey::get_all_from_tenant(tenant_uuid, &db).await {
+        Ok(api_keys) => Json(HttpResponse {
+            status: 200,
+            message: "Successfully retrieved all API keys from tenant".to_string(),
+            data: Some(api_keys)
+        }),
+        Err(err) => Json(err)
+    }
+}
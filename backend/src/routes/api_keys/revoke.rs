@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+use mongodb::bson::Uuid;
+use rocket::{delete, error, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::AuthenticatedUser, models::{api_key::{ApiKey, ApiKeyMinimal}, audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, membership::TenantPermission}};
+
+#[allow(unused)]
+#[delete("/tenants/<tenant_id>/api-keys/<api_key_id>", format = "json")]
+pub async fn revoke_api_key(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser, tenant_id: &str, api_key_id: &str) -> Json<HttpResponse<ApiKeyMinimal>> {
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let api_key_uuid = match Uuid::parse_str(api_key_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid API key UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ManageApiKeys, &db).await {
+        return Json(err);
+    }
+
+    let mut api_key = match ApiKey::get_from_tenant(api_key_uuid, tenant_uuid, &db).await {
+        Ok(api_key) => api_key,
+        Err(err) => return Json(err)
+    };
+
+    if api_key.revoked {
+        return Json(HttpResponse {
+            status: 400,
+            message: "API key is already revoked".to_string(),
+            data: None
+        });
+    }
+
+    api_key.revoked = true;
+
+    match api_key.update(&db).await {
+        Ok(api_key) => {
+            let old_values = HashMap::from([
+                ("revoked".to_string(), "false".to_string())
+            ]);
+            let new_values = HashMap::from([
+                ("revoked".to_string(), "true".to_string())
+            ]);
+            match AuditLog::new(api_key.id, AuditLogEntityType::ApiKey, AuditLogAction::Update, "API key revoked.".to_string(), auth.author(), Some(old_values), Some(new_values)).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            Json(HttpResponse {
+                status: 200,
+                message: "API key revoked".to_string(),
+                data: Some(api_key)
+            })
+        },
+        Err(err) => Json(err)
+    }
+}
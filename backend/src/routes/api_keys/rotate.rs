@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+use mongodb::bson::Uuid;
+use rocket::{error, post, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::AuthenticatedUser, models::{api_key::ApiKey, audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, membership::TenantPermission}};
+
+use super::create::ApiKeySecretResponse;
+
+// Issues a new secret for the key, keeping its id, name and permissions. The old secret stops working immediately.
+#[allow(unused)]
+#[post("/tenants/<tenant_id>/api-keys/<api_key_id>/rotate", format = "json")]
+pub async fn rotate_api_key(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser, tenant_id: &str, api_key_id: &str) -> Json<HttpResponse<ApiKeySecretResponse>> {
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let api_key_uuid = match Uuid::parse_str(api_key_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid API key UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ManageApiKeys, &db).await {
+        return Json(err);
+    }
+
+    let mut api_key = match ApiKey::get_from_tenant(api_key_uuid, tenant_uuid, &db).await {
+        Ok(api_key) => api_key,
+        Err(err) => return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    };
+
+    if api_key.revoked {
+        return Json(HttpResponse {
+            status: 400,
+            message: "Revoked API keys can't be rotated".to_string(),
+            data: None
+        });
+    }
+
+    let old_prefix = api_key.prefix.clone();
+    let key = api_key.regenerate();
+
+    match api_key.update_secret(&db).await {
+        Ok(api_key) => {
+            let old_values = HashMap::from([
+                ("prefix".to_string(), old_prefix)
+            ]);
+            let new_values = HashMap::from([
+                ("prefix".to_string(), api_key.prefix.clone())
+            ]);
+            match AuditLog::new(api_key.id, AuditLogEntityType::ApiKey, AuditLogAction::Update, "API key rotated.".to_string(), auth.author(), Some(old_values), Some(new_values)).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            Json(HttpResponse {
+                status: 200,
+                message: "API key rotated, store it now as it won't be shown again".to_string(),
+                data: Some(ApiKeySecretResponse { api_key, key })
+            })
+        },
+        Err(err) => Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    }
+}
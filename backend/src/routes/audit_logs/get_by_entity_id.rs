@@ -2,11 +2,11 @@ use mongodb::bson::Uuid;
 use rocket::{get, serde::json::Json};
 use rocket_db_pools::Connection;
 
-use crate::{db::ShelfWatcherDatabase, models::{audit_log::{AuditLog, AuditLogEntityType}, http_response::HttpResponse}};
+use crate::{db::ShelfWatcherDatabase, middleware::auth::AuthenticatedUser, models::{audit_log::{AuditLog, AuditLogEntityType}, http_response::HttpResponse}};
 
 #[allow(unused)]
 #[get("/audit-logs/<type>/entity/<id>", format = "json")] 
-pub async fn get_audit_log_by_entity_id(db: Connection<ShelfWatcherDatabase>, r#type: &str, id: &str) -> Json<HttpResponse<Vec<AuditLog>>> {
+pub async fn get_audit_log_by_entity_id(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser, r#type: &str, id: &str) -> Json<HttpResponse<Vec<AuditLog>>> {
     let entity_uuid = match Uuid::parse_str(id) {
         Ok(entity_uuid) => entity_uuid,
         Err(err) => return Json(HttpResponse {
@@ -16,11 +16,15 @@ pub async fn get_audit_log_by_entity_id(db: Connection<ShelfWatcherDatabase>, r#
         })
     };
 
-    let entity_type = match AuditLogEntityType::from_string(&r#type) {
+    let entity_type = match AuditLogEntityType::from_string(r#type) {
         Ok(entity_type) => entity_type,
         Err(err) => return Json(err)
     };
 
+    if let Err(err) = auth.require_audit_log_access(entity_uuid, &entity_type, &db).await {
+        return Json(err);
+    }
+
     match AuditLog::get_by_entity_id(entity_uuid, entity_type, &db).await {
         Ok(audit_log) => Json(HttpResponse {
             status: 200,
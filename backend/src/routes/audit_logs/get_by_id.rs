@@ -2,11 +2,11 @@ use mongodb::bson::Uuid;
 use rocket::{get, serde::json::Json};
 use rocket_db_pools::Connection;
 
-use crate::{db::ShelfWatcherDatabase, models::{audit_log::{AuditLog, AuditLogEntityType}, http_response::HttpResponse}};
+use crate::{db::ShelfWatcherDatabase, middleware::auth::AuthenticatedUser, models::{audit_log::{AuditLog, AuditLogEntityType}, http_response::HttpResponse}};
 
 #[allow(unused)]
 #[get("/audit-logs/<type>/id/<id>", format = "json")] 
-pub async fn get_audit_log_by_id(db: Connection<ShelfWatcherDatabase>, r#type: &str, id: &str) -> Json<HttpResponse<AuditLog>> {
+pub async fn get_audit_log_by_id(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser, r#type: &str, id: &str) -> Json<HttpResponse<AuditLog>> {
     let uuid = match Uuid::parse_str(id) {
         Ok(uuid) => uuid,
         Err(err) => return Json(HttpResponse {
@@ -16,17 +16,23 @@ pub async fn get_audit_log_by_id(db: Connection<ShelfWatcherDatabase>, r#type: &
         })
     };
 
-    let entity_type = match AuditLogEntityType::from_string(&r#type) {
+    let entity_type = match AuditLogEntityType::from_string(r#type) {
         Ok(entity_type) => entity_type,
         Err(err) => return Json(err)
     };
 
-    match AuditLog::get_by_id(uuid, entity_type, &db).await {
-        Ok(audit_log) => Json(HttpResponse {
-            status: 200,
-            message: "Audit Log found by id".to_string(),
-            data: Some(audit_log),
-        }),
-        Err(err) => Json(err)
+    let audit_log = match AuditLog::get_by_id(uuid, entity_type, &db).await {
+        Ok(audit_log) => audit_log,
+        Err(err) => return Json(err)
+    };
+
+    if let Err(err) = auth.require_audit_log_access(audit_log.entity_id, &audit_log.entity_type, &db).await {
+        return Json(err);
     }
+
+    Json(HttpResponse {
+        status: 200,
+        message: "Audit Log found by id".to_string(),
+        data: Some(audit_log),
+    })
 }
\ No newline at end of file
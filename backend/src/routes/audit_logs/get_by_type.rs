@@ -1,14 +1,17 @@
 use rocket::{get, serde::json::Json};
 use rocket_db_pools::Connection;
 
-use crate::{db::ShelfWatcherDatabase, models::{audit_log::{AuditLog, AuditLogEntityType}, http_response::HttpResponse}};
+use crate::{db::ShelfWatcherDatabase, middleware::auth::AuthenticatedUser, models::{audit_log::{AuditLog, AuditLogEntityType}, http_response::HttpResponse}};
 
 
 #[allow(unused)]
 #[get("/audit-logs/<type>", format = "json")] 
-pub async fn get_audit_logs_by_type(db: Connection<ShelfWatcherDatabase>, r#type: &str) -> Json<HttpResponse<Vec<AuditLog>>> {
-    // TODO: Only allow this for admins
-    let entity_type = match AuditLogEntityType::from_string(&r#type) {
+pub async fn get_audit_logs_by_type(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser, r#type: &str) -> Json<HttpResponse<Vec<AuditLog>>> {
+    if let Err(err) = auth.require_admin() {
+        return Json(err);
+    }
+
+    let entity_type = match AuditLogEntityType::from_string(r#type) {
         Ok(entity_type) => Some(entity_type),
         Err(err) => return Json(err)
     };
@@ -2,11 +2,11 @@ use mongodb::bson::Uuid;
 use rocket::{get, serde::json::Json};
 use rocket_db_pools::Connection;
 
-use crate::{db::ShelfWatcherDatabase, models::{audit_log::{AuditLog, AuditLogEntityType}, http_response::HttpResponse}};
+use crate::{db::ShelfWatcherDatabase, middleware::auth::AuthenticatedUser, models::{audit_log::{AuditLog, AuditLogEntityType}, http_response::HttpResponse}};
 
 #[allow(unused)]
 #[get("/users/<id>/audit-logs/<type>", format = "json")] 
-pub async fn get_audit_logs_by_user_id(db: Connection<ShelfWatcherDatabase>, r#type: &str, id: &str) -> Json<HttpResponse<Vec<AuditLog>>> {
+pub async fn get_audit_logs_by_user_id(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser, r#type: &str, id: &str) -> Json<HttpResponse<Vec<AuditLog>>> {
     let user_uuid = match Uuid::parse_str(id) {
         Ok(user_uuid) => user_uuid,
         Err(err) => return Json(HttpResponse {
@@ -16,7 +16,11 @@ pub async fn get_audit_logs_by_user_id(db: Connection<ShelfWatcherDatabase>, r#t
         })
     };
 
-    let entity_type = match AuditLogEntityType::from_string(&r#type) {
+    if let Err(err) = auth.require_self_or_admin(user_uuid) {
+        return Json(err);
+    }
+
+    let entity_type = match AuditLogEntityType::from_string(r#type) {
         Ok(entity_type) => entity_type,
         Err(err) => return Json(err)
     };
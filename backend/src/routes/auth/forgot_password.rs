@@ -0,0 +1,53 @@
+use rocket::{error, post, serde::{json::Json, Deserialize}, State};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, mailer::{Mail, MailConfig, Mailer}, models::{http_response::HttpResponse, user::User, user_token::{UserToken, UserTokenPurpose}}};
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ForgotPasswordData {
+    email: String,
+}
+
+#[allow(unused)]
+#[post("/auth/password/forgot", format = "json", data = "<data>")]
+pub async fn forgot_password(db: Connection<ShelfWatcherDatabase>, mailer: &State<Box<dyn Mailer>>, mail_config: &State<MailConfig>, data: Json<ForgotPasswordData>) -> Json<HttpResponse<()>> {
+    let data = data.into_inner();
+
+    // Same answer whether or not the account exists, so this can't be used to probe for emails
+    let response = Json(HttpResponse {
+        status: 200,
+        message: "If an account with that email exists, a reset link was sent".to_string(),
+        data: None
+    });
+
+    let user = match User::get_full_by_email(&data.email, &db).await {
+        Ok(user) if !user.disabled => user,
+        _ => return response
+    };
+
+    if let Err(err) = UserToken::invalidate_all(user.id, UserTokenPurpose::PasswordReset, &db).await {
+        error!("{}", err.message);
+        return response;
+    }
+
+    let (user_token, token) = UserToken::new(user.id, user.email.clone(), UserTokenPurpose::PasswordReset);
+    if let Err(err) = user_token.insert(&db).await {
+        error!("{}", err.message);
+        return response;
+    }
+
+    let mail = Mail {
+        to: user.email.clone(),
+        subject: "Reset your password".to_string(),
+        body: format!(
+            "Hi {},\n\nsomeone asked to reset the password of your ShelfWatcher account. If that was you, choose a new password here: {}/reset-password?token={}\n\nThe link expires in {} hour(s). If you did not ask for this, you can ignore this mail.",
+            user.first_name, mail_config.app_url, token, UserTokenPurpose::PasswordReset.lifetime_hours()
+        )
+    };
+    if let Err(err) = mailer.send(mail).await {
+        error!("{}", err);
+    }
+
+    response
+}
@@ -0,0 +1,173 @@
+use std::{collections::HashMap, net::IpAddr};
+use pwhash::bcrypt;
+use rocket::{error, post, serde::{json::Json, Deserialize, Serialize}, warn, State};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::AuthConfig, models::{audit_log::{AuditLog, AuditLogAction, AuditLogAuthor, AuditLogEntityType}, http_response::HttpResponse, login_throttle::{LoginThrottle, LoginThrottleKind}, session::Session, user::{User, UserMinimal}}};
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct LoginData {
+    email: String,
+    password: String,
+    #[serde(rename = "totpCode")]
+    totp_code: Option<String>,
+    #[serde(rename = "recoveryCode")]
+    recovery_code: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct LoginResponse {
+    token: String,
+    #[serde(rename = "expiresAt")]
+    expires_at: String,
+    user: UserMinimal,
+}
+
+// bcrypt hash of a throwaway password at the default cost, only used to spend the same time on unknown emails
+const DUMMY_PASSWORD_HASH: &str = "$2b$10$mh9IUH9gmriC3XIn1iPJe.7Q/L7zlNqyWZ/rFmwWpfXxST2MUZdGm";
+
+// Counts a failed attempt against the account and the client IP. Account lockouts are audited.
+async fn register_failed_login(db: &Connection<ShelfWatcherDatabase>, config: &AuthConfig, email: &str, ip: Option<IpAddr>, user: Option<&User>) {
+    match LoginThrottle::register_failure(LoginThrottleKind::Account, email, config.max_failed_logins, config.lockout_minutes, db).await {
+        Ok((account_throttle, true)) => match user {
+            Some(user) => {
+                let new_values = HashMap::from([
+                    ("failedLogins".to_string(), account_throttle.failures.to_string()),
+                    ("lockedUntil".to_string(), account_throttle.blocked_until.map(|until| until.to_string()).unwrap_or_default())
+                ]);
+                match AuditLog::new(user.id, AuditLogEntityType::User, AuditLogAction::Update, "Account locked after too many failed login attempts.".to_string(), AuditLogAuthor::System, None, Some(new_values)).insert(db).await {
+                    Ok(_) => (),
+                    Err(err) => error!("{}", err)
+                }
+            },
+            None => warn!("Login for unknown account {} locked after {} failed attempts", account_throttle.subject, account_throttle.failures)
+        },
+        Ok(_) => (),
+        Err(err) => error!("{}", err.message)
+    }
+
+    if let Some(ip) = ip {
+        match LoginThrottle::register_failure(LoginThrottleKind::Ip, &ip.to_string(), config.max_failed_logins_per_ip, config.lockout_minutes, db).await {
+            Ok((ip_throttle, true)) => warn!("Logins from {} locked after {} failed attempts", ip_throttle.subject, ip_throttle.failures),
+            Ok(_) => (),
+            Err(err) => error!("{}", err.message)
+        }
+    }
+}
+
+#[allow(unused)]
+#[post("/auth/login", format = "json", data = "<data>")]
+pub async fn login(db: Connection<ShelfWatcherDatabase>, config: &State<AuthConfig>, ip: Option<IpAddr>, data: Json<LoginData>) -> Json<HttpResponse<LoginResponse>> {
+    let data = data.into_inner();
+
+    let account_throttle = match LoginThrottle::get(LoginThrottleKind::Account, &data.email, &db).await {
+        Ok(throttle) => throttle,
+        Err(err) => return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    };
+
+    let ip_throttle = match ip {
+        Some(ip) => match LoginThrottle::get(LoginThrottleKind::Ip, &ip.to_string(), &db).await {
+            Ok(throttle) => Some(throttle),
+            Err(err) => return Json(HttpResponse {
+                status: err.status,
+                message: err.message,
+                data: None
+            })
+        },
+        None => None
+    };
+
+    if let Some(throttle) = [Some(&account_throttle), ip_throttle.as_ref()].into_iter().flatten().find(|throttle| throttle.is_blocked()) {
+        return Json(HttpResponse {
+            status: 429,
+            message: format!("Too many failed login attempts, try again in {} seconds", throttle.retry_after_seconds()),
+            data: None
+        });
+    }
+
+    // Unknown emails are verified against a dummy hash, so the response time doesn't tell which accounts exist
+    let user = User::get_full_by_email(&data.email, &db).await;
+    let password_hash = user.as_ref().map(|user| user.password_hash.as_str()).unwrap_or(DUMMY_PASSWORD_HASH);
+    let password_valid = bcrypt::verify(&data.password, password_hash);
+
+    let user = match user {
+        Ok(user) if password_valid => user,
+        user => {
+            register_failed_login(&db, config, &data.email, ip, user.ok().as_ref()).await;
+            return Json(HttpResponse {
+                status: 401,
+                message: "Invalid email or password".to_string(),
+                data: None
+            });
+        }
+    };
+
+    if user.disabled {
+        return Json(HttpResponse {
+            status: 403,
+            message: "User is disabled".to_string(),
+            data: None
+        });
+    }
+
+    if user.totp_secret.is_some() {
+        if data.totp_code.is_none() && data.recovery_code.is_none() {
+            return Json(HttpResponse {
+                status: 401,
+                message: "Two-factor code required".to_string(),
+                data: None
+            });
+        }
+
+        let verified = match user.verify_second_factor(data.totp_code.as_deref(), data.recovery_code.as_deref(), &db).await {
+            Ok(verified) => verified,
+            Err(err) => return Json(HttpResponse {
+                status: err.status,
+                message: err.message,
+                data: None
+            })
+        };
+        if !verified {
+            register_failed_login(&db, config, &data.email, ip, Some(&user)).await;
+            return Json(HttpResponse {
+                status: 401,
+                message: "Invalid two-factor code".to_string(),
+                data: None
+            });
+        }
+    }
+
+    if account_throttle.failures > 0 {
+        if let Err(err) = LoginThrottle::clear(LoginThrottleKind::Account, &account_throttle.subject, &db).await {
+            error!("{}", err.message);
+        }
+    }
+
+    let session = match Session::new(user.id, config.session_lifetime_hours).insert(&db).await {
+        Ok(session) => session,
+        Err(err) => {
+            error!("{}", err.message);
+            return Json(HttpResponse {
+                status: err.status,
+                message: err.message,
+                data: None
+            });
+        }
+    };
+
+    Json(HttpResponse {
+        status: 200,
+        message: "Logged in".to_string(),
+        data: Some(LoginResponse {
+            token: config.sign_session(session.id),
+            expires_at: session.expires_at.to_string(),
+            user: user.to_minimal()
+        })
+    })
+}
@@ -0,0 +1,17 @@
+use rocket::{post, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::AuthenticatedUser, models::http_response::HttpResponse};
+
+#[allow(unused)]
+#[post("/auth/logout", format = "json")]
+pub async fn logout(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser) -> Json<HttpResponse<()>> {
+    match auth.session.revoke(&db).await {
+        Ok(_) => Json(HttpResponse {
+            status: 200,
+            message: "Logged out".to_string(),
+            data: None
+        }),
+        Err(err) => Json(err)
+    }
+}
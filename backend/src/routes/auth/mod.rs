@@ -0,0 +1,6 @@
+pub mod login;
+pub mod logout;
+pub mod request_email_verification;
+pub mod verify_email;
+pub mod forgot_password;
+pub mod reset_password;
@@ -0,0 +1,50 @@
+use rocket::{post, serde::json::Json, State};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, mailer::{Mail, MailConfig, Mailer}, middleware::auth::AuthenticatedUser, models::{http_response::HttpResponse, user::User, user_token::{UserToken, UserTokenPurpose}}};
+
+// Used on sign up, after an email change and when the user asks for a new link
+pub async fn send_verification_mail(db: &Connection<ShelfWatcherDatabase>, mailer: &dyn Mailer, mail_config: &MailConfig, user: &User) -> Result<(), HttpResponse<()>> {
+    UserToken::invalidate_all(user.id, UserTokenPurpose::EmailVerification, db).await?;
+
+    let (user_token, token) = UserToken::new(user.id, user.email.clone(), UserTokenPurpose::EmailVerification);
+    user_token.insert(db).await?;
+
+    let mail = Mail {
+        to: user.email.clone(),
+        subject: "Verify your email address".to_string(),
+        body: format!(
+            "Hi {},\n\nplease verify your email address: {}/verify-email?token={}\n\nThe link expires in {} hours.",
+            user.first_name, mail_config.app_url, token, UserTokenPurpose::EmailVerification.lifetime_hours()
+        )
+    };
+    match mailer.send(mail).await {
+        Ok(_) => Ok(()),
+        Err(err) => Err(HttpResponse {
+            status: 500,
+            message: err,
+            data: None
+        })
+    }
+}
+
+#[allow(unused)]
+#[post("/auth/email/verification", format = "json")]
+pub async fn request_email_verification(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser, mailer: &State<Box<dyn Mailer>>, mail_config: &State<MailConfig>) -> Json<HttpResponse<()>> {
+    if auth.user.email_verified {
+        return Json(HttpResponse {
+            status: 400,
+            message: "Email address is already verified".to_string(),
+            data: None
+        });
+    }
+
+    match send_verification_mail(&db, mailer.as_ref(), mail_config, &auth.user).await {
+        Ok(_) => Json(HttpResponse {
+            status: 200,
+            message: "Verification mail sent".to_string(),
+            data: None
+        }),
+        Err(err) => Json(err)
+    }
+}
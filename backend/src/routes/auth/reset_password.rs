@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+use mongodb::bson::doc;
+use pwhash::bcrypt;
+use rocket::{error, post, serde::{json::Json, Deserialize}};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, models::{audit_log::{AuditLog, AuditLogAction, AuditLogAuthor, AuditLogEntityType}, http_response::HttpResponse, session::Session, user::User, user_token::{UserToken, UserTokenPurpose}}};
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResetPasswordData {
+    token: String,
+    password: String,
+}
+
+#[allow(unused)]
+#[post("/auth/password/reset", format = "json", data = "<data>")]
+pub async fn reset_password(db: Connection<ShelfWatcherDatabase>, data: Json<ResetPasswordData>) -> Json<HttpResponse<()>> {
+    let data = data.into_inner();
+
+    let user_token = match UserToken::consume(&data.token, UserTokenPurpose::PasswordReset, &db).await {
+        Ok(user_token) => user_token,
+        Err(err) => return Json(err)
+    };
+
+    let user = match User::get_full_by_id(user_token.user_id, &db).await {
+        Ok(user) if user.email == user_token.email && !user.disabled => user,
+        _ => return Json(HttpResponse {
+            status: 400,
+            message: "Token is invalid or has expired".to_string(),
+            data: None
+        })
+    };
+
+    let password_hash = match bcrypt::hash(data.password) {
+        Ok(hash) => hash,
+        Err(err) => return Json(HttpResponse {
+            status: 500,
+            message: format!("Failed to hash password: {:?}", err),
+            data: None
+        })
+    };
+    // Receiving the reset mail proves the address
+    let fields = doc! {
+        "passwordHash": password_hash,
+        "emailVerified": true
+    };
+
+    match user.update_fields(fields, &db).await {
+        Ok(user) => {
+            if let Err(err) = Session::revoke_all_for_user(user.id, &db).await {
+                error!("{}", err.message);
+            }
+
+            let old_values = HashMap::from([("password".to_string(), "HIDDEN".to_string())]);
+            let new_values = HashMap::from([("password".to_string(), "HIDDEN".to_string())]);
+            match AuditLog::new(user.id, AuditLogEntityType::User, AuditLogAction::Update, "Password reset.".to_string(), AuditLogAuthor::User(user.id), Some(old_values), Some(new_values)).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            Json(HttpResponse {
+                status: 200,
+                message: "Password reset, please log in again".to_string(),
+                data: None
+            })
+        },
+        Err(err) => Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    }
+}
@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+use rocket::{error, post, serde::{json::Json, Deserialize}};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, models::{audit_log::{AuditLog, AuditLogAction, AuditLogAuthor, AuditLogEntityType}, http_response::HttpResponse, user::{User, UserMinimal}, user_token::{UserToken, UserTokenPurpose}}};
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct VerifyEmailData {
+    token: String,
+}
+
+#[allow(unused)]
+#[post("/auth/email/verify", format = "json", data = "<data>")]
+pub async fn verify_email(db: Connection<ShelfWatcherDatabase>, data: Json<VerifyEmailData>) -> Json<HttpResponse<UserMinimal>> {
+    let data = data.into_inner();
+
+    let user_token = match UserToken::consume(&data.token, UserTokenPurpose::EmailVerification, &db).await {
+        Ok(user_token) => user_token,
+        Err(err) => return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    };
+
+    let mut user = match User::get_full_by_id(user_token.user_id, &db).await {
+        Ok(user) => user,
+        Err(err) => return Json(err)
+    };
+
+    // The address changed after the mail was sent
+    if user.email != user_token.email {
+        return Json(HttpResponse {
+            status: 400,
+            message: "Token is invalid or has expired".to_string(),
+            data: None
+        });
+    }
+
+    if user.email_verified {
+        return Json(HttpResponse {
+            status: 200,
+            message: "Email address is already verified".to_string(),
+            data: Some(user.to_minimal())
+        });
+    }
+
+    match user.mark_email_verified(&user_token.email, &db).await {
+        Ok(()) => {
+            user.email_verified = true;
+
+            let old_values = HashMap::from([("emailVerified".to_string(), "false".to_string())]);
+            let new_values = HashMap::from([("emailVerified".to_string(), "true".to_string())]);
+            match AuditLog::new(user.id, AuditLogEntityType::User, AuditLogAction::Update, "Email address verified.".to_string(), AuditLogAuthor::User(user.id), Some(old_values), Some(new_values)).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            Json(HttpResponse {
+                status: 200,
+                message: "Email address verified".to_string(),
+                data: Some(user.to_minimal())
+            })
+        },
+        Err(err) => Json(err)
+    }
+}
@@ -0,0 +1,15 @@
+use rocket::{catch, serde::json::Json, Request};
+
+use crate::{middleware::auth::AuthFailure, models::http_response::HttpResponse};
+
+#[allow(unused)]
+#[catch(401)]
+pub fn unauthorized(req: &Request) -> Json<HttpResponse<()>> {
+    let failure = req.local_cache(|| AuthFailure("Authentication required".to_string()));
+
+    Json(HttpResponse {
+        status: 401,
+        message: failure.0.clone(),
+        data: None
+    })
+}
@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+use rocket::{error, post, serde::{json::Json, Deserialize}};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, models::{audit_log::{AuditLog, AuditLogAction, AuditLogAuthor, AuditLogEntityType}, http_response::HttpResponse, invitation::Invitation, membership::Membership, tenant::Tenant, tenant_plan::QuotaResource, user::{User, UserMinimal}}, routes::users::create::{register_user, CreateUserData}};
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct AcceptInvitationData {
+    token: String,
+    // Only needed when no user with the invited email exists yet
+    password: Option<String>,
+    #[serde(rename = "firstName")]
+    first_name: Option<String>,
+    #[serde(rename = "lastName")]
+    last_name: Option<String>,
+}
+
+#[allow(unused)]
+#[post("/invitations/accept", format = "json", data = "<data>")]
+pub async fn accept_invitation(db: Connection<ShelfWatcherDatabase>, data: Json<AcceptInvitationData>) -> Json<HttpResponse<UserMinimal>> {
+    let data = data.into_inner();
+
+    let invitation = match Invitation::get_by_token(&data.token, &db).await {
+        Ok(invitation) if invitation.is_pending() => invitation,
+        _ => return Json(HttpResponse {
+            status: 400,
+            message: "Invitation is invalid or has expired".to_string(),
+            data: None
+        })
+    };
+
+    let tenant = match Tenant::get_by_id(invitation.tenant_id, &db).await {
+        Ok(tenant) => tenant,
+        Err(err) => return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    };
+
+    if let Err(err) = tenant.require_active() {
+        return Json(err);
+    }
+
+    let existing = User::get_by_email(&invitation.email, &db).await;
+    if existing.is_err() && (data.password.is_none() || data.first_name.is_none() || data.last_name.is_none()) {
+        return Json(HttpResponse {
+            status: 400,
+            message: "password, firstName and lastName are required to create an account".to_string(),
+            data: None
+        });
+    }
+
+    // The quota may have shrunk since the invitation was sent. The pending invitation already holds a seat, members accepting again don't need one.
+    let already_member = existing.as_ref().is_ok_and(|user| user.tenants.iter().any(|membership| membership.tenant_id == tenant.id));
+    if !already_member {
+        if let Err(err) = tenant.require_quota_excluding(QuotaResource::Members, 1, &db).await {
+            return Json(err);
+        }
+    }
+
+    // Claimed before the account is touched, so two requests can't both accept it. Handed back below if accepting fails.
+    if let Err(err) = invitation.accept(&db).await {
+        return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        });
+    }
+
+    let accepted = async {
+        let user = match existing {
+            Ok(user) => user,
+            Err(_) => {
+                let create_data = CreateUserData {
+                    email: invitation.email.clone(),
+                    password: data.password.unwrap_or_default(),
+                    first_name: data.first_name.unwrap_or_default(),
+                    last_name: data.last_name.unwrap_or_default()
+                };
+                register_user(&db, create_data).await?
+            }
+        };
+
+        // The token was delivered to this address
+        let user = user.to_full(&db).await?;
+        user.mark_email_verified(&invitation.email, &db).await?;
+
+        // Members accepting again keep their role
+        match user.add_membership(&Membership::new(invitation.tenant_id, invitation.role), &db).await? {
+            Some(user) => Ok(user),
+            None => User::get_by_id(user.id, &db).await
+        }
+    }.await;
+
+    match accepted {
+        Ok(user) => {
+            let new_values = HashMap::from([
+                ("memberId".to_string(), user.id.to_string()),
+                ("role".to_string(), format!("{:?}", invitation.role)),
+                ("invitationId".to_string(), invitation.id.to_string())
+            ]);
+            match AuditLog::new(invitation.tenant_id, AuditLogEntityType::Tenant, AuditLogAction::Update, "Invitation accepted.".to_string(), AuditLogAuthor::User(user.id), None, Some(new_values)).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            Json(HttpResponse {
+                status: 200,
+                message: "Invitation accepted".to_string(),
+                data: Some(user)
+            })
+        },
+        Err(err) => {
+            if let Err(reopen_err) = invitation.reopen(&db).await {
+                error!("{}", reopen_err.message);
+            }
+            Json(err)
+        }
+    }
+}
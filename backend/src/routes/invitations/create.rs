@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+use mongodb::bson::Uuid;
+use rocket::{error, post, serde::{json::Json, Deserialize}, State};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, mailer::{Mail, MailConfig, Mailer}, middleware::auth::AuthenticatedUser, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, invitation::{Invitation, InvitationMinimal}, membership::{TenantPermission, TenantRole}, user::User, tenant_plan::QuotaResource}};
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CreateInvitationData {
+    email: String,
+    role: String
+}
+
+#[allow(unused)]
+#[post("/tenants/<tenant_id>/invitations", format = "json", data = "<data>")]
+pub async fn create_invitation(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser, mailer: &State<Box<dyn Mailer>>, mail_config: &State<MailConfig>, tenant_id: &str, data: Json<CreateInvitationData>) -> Json<HttpResponse<InvitationMinimal>> {
+    let data = data.into_inner();
+
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let role = match TenantRole::from_string(&data.role) {
+        Ok(role) => role,
+        Err(err) => return Json(err)
+    };
+
+    if role == TenantRole::Owner {
+        return Json(HttpResponse {
+            status: 400,
+            message: "Ownership can only be transferred, not granted".to_string(),
+            data: None
+        });
+    }
+
+    let tenant = match auth.require_permission(tenant_uuid, TenantPermission::ManageMembers, &db).await {
+        Ok(tenant) => tenant,
+        Err(err) => return Json(err)
+    };
+
+    if let Err(err) = tenant.require_quota(QuotaResource::Members, &db).await {
+        return Json(err);
+    }
+
+    if let Err(err) = auth.require_can_manage_role(&tenant, role) {
+        return Json(err);
+    }
+
+    if let Ok(user) = User::get_by_email(&data.email, &db).await {
+        if user.membership(tenant.id).is_some() {
+            return Json(HttpResponse {
+                status: 400,
+                message: "User is already a member of this tenant".to_string(),
+                data: None
+            });
+        }
+    }
+
+    let (invitation, token) = Invitation::new(tenant.id, data.email, role, auth.user.id);
+
+    let invitation = match invitation.insert(&db).await {
+        Ok(invitation) => invitation,
+        Err(err) => return Json(err)
+    };
+
+    let new_values = HashMap::from([
+        ("invitationId".to_string(), invitation.id.to_string()),
+        ("email".to_string(), invitation.email.clone()),
+        ("role".to_string(), format!("{:?}", invitation.role))
+    ]);
+    match AuditLog::new(tenant.id, AuditLogEntityType::Tenant, AuditLogAction::Update, "Invitation created.".to_string(), auth.author(), None, Some(new_values)).insert(&db).await {
+        Ok(_) => (),
+        Err(err) => error!("{}", err)
+    }
+
+    let mail = Mail {
+        to: invitation.email.clone(),
+        subject: format!("You have been invited to {}", tenant.name),
+        body: format!(
+            "You have been invited to join {} on ShelfWatcher as {:?}.\n\nAccept the invitation: {}/invitations/accept?token={}\n\nThe invitation expires at {}.",
+            tenant.name, invitation.role, mail_config.app_url, token, invitation.expires_at
+        )
+    };
+    if let Err(err) = mailer.send(mail).await {
+        error!("{}", err);
+        return Json(HttpResponse {
+            status: 201,
+            message: "Invitation created, but the mail could not be sent".to_string(),
+            data: Some(invitation)
+        });
+    }
+
+    Json(HttpResponse {
+        status: 201,
+        message: "Invitation created".to_string(),
+        data: Some(invitation)
+    })
+}
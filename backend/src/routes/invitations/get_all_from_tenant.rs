@@ -0,0 +1,31 @@
+use mongodb::bson::Uuid;
+use rocket::{get, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::AuthenticatedUser, models::{http_response::HttpResponse, invitation::{Invitation, InvitationMinimal}, membership::TenantPermission}};
+
+#[allow(unused)]
+#[get("/tenants/<tenant_id>/invitations", format = "json")]
+pub async fn get_all_invitations_from_tenant(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser, tenant_id: &str) -> Json<HttpResponse<Vec<InvitationMinimal>>> {
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ManageMembers, &db).await {
+        return Json(err);
+    }
+
+    match Invitation::get_all_from_tenant(tenant_uuid, &db).await {
+        Ok(invitations) => Json(HttpResponse {
+            status: 200,
+            message: "Successfully retrieved all invitations from tenant".to_string(),
+            data: Some(invitations),
+        }),
+        Err(err) => Json(err)
+    }
+}
@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+use mongodb::bson::Uuid;
+use rocket::{delete, error, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::AuthenticatedUser, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, invitation::Invitation, membership::TenantPermission}};
+
+#[allow(unused)]
+#[delete("/tenants/<tenant_id>/invitations/<invitation_id>", format = "json")]
+pub async fn revoke_invitation(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser, tenant_id: &str, invitation_id: &str) -> Json<HttpResponse<()>> {
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let invitation_uuid = match Uuid::parse_str(invitation_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid invitation UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let tenant = match auth.require_permission(tenant_uuid, TenantPermission::ManageMembers, &db).await {
+        Ok(tenant) => tenant,
+        Err(err) => return Json(err)
+    };
+
+    let mut invitation = match Invitation::get_by_id(invitation_uuid, &db).await {
+        Ok(invitation) if invitation.tenant_id == tenant.id => invitation,
+        _ => return Json(HttpResponse {
+            status: 404,
+            message: "Invitation not found".to_string(),
+            data: None
+        })
+    };
+
+    if !invitation.is_pending() {
+        return Json(HttpResponse {
+            status: 400,
+            message: "Invitation is no longer pending".to_string(),
+            data: None
+        });
+    }
+
+    invitation.revoked = true;
+
+    match invitation.update(&db).await {
+        Ok(invitation) => {
+            let old_values = HashMap::from([
+                ("invitationId".to_string(), invitation.id.to_string()),
+                ("email".to_string(), invitation.email.clone())
+            ]);
+            match AuditLog::new(tenant.id, AuditLogEntityType::Tenant, AuditLogAction::Update, "Invitation revoked.".to_string(), auth.author(), Some(old_values), None).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            Json(HttpResponse {
+                status: 200,
+                message: "Invitation revoked".to_string(),
+                data: None
+            })
+        },
+        Err(err) => Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use mongodb::bson::Uuid;
+use rocket::{error, post, serde::{json::Json, Deserialize}};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, item::{Item, ItemStatus}, location::Location, membership::TenantPermission, stock_movement::StockMovement}};
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ChangeItemStatusData {
+    status: String,
+    reason: Option<String>,
+    // Where the item is now, e.g. when a return arrives at another location
+    #[serde(rename = "locationId")]
+    location_id: Option<String>
+}
+
+// Checks the stock, claims the status change and books the movement, while the caller holds the stock lock
+async fn book_status_change(item: &Item, old_status: ItemStatus, movement: Option<&StockMovement>, db: &Connection<ShelfWatcherDatabase>) -> Result<(), HttpResponse<Item>> {
+    if let Some(movement) = movement {
+        movement.check_available(db).await?;
+    }
+
+    // Claim the change before booking, so concurrent requests can't book the same item twice
+    if !item.replace_if(old_status, db).await? {
+        return Err(HttpResponse {
+            status: 400,
+            message: "Item status was changed in the meantime".to_string(),
+            data: None
+        });
+    }
+
+    if let Some(movement) = movement {
+        if let Err(err) = movement.insert(db).await {
+            return Err(HttpResponse {
+                status: err.status,
+                message: err.message,
+                data: None
+            });
+        }
+    }
+
+    Ok(())
+}
+
+#[allow(unused)]
+#[post("/tenants/<tenant_id>/items/<item_id>/status", format = "json", data = "<data>")]
+pub async fn change_item_status(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, item_id: &str, data: Json<ChangeItemStatusData>) -> Json<HttpResponse<Item>> {
+    let data = data.into_inner();
+
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::MoveStock, &db).await {
+        return Json(err);
+    }
+
+    let item_uuid = match Uuid::parse_str(item_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid item UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let status = match ItemStatus::from_string(&data.status) {
+        Ok(status) => status,
+        Err(err) => return Json(err)
+    };
+
+    let location_uuid = match data.location_id.as_deref().map(Uuid::parse_str) {
+        Some(Ok(uuid)) => Some(uuid),
+        Some(Err(err)) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid location UUID: {:?}", err),
+            data: None
+        }),
+        None => None
+    };
+
+    if let Some(location_uuid) = location_uuid {
+        if let Err(err) = Location::get_from_tenant(location_uuid, tenant_uuid, &db).await {
+            return Json(HttpResponse {
+                status: err.status,
+                message: err.message,
+                data: None
+            });
+        }
+    }
+
+    let mut item = match Item::get_from_tenant(item_uuid, tenant_uuid, &db).await {
+        Ok(item) => item,
+        Err(err) => return Json(err)
+    };
+
+    let mut old_values = HashMap::from([("status".to_string(), format!("{:?}", item.status))]);
+    let mut new_values = HashMap::from([("status".to_string(), format!("{:?}", status))]);
+    if let Some(location_uuid) = location_uuid.filter(|location_uuid| *location_uuid != item.location_id) {
+        old_values.insert("locationId".to_string(), item.location_id.to_string());
+        new_values.insert("locationId".to_string(), location_uuid.to_string());
+    }
+
+    let (old_status, old_location_id) = (item.status, item.location_id);
+    if let Err(err) = item.change_status(status, location_uuid, data.reason.clone(), auth.author()) {
+        return Json(err);
+    }
+
+    let movement = match item.stock_movement(old_status, old_location_id, auth.author()) {
+        Ok(movement) => movement,
+        Err(err) => return Json(err)
+    };
+    if let Some(movement) = &movement {
+        new_values.insert("movementId".to_string(), movement.id.to_string());
+    }
+
+    // Items leaving stock hold the lock of their product at the location until the movement is booked, see `StockLock`
+    let lock = match &movement {
+        Some(movement) => match movement.lock_source(&db).await {
+            Ok(lock) => lock,
+            Err(err) => return Json(err)
+        },
+        None => None
+    };
+
+    let booked = book_status_change(&item, old_status, movement.as_ref(), &db).await;
+    if let Some(lock) = lock {
+        lock.release(&db).await;
+    }
+    if let Err(err) = booked {
+        return Json(err);
+    }
+
+    let reason = match data.reason {
+        Some(reason) => format!("Item status changed: {}", reason),
+        None => "Item status changed.".to_string()
+    };
+    match AuditLog::new(item.id, AuditLogEntityType::Item, AuditLogAction::Update, reason, auth.author(), Some(old_values), Some(new_values)).insert(&db).await {
+        Ok(_) => (),
+        Err(err) => error!("{}", err)
+    }
+
+    Json(HttpResponse {
+        status: 200,
+        message: "Item status changed".to_string(),
+        data: Some(item)
+    })
+}
@@ -0,0 +1,92 @@
+use mongodb::bson::Uuid;
+use rocket::{error, post, serde::{json::Json, Deserialize}};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, item::Item, membership::TenantPermission, product_batch::ProductBatch}};
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CreateItemData {
+    #[serde(rename = "serialNumber")]
+    serial_number: String,
+    #[serde(rename = "batchId")]
+    batch_id: String
+}
+
+// Registers a serialized unit of a batch. Product and location are taken from the batch.
+#[allow(unused)]
+#[post("/tenants/<tenant_id>/items", format = "json", data = "<data>")]
+pub async fn create_item(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, data: Json<CreateItemData>) -> Json<HttpResponse<Item>> {
+    let data = data.into_inner();
+
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::MoveStock, &db).await {
+        return Json(err);
+    }
+
+    let batch_uuid = match Uuid::parse_str(&data.batch_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid batch UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let serial_number = data.serial_number.trim().to_string();
+    if serial_number.is_empty() {
+        return Json(HttpResponse {
+            status: 400,
+            message: "Serial number must not be empty".to_string(),
+            data: None
+        });
+    }
+
+    let batch = match ProductBatch::get_from_tenant(batch_uuid, tenant_uuid, &db).await {
+        Ok(batch) if !batch.closed => batch,
+        Ok(_) => return Json(HttpResponse {
+            status: 400,
+            message: "Batch is closed".to_string(),
+            data: None
+        }),
+        Err(err) => return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    };
+
+    if Item::get_by_serial(&serial_number, tenant_uuid, &db).await.is_ok() {
+        return Json(HttpResponse {
+            status: 400,
+            message: "Serial number already exists".to_string(),
+            data: None
+        });
+    }
+
+    let item = Item::new(tenant_uuid, serial_number, batch.product_id, batch.id, batch.location_id, auth.author());
+
+    match item.insert(&db).await {
+        Ok(item) => {
+            match AuditLog::new(item.id, AuditLogEntityType::Item, AuditLogAction::Create, "Item created.".to_string(), auth.author(), None, None).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            Json(HttpResponse {
+                status: 201,
+                message: "Item created".to_string(),
+                data: Some(item)
+            })
+        },
+        Err(err) => Json(err)
+    }
+}
@@ -0,0 +1,67 @@
+use mongodb::bson::Uuid;
+use rocket::{get, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{http_response::HttpResponse, item::{Item, ItemStatus}, membership::TenantPermission}};
+
+#[allow(unused)]
+#[get("/tenants/<tenant_id>/items?<product_id>&<batch_id>&<location_id>&<status>", format = "json")]
+pub async fn get_all_items_from_tenant(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, product_id: Option<&str>, batch_id: Option<&str>, location_id: Option<&str>, status: Option<&str>) -> Json<HttpResponse<Vec<Item>>> {
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ViewTenant, &db).await {
+        return Json(err);
+    }
+
+    let product_uuid = match product_id.map(Uuid::parse_str) {
+        Some(Ok(uuid)) => Some(uuid),
+        Some(Err(err)) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid product UUID: {:?}", err),
+            data: None
+        }),
+        None => None
+    };
+
+    let batch_uuid = match batch_id.map(Uuid::parse_str) {
+        Some(Ok(uuid)) => Some(uuid),
+        Some(Err(err)) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid batch UUID: {:?}", err),
+            data: None
+        }),
+        None => None
+    };
+
+    let location_uuid = match location_id.map(Uuid::parse_str) {
+        Some(Ok(uuid)) => Some(uuid),
+        Some(Err(err)) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid location UUID: {:?}", err),
+            data: None
+        }),
+        None => None
+    };
+
+    let status = match status.map(ItemStatus::from_string) {
+        Some(Ok(status)) => Some(status),
+        Some(Err(err)) => return Json(err),
+        None => None
+    };
+
+    match Item::get_all_from_tenant(tenant_uuid, product_uuid, batch_uuid, location_uuid, status, &db).await {
+        Ok(items) => Json(HttpResponse {
+            status: 200,
+            message: "Successfully retrieved items".to_string(),
+            data: Some(items)
+        }),
+        Err(err) => Json(err)
+    }
+}
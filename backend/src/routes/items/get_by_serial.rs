@@ -0,0 +1,31 @@
+use mongodb::bson::Uuid;
+use rocket::{get, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{http_response::HttpResponse, item::Item, membership::TenantPermission}};
+
+#[allow(unused)]
+#[get("/tenants/<tenant_id>/items/by-serial/<serial_number>", format = "json")]
+pub async fn get_item_by_serial(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, serial_number: &str) -> Json<HttpResponse<Item>> {
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ViewTenant, &db).await {
+        return Json(err);
+    }
+
+    match Item::get_by_serial(serial_number.trim(), tenant_uuid, &db).await {
+        Ok(item) => Json(HttpResponse {
+            status: 200,
+            message: "Found item by serial number".to_string(),
+            data: Some(item)
+        }),
+        Err(err) => Json(err)
+    }
+}
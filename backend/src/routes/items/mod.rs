@@ -0,0 +1,5 @@
+pub mod create;
+pub mod get_all_from_tenant;
+pub mod get_by_serial;
+pub mod get_history;
+pub mod change_status;
\ No newline at end of file
@@ -2,7 +2,7 @@ use mongodb::bson::Uuid;
 use rocket::{error, post, serde::{json::Json, Deserialize}};
 use rocket_db_pools::Connection;
 
-use crate::{db::ShelfWatcherDatabase, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, location::Location, tenant::Tenant}};
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, membership::TenantPermission, location::Location, tenant_plan::QuotaResource}};
 
 #[derive(Deserialize)]
 #[serde(crate = "rocket::serde")]
@@ -12,7 +12,7 @@ pub struct CreateLocationData {
 
 #[allow(unused)]
 #[post("/tenants/<tenant_id>/locations", format = "json", data = "<data>")] 
-pub async fn create_location(db: Connection<ShelfWatcherDatabase>, data: Json<CreateLocationData>, tenant_id: &str) -> Json<HttpResponse<Location>> { 
+pub async fn create_location(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, data: Json<CreateLocationData>, tenant_id: &str) -> Json<HttpResponse<Location>> { 
     let data = data.into_inner();
 
     let tenant_uuid = match Uuid::parse_str(tenant_id) {
@@ -25,13 +25,10 @@ pub async fn create_location(db: Connection<ShelfWatcherDatabase>, data: Json<Cr
         
     };
 
-    if Tenant::get_by_id(tenant_uuid, &db).await.is_err() {
-        return Json(HttpResponse {
-            status: 400,
-            message: "Tenant does not exists".to_string(),
-            data: None
-        });
-    }
+    let tenant = match auth.require_permission(tenant_uuid, TenantPermission::ManageLocations, &db).await {
+        Ok(tenant) => tenant,
+        Err(err) => return Json(err)
+    };
 
     let existing = match Location::get_all_from_tenant(tenant_uuid, &db).await {
         Ok(locations) => locations,
@@ -42,14 +39,6 @@ pub async fn create_location(db: Connection<ShelfWatcherDatabase>, data: Json<Cr
         })
     };
 
-    if existing.len() >= 3 {
-        return Json(HttpResponse {
-            status: 400,
-            message: "Tenant has reached the maximum number of locations (3)".to_string(),
-            data: None
-        });
-    }
-
     if existing.iter().any(|location| location.name == data.name) {
         return Json(HttpResponse {
             status: 400,
@@ -58,12 +47,15 @@ pub async fn create_location(db: Connection<ShelfWatcherDatabase>, data: Json<Cr
         });
     }
 
+    if let Err(err) = tenant.require_quota(QuotaResource::Locations, &db).await {
+        return Json(err);
+    }
+
     let location = Location::new(data.name, tenant_uuid);
     
     match location.insert(&db).await {
         Ok(location) => {
-        // TODO: Implement author_id
-            match AuditLog::new(location.id, AuditLogEntityType::Location, AuditLogAction::Create, "Location created.".to_string(), Uuid::new(), None, None).insert(&db).await {
+            match AuditLog::new(location.id, AuditLogEntityType::Location, AuditLogAction::Create, "Location created.".to_string(), auth.author(), None, None).insert(&db).await {
                 Ok(_) => (),
                 Err(err) => error!("{}", err)
             }
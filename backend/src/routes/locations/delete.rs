@@ -2,11 +2,11 @@ use mongodb::bson::Uuid;
 use rocket::{delete, error, serde::json::Json};
 use rocket_db_pools::Connection;
 
-use crate::{db::ShelfWatcherDatabase, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, location::Location, tenant::Tenant}};
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, membership::TenantPermission, location::Location}};
 
 #[allow(unused)]
 #[delete("/tenants/<tenant_id>/locations/<location_id>", format = "json")] 
-pub async fn delete_location(db: Connection<ShelfWatcherDatabase>, tenant_id: &str, location_id: &str) -> Json<HttpResponse<()>> {
+pub async fn delete_location(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, location_id: &str) -> Json<HttpResponse<()>> {
     let tenant_uuid = match Uuid::parse_str(tenant_id) {
         Ok(uuid) => uuid,
         Err(err) => return Json(HttpResponse {
@@ -16,14 +16,9 @@ pub async fn delete_location(db: Connection<ShelfWatcherDatabase>, tenant_id: &s
         })
     };
 
-    let tenant = match Tenant::get_by_id(tenant_uuid, &db).await {
-        Ok(tenant) => tenant,
-        Err(err) => return Json(HttpResponse {
-            status: err.status,
-            message: err.message,
-            data: None
-        })
-    };
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ManageLocations, &db).await {
+        return Json(err);
+    }
 
     let location_uuid = match Uuid::parse_str(location_id) {
         Ok(uuid) => uuid,
@@ -34,7 +29,7 @@ pub async fn delete_location(db: Connection<ShelfWatcherDatabase>, tenant_id: &s
         })
     };
 
-    let location = match Location::get_by_id(location_uuid, &db).await {
+    let location = match Location::get_from_tenant(location_uuid, tenant_uuid, &db).await {
         Ok(location) => location,
         Err(err) => return Json(HttpResponse {
             status: err.status,
@@ -43,10 +38,19 @@ pub async fn delete_location(db: Connection<ShelfWatcherDatabase>, tenant_id: &s
         })
     };
 
+    match location.get_delete_blocker(&db).await {
+        Ok(None) => (),
+        Ok(Some(blocker)) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Location still has {}", blocker),
+            data: None
+        }),
+        Err(err) => return Json(err)
+    }
+
     match location.delete(&db).await {
         Ok(location) => {
-            // TODO: Implement author_id
-            match AuditLog::new(location.id, AuditLogEntityType::Location, AuditLogAction::Delete, "Location deleted.".to_string(), Uuid::new(), None, None).insert(&db).await {
+            match AuditLog::new(location.id, AuditLogEntityType::Location, AuditLogAction::Delete, "Location deleted.".to_string(), auth.author(), None, None).insert(&db).await {
                 Ok(_) => (),
                 Err(err) => error!("{}", err)
             }
@@ -1,12 +1,15 @@
 use rocket::{get, serde::json::Json};
 use rocket_db_pools::Connection;
 
-use crate::{db::ShelfWatcherDatabase, models::{http_response::HttpResponse, location::Location}};
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{http_response::HttpResponse, location::Location}};
 
 #[allow(unused)]
 #[get("/locations", format = "json")] 
-pub async fn get_all_locations(db: Connection<ShelfWatcherDatabase>) -> Json<HttpResponse<Vec<Location>>> {
-    // TODO: Only allow this for admins
+pub async fn get_all_locations(db: Connection<ShelfWatcherDatabase>, auth: Authenticated) -> Json<HttpResponse<Vec<Location>>> {
+    if let Err(err) = auth.require_admin() {
+        return Json(err);
+    }
+
     match Location::get_all(&db).await {
         Ok(tenants) => Json(HttpResponse {
             status: 200,
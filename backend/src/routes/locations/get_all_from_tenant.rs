@@ -2,11 +2,11 @@ use mongodb::bson::Uuid;
 use rocket::{get, serde::json::Json};
 use rocket_db_pools::Connection;
 
-use crate::{db::ShelfWatcherDatabase, models::{http_response::HttpResponse, location::Location}};
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{http_response::HttpResponse, membership::TenantPermission, location::Location}};
 
 #[allow(unused)]
 #[get("/tenants/<tenant_id>/locations", format = "json")] 
-pub async fn get_all_locations_from_tenant(db: Connection<ShelfWatcherDatabase>, tenant_id: &str) -> Json<HttpResponse<Vec<Location>>> {
+pub async fn get_all_locations_from_tenant(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str) -> Json<HttpResponse<Vec<Location>>> {
     let tenant_uuid = match Uuid::parse_str(tenant_id) {
         Ok(uuid) => uuid,
         Err(_) => return Json(HttpResponse {
@@ -16,6 +16,10 @@ pub async fn get_all_locations_from_tenant(db: Connection<ShelfWatcherDatabase>,
         })
     };
 
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ViewTenant, &db).await {
+        return Json(err);
+    }
+
     match Location::get_all_from_tenant(tenant_uuid, &db).await {
         Ok(locations) => Json(HttpResponse {
             status: 200,
@@ -2,11 +2,11 @@ use mongodb::bson::Uuid;
 use rocket::{get, serde::json::Json};
 use rocket_db_pools::Connection;
 
-use crate::{db::ShelfWatcherDatabase, models::{http_response::HttpResponse, location::Location}};
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{http_response::HttpResponse, membership::TenantPermission, location::Location}};
 
 #[allow(unused)]
 #[get("/tenants/<tenant_id>/locations/<location_id>", format = "json")] 
-pub async fn get_location_by_id(db: Connection<ShelfWatcherDatabase>, tenant_id: &str, location_id: &str) -> Json<HttpResponse<Location>> {
+pub async fn get_location_by_id(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, location_id: &str) -> Json<HttpResponse<Location>> {
     let tenant_uuid = match Uuid::parse_str(tenant_id) {
         Ok(uuid) => uuid,
         Err(err) => return Json(HttpResponse {
@@ -16,6 +16,10 @@ pub async fn get_location_by_id(db: Connection<ShelfWatcherDatabase>, tenant_id:
         })
     };
 
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ViewTenant, &db).await {
+        return Json(err);
+    }
+
     let location_uuid = match Uuid::parse_str(location_id) {
         Ok(uuid) => uuid,
         Err(err) => return Json(HttpResponse {
@@ -25,7 +29,7 @@ pub async fn get_location_by_id(db: Connection<ShelfWatcherDatabase>, tenant_id:
         })
     };
 
-    match Location::get_by_id(location_uuid, &db).await {
+    match Location::get_from_tenant(location_uuid, tenant_uuid, &db).await {
         Ok(tenant) => Json(HttpResponse {
             status: 200,
             message: "Found location by id".to_string(),
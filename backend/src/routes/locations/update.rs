@@ -3,7 +3,7 @@ use mongodb::bson::Uuid;
 use rocket::{error, patch, serde::{json::Json, Deserialize}};
 use rocket_db_pools::Connection;
 
-use crate::{db::ShelfWatcherDatabase, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, location::Location}};
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, membership::TenantPermission, location::Location}};
 
 #[derive(Deserialize)]
 #[serde(crate = "rocket::serde")]
@@ -13,7 +13,7 @@ pub struct UpdateLocationData {
 
 #[allow(unused)]
 #[patch("/tenants/<tenant_id>/locations/<location_id>", format = "json", data = "<data>")] 
-pub async fn update_location(db: Connection<ShelfWatcherDatabase>, tenant_id: &str, location_id: &str, data: Json<UpdateLocationData>) -> Json<HttpResponse<Location>> { 
+pub async fn update_location(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, location_id: &str, data: Json<UpdateLocationData>) -> Json<HttpResponse<Location>> { 
     let data = data.into_inner();
 
     let tenant_uuid = match Uuid::parse_str(tenant_id) {
@@ -25,6 +25,10 @@ pub async fn update_location(db: Connection<ShelfWatcherDatabase>, tenant_id: &s
         })
     };
 
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ManageLocations, &db).await {
+        return Json(err);
+    }
+
     let location_uuid = match Uuid::parse_str(location_id) {
         Ok(uuid) => uuid,
         Err(err) => return Json(HttpResponse {
@@ -34,7 +38,7 @@ pub async fn update_location(db: Connection<ShelfWatcherDatabase>, tenant_id: &s
         })
     };
 
-    let old_location = match Location::get_by_id(location_uuid, &db).await {
+    let old_location = match Location::get_from_tenant(location_uuid, tenant_uuid, &db).await {
         Ok(location) => location,
         Err(err) => return Json(err)
     };
@@ -44,8 +48,8 @@ pub async fn update_location(db: Connection<ShelfWatcherDatabase>, tenant_id: &s
     let mut old_values: HashMap<String, String> = HashMap::new();
     let mut new_values: HashMap<String, String> = HashMap::new();
 
-    if data.name.is_some() {
-        new_location.name = data.name.unwrap();
+    if let Some(name) = data.name {
+        new_location.name = name;
         old_values.insert("name".to_owned(), old_location.name.clone());
         new_values.insert("name".to_owned(), new_location.name.clone());
     }
@@ -60,8 +64,7 @@ pub async fn update_location(db: Connection<ShelfWatcherDatabase>, tenant_id: &s
 
     match new_location.update(&db).await {
         Ok(location) => {
-            // TODO: Implement author_id
-            match AuditLog::new(location.id, AuditLogEntityType::Location, AuditLogAction::Update, "Location updated.".to_string(), Uuid::new(), Some(old_values), Some(new_values)).insert(&db).await {
+            match AuditLog::new(location.id, AuditLogEntityType::Location, AuditLogAction::Update, "Location updated.".to_string(), auth.author(), Some(old_values), Some(new_values)).insert(&db).await {
                 Ok(_) => (),
                 Err(err) => error!("{}", err)
             }
@@ -1,4 +1,19 @@
+pub mod alerts;
+pub mod api_keys;
 pub mod audit_logs;
+pub mod auth;
+pub mod catchers;
+pub mod invitations;
 pub mod tenants;
+pub mod two_factor;
 pub mod locations;
+pub mod product_groups;
+pub mod products;
+pub mod product_batches;
+pub mod items;
+pub mod stock;
+pub mod stocktakes;
+pub mod storage_areas;
+pub mod reorder_points;
+pub mod transfers;
 pub mod users;
@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+use mongodb::bson::Uuid;
+use rocket::{error, post, serde::{json::Json, Deserialize}};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{audit_log::{AuditLog, AuditLogAction, AuditLogAuthor, AuditLogEntityType}, http_response::HttpResponse, location::Location, membership::TenantPermission, product_batch::ProductBatch, stock_lock::StockLock, stock_movement::{StockMovement, StockMovementType}}};
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct AdjustProductBatchData {
+    // The counted quantity, the difference to the stock on hand is booked as an adjustment
+    quantity: f64,
+    reason: String,
+    // Defaults to the location the batch was received at
+    #[serde(rename = "locationId")]
+    location_id: Option<String>
+}
+
+// Books the difference between the counted quantity and the stock on hand, while the caller holds the stock lock
+async fn book_adjustment(batch: &ProductBatch, location_uuid: Uuid, quantity: f64, author: AuditLogAuthor, db: &Connection<ShelfWatcherDatabase>) -> Result<(f64, StockMovement), HttpResponse<StockMovement>> {
+    let on_hand = match StockMovement::quantity_at(batch.tenant_id, batch.product_id, Some(batch.id), location_uuid, db).await {
+        Ok(quantity) => quantity,
+        Err(err) => return Err(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    };
+
+    let difference = quantity - on_hand;
+    if difference == 0.0 {
+        return Err(HttpResponse {
+            status: 400,
+            message: "Counted quantity matches the stock on hand".to_string(),
+            data: None
+        });
+    }
+
+    let (from_location, to_location) = if difference < 0.0 { (Some(location_uuid), None) } else { (None, Some(location_uuid)) };
+    let movement = StockMovement::new(batch.tenant_id, StockMovementType::Adjustment, batch.product_id, Some(batch.id), from_location, to_location, None, None, difference.abs(), "BATCH_ADJUSTED", Some(batch.id), author)?;
+
+    match movement.insert(db).await {
+        Ok(movement) => Ok((on_hand, movement)),
+        Err(err) => Err(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    }
+}
+
+#[allow(unused)]
+#[post("/tenants/<tenant_id>/batches/<batch_id>/adjust", format = "json", data = "<data>")]
+pub async fn adjust_product_batch(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, batch_id: &str, data: Json<AdjustProductBatchData>) -> Json<HttpResponse<StockMovement>> {
+    let data = data.into_inner();
+
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::MoveStock, &db).await {
+        return Json(err);
+    }
+
+    let batch_uuid = match Uuid::parse_str(batch_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid batch UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if data.reason.trim().is_empty() {
+        return Json(HttpResponse {
+            status: 400,
+            message: "A reason is required".to_string(),
+            data: None
+        });
+    }
+
+    if data.quantity < 0.0 {
+        return Json(HttpResponse {
+            status: 400,
+            message: "Quantity must not be negative".to_string(),
+            data: None
+        });
+    }
+
+    let batch = match ProductBatch::get_from_tenant(batch_uuid, tenant_uuid, &db).await {
+        Ok(batch) => batch,
+        Err(err) => return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    };
+
+    if batch.closed {
+        return Json(HttpResponse {
+            status: 400,
+            message: "Batch is closed".to_string(),
+            data: None
+        });
+    }
+
+    let location_uuid = match data.location_id.as_deref().map(Uuid::parse_str) {
+        Some(Ok(uuid)) => uuid,
+        Some(Err(err)) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid location UUID: {:?}", err),
+            data: None
+        }),
+        None => batch.location_id
+    };
+
+    if let Err(err) = Location::get_from_tenant(location_uuid, tenant_uuid, &db).await {
+        return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        });
+    }
+
+    // The stock on hand must not change between reading it and booking the difference, see `StockLock`
+    let lock = match StockLock::acquire(tenant_uuid, batch.product_id, location_uuid, &db).await {
+        Ok(lock) => lock,
+        Err(err) => return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    };
+    let booked = book_adjustment(&batch, location_uuid, data.quantity, auth.author(), &db).await;
+    lock.release(&db).await;
+
+    match booked {
+        Ok((on_hand, movement)) => {
+            let old_values = HashMap::from([("quantity".to_string(), on_hand.to_string())]);
+            let new_values = HashMap::from([
+                ("quantity".to_string(), data.quantity.to_string()),
+                ("locationId".to_string(), location_uuid.to_string()),
+                ("movementId".to_string(), movement.id.to_string())
+            ]);
+            match AuditLog::new(batch.id, AuditLogEntityType::ProductBatch, AuditLogAction::Update, format!("Batch adjusted: {}", data.reason.trim()), auth.author(), Some(old_values), Some(new_values)).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            Json(HttpResponse {
+                status: 200,
+                message: "Batch adjusted".to_string(),
+                data: Some(movement)
+            })
+        },
+        Err(err) => Json(err)
+    }
+}
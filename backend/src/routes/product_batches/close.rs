@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+use mongodb::bson::{DateTime, Uuid};
+use rocket::{error, post, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, membership::TenantPermission, product_batch::ProductBatch, stock_movement::StockMovement}};
+
+// Closed batches are kept for traceability but no longer take part in stock operations, so only empty batches can be closed
+#[allow(unused)]
+#[post("/tenants/<tenant_id>/batches/<batch_id>/close", format = "json")]
+pub async fn close_product_batch(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, batch_id: &str) -> Json<HttpResponse<ProductBatch>> {
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::MoveStock, &db).await {
+        return Json(err);
+    }
+
+    let batch_uuid = match Uuid::parse_str(batch_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid batch UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let mut batch = match ProductBatch::get_from_tenant(batch_uuid, tenant_uuid, &db).await {
+        Ok(batch) => batch,
+        Err(err) => return Json(err)
+    };
+
+    if batch.closed {
+        return Json(HttpResponse {
+            status: 400,
+            message: "Batch is already closed".to_string(),
+            data: None
+        });
+    }
+
+    // Movements of closed batches are rejected, stock left in one could never be moved or written off
+    let on_hand = match StockMovement::on_hand(tenant_uuid, Some(batch.product_id), Some(batch.id), None, &db).await {
+        Ok(levels) => levels.iter().filter(|level| level.batch_id == Some(batch.id)).map(|level| level.quantity).sum::<f64>(),
+        Err(err) => return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    };
+    if on_hand != 0.0 {
+        return Json(HttpResponse {
+            status: 400,
+            message: format!("Batch still has {} on hand, move or write it off before closing", on_hand),
+            data: None
+        });
+    }
+
+    batch.closed = true;
+    batch.closed_at = Some(DateTime::now().to_string());
+
+    match batch.update(&db).await {
+        Ok(batch) => {
+            let old_values = HashMap::from([("closed".to_string(), "false".to_string())]);
+            let new_values = HashMap::from([
+                ("closed".to_string(), "true".to_string()),
+                ("quantity".to_string(), batch.quantity.to_string())
+            ]);
+            match AuditLog::new(batch.id, AuditLogEntityType::ProductBatch, AuditLogAction::Update, "Batch closed.".to_string(), auth.author(), Some(old_values), Some(new_values)).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            Json(HttpResponse {
+                status: 200,
+                message: "Batch closed".to_string(),
+                data: Some(batch)
+            })
+        },
+        Err(err) => Json(err)
+    }
+}
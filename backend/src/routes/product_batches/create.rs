@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use mongodb::bson::Uuid;
+use rocket::{error, post, serde::{json::Json, Deserialize}};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, location::Location, membership::TenantPermission, product::Product, product_batch::ProductBatch, stock_movement::{StockMovement, StockMovementType}}};
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CreateProductBatchData {
+    #[serde(rename = "productId")]
+    product_id: String,
+    #[serde(rename = "locationId")]
+    location_id: String,
+    #[serde(rename = "lotNumber")]
+    lot_number: String,
+    // Defaults to today
+    #[serde(rename = "receivedDate")]
+    received_date: Option<String>,
+    #[serde(rename = "expiryDate")]
+    expiry_date: Option<String>,
+    quantity: f64,
+    #[serde(rename = "unitCost")]
+    unit_cost: f64
+}
+
+#[allow(unused)]
+#[post("/tenants/<tenant_id>/batches", format = "json", data = "<data>")]
+pub async fn create_product_batch(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, data: Json<CreateProductBatchData>) -> Json<HttpResponse<ProductBatch>> {
+    let data = data.into_inner();
+
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let tenant = match auth.require_permission(tenant_uuid, TenantPermission::MoveStock, &db).await {
+        Ok(tenant) => tenant,
+        Err(err) => return Json(err)
+    };
+
+    let product_uuid = match Uuid::parse_str(&data.product_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid product UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let location_uuid = match Uuid::parse_str(&data.location_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid location UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    match Product::get_from_tenant(product_uuid, tenant_uuid, &db).await {
+        Ok(product) if product.active => (),
+        Ok(_) => return Json(HttpResponse {
+            status: 400,
+            message: "Product is inactive".to_string(),
+            data: None
+        }),
+        Err(err) => return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    }
+
+    if let Err(err) = Location::get_from_tenant(location_uuid, tenant_uuid, &db).await {
+        return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        });
+    }
+
+    if data.lot_number.trim().is_empty() {
+        return Json(HttpResponse {
+            status: 400,
+            message: "Lot number must not be empty".to_string(),
+            data: None
+        });
+    }
+
+    if data.quantity < 0.0 || data.unit_cost < 0.0 {
+        return Json(HttpResponse {
+            status: 400,
+            message: "Quantity and unit cost must not be negative".to_string(),
+            data: None
+        });
+    }
+
+    let received_date = match data.received_date {
+        Some(received_date) => match ProductBatch::parse_date(&received_date, "received date") {
+            Ok(date) => date,
+            Err(err) => return Json(err)
+        },
+        None => tenant.settings.today()
+    };
+
+    let expiry_date = match data.expiry_date {
+        Some(expiry_date) => match ProductBatch::parse_date(&expiry_date, "expiry date") {
+            Ok(date) => Some(date),
+            Err(err) => return Json(err)
+        },
+        None => None
+    };
+
+    let batch = ProductBatch::new(tenant_uuid, product_uuid, location_uuid, data.lot_number.trim().to_string(), received_date, expiry_date, data.quantity, data.unit_cost, tenant.settings.currency.clone());
+
+    // The received quantity enters the stock ledger, on-hand stock is derived from there
+    let receipt = if batch.quantity > 0.0 {
+        match StockMovement::new(tenant_uuid, StockMovementType::Receipt, product_uuid, Some(batch.id), None, Some(location_uuid), None, None, batch.quantity, "BATCH_RECEIVED", Some(batch.id), auth.author()) {
+            Ok(receipt) => Some(receipt),
+            Err(err) => return Json(err)
+        }
+    } else {
+        None
+    };
+
+    match batch.insert(&db).await {
+        Ok(batch) => {
+            if let Some(receipt) = receipt {
+                if let Err(err) = receipt.insert(&db).await {
+                    return Json(HttpResponse {
+                        status: err.status,
+                        message: err.message,
+                        data: None
+                    });
+                }
+            }
+
+            let new_values = HashMap::from([
+                ("productId".to_string(), batch.product_id.to_string()),
+                ("locationId".to_string(), batch.location_id.to_string()),
+                ("lotNumber".to_string(), batch.lot_number.clone()),
+                ("quantity".to_string(), batch.quantity.to_string())
+            ]);
+            match AuditLog::new(batch.id, AuditLogEntityType::ProductBatch, AuditLogAction::Create, "Batch created.".to_string(), auth.author(), None, Some(new_values)).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            Json(HttpResponse {
+                status: 201,
+                message: "Batch created".to_string(),
+                data: Some(batch)
+            })
+        },
+        Err(err) => Json(err)
+    }
+}
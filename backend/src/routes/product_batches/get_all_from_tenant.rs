@@ -0,0 +1,51 @@
+use mongodb::bson::Uuid;
+use rocket::{get, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{http_response::HttpResponse, membership::TenantPermission, product_batch::ProductBatch}};
+
+#[allow(unused)]
+#[get("/tenants/<tenant_id>/batches?<product_id>&<location_id>&<include_closed>", format = "json")]
+pub async fn get_all_product_batches_from_tenant(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, product_id: Option<&str>, location_id: Option<&str>, include_closed: Option<bool>) -> Json<HttpResponse<Vec<ProductBatch>>> {
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ViewTenant, &db).await {
+        return Json(err);
+    }
+
+    let product_uuid = match product_id.map(Uuid::parse_str) {
+        Some(Ok(uuid)) => Some(uuid),
+        Some(Err(err)) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid product UUID: {:?}", err),
+            data: None
+        }),
+        None => None
+    };
+
+    let location_uuid = match location_id.map(Uuid::parse_str) {
+        Some(Ok(uuid)) => Some(uuid),
+        Some(Err(err)) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid location UUID: {:?}", err),
+            data: None
+        }),
+        None => None
+    };
+
+    match ProductBatch::get_all_from_tenant(tenant_uuid, product_uuid, location_uuid, include_closed.unwrap_or(false), &db).await {
+        Ok(batches) => Json(HttpResponse {
+            status: 200,
+            message: "Successfully retrieved batches".to_string(),
+            data: Some(batches)
+        }),
+        Err(err) => Json(err)
+    }
+}
@@ -0,0 +1,40 @@
+use mongodb::bson::Uuid;
+use rocket::{get, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{http_response::HttpResponse, membership::TenantPermission, product_batch::ProductBatch}};
+
+#[allow(unused)]
+#[get("/tenants/<tenant_id>/batches/<batch_id>", format = "json")]
+pub async fn get_product_batch_by_id(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, batch_id: &str) -> Json<HttpResponse<ProductBatch>> {
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ViewTenant, &db).await {
+        return Json(err);
+    }
+
+    let batch_uuid = match Uuid::parse_str(batch_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid batch UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    match ProductBatch::get_from_tenant(batch_uuid, tenant_uuid, &db).await {
+        Ok(batch) => Json(HttpResponse {
+            status: 200,
+            message: "Found batch by id".to_string(),
+            data: Some(batch)
+        }),
+        Err(err) => Json(err)
+    }
+}
@@ -0,0 +1,5 @@
+pub mod create;
+pub mod get_all_from_tenant;
+pub mod get_by_id;
+pub mod adjust;
+pub mod close;
\ No newline at end of file
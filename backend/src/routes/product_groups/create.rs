@@ -0,0 +1,94 @@
+use mongodb::bson::Uuid;
+use rocket::{error, post, serde::{json::Json, Deserialize}};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, membership::TenantPermission, product_group::ProductGroup}};
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CreateProductGroupData {
+    name: String,
+    description: Option<String>,
+    #[serde(rename = "parentId")]
+    parent_id: Option<String>
+}
+
+#[allow(unused)]
+#[post("/tenants/<tenant_id>/product-groups", format = "json", data = "<data>")]
+pub async fn create_product_group(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, data: Json<CreateProductGroupData>) -> Json<HttpResponse<ProductGroup>> {
+    let data = data.into_inner();
+
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ManageCatalog, &db).await {
+        return Json(err);
+    }
+
+    if data.name.trim().is_empty() {
+        return Json(HttpResponse {
+            status: 400,
+            message: "Product group name must not be empty".to_string(),
+            data: None
+        });
+    }
+
+    let parent_id = match data.parent_id {
+        Some(parent_id) => match Uuid::parse_str(parent_id) {
+            Ok(uuid) => Some(uuid),
+            Err(err) => return Json(HttpResponse {
+                status: 400,
+                message: format!("Invalid parent UUID: {:?}", err),
+                data: None
+            })
+        },
+        None => None
+    };
+
+    if let Some(parent_id) = parent_id {
+        if let Err(err) = ProductGroup::validate_parent(None, parent_id, tenant_uuid, &db).await {
+            return Json(err);
+        }
+    }
+
+    let existing = match ProductGroup::get_all_from_tenant(tenant_uuid, &db).await {
+        Ok(groups) => groups,
+        Err(err) => return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    };
+
+    if existing.iter().any(|group| group.parent_id == parent_id && group.name == data.name) {
+        return Json(HttpResponse {
+            status: 400,
+            message: "Product group already exists".to_string(),
+            data: None
+        });
+    }
+
+    let group = ProductGroup::new(tenant_uuid, data.name, data.description, parent_id);
+
+    match group.insert(&db).await {
+        Ok(group) => {
+            match AuditLog::new(group.id, AuditLogEntityType::ProductGroup, AuditLogAction::Create, "Product group created.".to_string(), auth.author(), None, None).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            Json(HttpResponse {
+                status: 201,
+                message: "Product group created".to_string(),
+                data: Some(group)
+            })
+        },
+        Err(err) => Json(err)
+    }
+}
@@ -0,0 +1,76 @@
+use mongodb::bson::Uuid;
+use rocket::{delete, error, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, membership::TenantPermission, product::Product, product_group::ProductGroup}};
+
+#[allow(unused)]
+#[delete("/tenants/<tenant_id>/product-groups/<group_id>", format = "json")]
+pub async fn delete_product_group(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, group_id: &str) -> Json<HttpResponse<()>> {
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ManageCatalog, &db).await {
+        return Json(err);
+    }
+
+    let group_uuid = match Uuid::parse_str(group_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid product group UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let group = match ProductGroup::get_from_tenant(group_uuid, tenant_uuid, &db).await {
+        Ok(group) => group,
+        Err(err) => return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    };
+
+    match Product::exists_in_group(group.id, &db).await {
+        Ok(false) => (),
+        Ok(true) => return Json(HttpResponse {
+            status: 400,
+            message: "Product group still contains products".to_string(),
+            data: None
+        }),
+        Err(err) => return Json(err)
+    }
+
+    match group.has_children(&db).await {
+        Ok(false) => (),
+        Ok(true) => return Json(HttpResponse {
+            status: 400,
+            message: "Product group still contains other product groups".to_string(),
+            data: None
+        }),
+        Err(err) => return Json(err)
+    }
+
+    match group.delete(&db).await {
+        Ok(group) => {
+            match AuditLog::new(group.id, AuditLogEntityType::ProductGroup, AuditLogAction::Delete, "Product group deleted.".to_string(), auth.author(), None, None).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            Json(HttpResponse {
+                status: 200,
+                message: "Product group deleted".to_string(),
+                data: None
+            })
+        },
+        Err(err) => Json(err)
+    }
+}
@@ -0,0 +1,31 @@
+use mongodb::bson::Uuid;
+use rocket::{get, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{http_response::HttpResponse, membership::TenantPermission, product_group::ProductGroup}};
+
+#[allow(unused)]
+#[get("/tenants/<tenant_id>/product-groups", format = "json")]
+pub async fn get_all_product_groups_from_tenant(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str) -> Json<HttpResponse<Vec<ProductGroup>>> {
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ViewTenant, &db).await {
+        return Json(err);
+    }
+
+    match ProductGroup::get_all_from_tenant(tenant_uuid, &db).await {
+        Ok(groups) => Json(HttpResponse {
+            status: 200,
+            message: "Successfully retrieved all product groups from tenant".to_string(),
+            data: Some(groups)
+        }),
+        Err(err) => Json(err)
+    }
+}
@@ -0,0 +1,40 @@
+use mongodb::bson::Uuid;
+use rocket::{get, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{http_response::HttpResponse, membership::TenantPermission, product_group::ProductGroup}};
+
+#[allow(unused)]
+#[get("/tenants/<tenant_id>/product-groups/<group_id>", format = "json")]
+pub async fn get_product_group_by_id(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, group_id: &str) -> Json<HttpResponse<ProductGroup>> {
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ViewTenant, &db).await {
+        return Json(err);
+    }
+
+    let group_uuid = match Uuid::parse_str(group_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid product group UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    match ProductGroup::get_from_tenant(group_uuid, tenant_uuid, &db).await {
+        Ok(group) => Json(HttpResponse {
+            status: 200,
+            message: "Found product group by id".to_string(),
+            data: Some(group)
+        }),
+        Err(err) => Json(err)
+    }
+}
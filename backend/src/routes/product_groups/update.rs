@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+use mongodb::bson::Uuid;
+use rocket::{error, patch, serde::{json::Json, Deserialize}};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, membership::TenantPermission, product_group::ProductGroup}};
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct UpdateProductGroupData {
+    name: Option<String>,
+    description: Option<String>,
+    // An empty string moves the group to the top level
+    #[serde(rename = "parentId")]
+    parent_id: Option<String>
+}
+
+#[allow(unused)]
+#[patch("/tenants/<tenant_id>/product-groups/<group_id>", format = "json", data = "<data>")]
+pub async fn update_product_group(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, group_id: &str, data: Json<UpdateProductGroupData>) -> Json<HttpResponse<ProductGroup>> {
+    let data = data.into_inner();
+
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ManageCatalog, &db).await {
+        return Json(err);
+    }
+
+    let group_uuid = match Uuid::parse_str(group_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid product group UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let old_group = match ProductGroup::get_from_tenant(group_uuid, tenant_uuid, &db).await {
+        Ok(group) => group,
+        Err(err) => return Json(err)
+    };
+
+    let mut new_group = old_group.clone();
+
+    let mut old_values: HashMap<String, String> = HashMap::new();
+    let mut new_values: HashMap<String, String> = HashMap::new();
+
+    if let Some(name) = data.name {
+        if name.trim().is_empty() {
+            return Json(HttpResponse {
+                status: 400,
+                message: "Product group name must not be empty".to_string(),
+                data: None
+            });
+        }
+
+        new_group.name = name;
+        old_values.insert("name".to_owned(), old_group.name.clone());
+        new_values.insert("name".to_owned(), new_group.name.clone());
+    }
+
+    if let Some(description) = data.description {
+        new_group.description = Some(description);
+        old_values.insert("description".to_owned(), old_group.description.clone().unwrap_or_default());
+        new_values.insert("description".to_owned(), new_group.description.clone().unwrap_or_default());
+    }
+
+    if let Some(parent_id) = data.parent_id {
+        new_group.parent_id = match parent_id.as_str() {
+            "" => None,
+            parent_id => match Uuid::parse_str(parent_id) {
+                Ok(uuid) => Some(uuid),
+                Err(err) => return Json(HttpResponse {
+                    status: 400,
+                    message: format!("Invalid parent UUID: {:?}", err),
+                    data: None
+                })
+            }
+        };
+
+        if let Some(parent_id) = new_group.parent_id {
+            if let Err(err) = ProductGroup::validate_parent(Some(new_group.id), parent_id, tenant_uuid, &db).await {
+                return Json(err);
+            }
+        }
+
+        old_values.insert("parentId".to_owned(), old_group.parent_id.map(|id| id.to_string()).unwrap_or_default());
+        new_values.insert("parentId".to_owned(), new_group.parent_id.map(|id| id.to_string()).unwrap_or_default());
+    }
+
+    if new_values.is_empty() {
+        return Json(HttpResponse {
+            status: 200,
+            message: "No updates applied.".to_string(),
+            data: Some(new_group)
+        });
+    }
+
+    if new_group.name != old_group.name || new_group.parent_id != old_group.parent_id {
+        let existing = match ProductGroup::get_all_from_tenant(tenant_uuid, &db).await {
+            Ok(groups) => groups,
+            Err(err) => return Json(HttpResponse {
+                status: err.status,
+                message: err.message,
+                data: None
+            })
+        };
+
+        if existing.iter().any(|group| group.id != new_group.id && group.parent_id == new_group.parent_id && group.name == new_group.name) {
+            return Json(HttpResponse {
+                status: 400,
+                message: "Product group already exists".to_string(),
+                data: None
+            });
+        }
+    }
+
+    match new_group.update(&db).await {
+        Ok(group) => {
+            match AuditLog::new(group.id, AuditLogEntityType::ProductGroup, AuditLogAction::Update, "Product group updated.".to_string(), auth.author(), Some(old_values), Some(new_values)).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            Json(HttpResponse {
+                status: 200,
+                message: "Product group updated".to_string(),
+                data: Some(group)
+            })
+        },
+        Err(err) => Json(err)
+    }
+}
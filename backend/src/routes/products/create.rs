@@ -0,0 +1,105 @@
+use mongodb::bson::Uuid;
+use rocket::{error, post, serde::{json::Json, Deserialize}};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, membership::TenantPermission, product::{Product, UnitOfMeasure}, product_group::ProductGroup, tenant_plan::QuotaResource}};
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CreateProductData {
+    name: String,
+    sku: String,
+    #[serde(default)]
+    barcodes: Vec<String>,
+    unit: String,
+    #[serde(rename = "productGroupId")]
+    product_group_id: Option<String>,
+    #[serde(rename = "imageUrl")]
+    image_url: Option<String>
+}
+
+#[allow(unused)]
+#[post("/tenants/<tenant_id>/products", format = "json", data = "<data>")]
+pub async fn create_product(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, data: Json<CreateProductData>) -> Json<HttpResponse<Product>> {
+    let data = data.into_inner();
+
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let tenant = match auth.require_permission(tenant_uuid, TenantPermission::ManageCatalog, &db).await {
+        Ok(tenant) => tenant,
+        Err(err) => return Json(err)
+    };
+
+    if let Err(err) = tenant.require_quota(QuotaResource::Products, &db).await {
+        return Json(err);
+    }
+
+    if data.name.trim().is_empty() || data.sku.trim().is_empty() {
+        return Json(HttpResponse {
+            status: 400,
+            message: "Product name and SKU must not be empty".to_string(),
+            data: None
+        });
+    }
+
+    let unit = match UnitOfMeasure::from_string(&data.unit) {
+        Ok(unit) => unit,
+        Err(err) => return Json(err)
+    };
+
+    let barcodes = match Product::normalize_barcodes(data.barcodes) {
+        Ok(barcodes) => barcodes,
+        Err(err) => return Json(err)
+    };
+
+    let product_group_id = match data.product_group_id {
+        Some(product_group_id) => match Uuid::parse_str(product_group_id) {
+            Ok(uuid) => Some(uuid),
+            Err(err) => return Json(HttpResponse {
+                status: 400,
+                message: format!("Invalid product group UUID: {:?}", err),
+                data: None
+            })
+        },
+        None => None
+    };
+
+    if let Some(product_group_id) = product_group_id {
+        if let Err(err) = ProductGroup::get_from_tenant(product_group_id, tenant_uuid, &db).await {
+            return Json(HttpResponse {
+                status: err.status,
+                message: err.message,
+                data: None
+            });
+        }
+    }
+
+    let product = Product::new(tenant_uuid, data.name, data.sku.trim().to_string(), barcodes, unit, product_group_id, data.image_url);
+
+    if let Err(err) = product.check_unique(&db).await {
+        return Json(err);
+    }
+
+    match product.insert(&db).await {
+        Ok(product) => {
+            match AuditLog::new(product.id, AuditLogEntityType::Product, AuditLogAction::Create, "Product created.".to_string(), auth.author(), None, None).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            Json(HttpResponse {
+                status: 201,
+                message: "Product created".to_string(),
+                data: Some(product)
+            })
+        },
+        Err(err) => Json(err)
+    }
+}
@@ -0,0 +1,66 @@
+use mongodb::bson::Uuid;
+use rocket::{delete, error, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, membership::TenantPermission, product::Product}};
+
+#[allow(unused)]
+#[delete("/tenants/<tenant_id>/products/<product_id>", format = "json")]
+pub async fn delete_product(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, product_id: &str) -> Json<HttpResponse<()>> {
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ManageCatalog, &db).await {
+        return Json(err);
+    }
+
+    let product_uuid = match Uuid::parse_str(product_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid product UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let product = match Product::get_from_tenant(product_uuid, tenant_uuid, &db).await {
+        Ok(product) => product,
+        Err(err) => return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    };
+
+    match product.get_delete_blocker(&db).await {
+        Ok(None) => (),
+        Ok(Some(blocker)) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Product still has {}", blocker),
+            data: None
+        }),
+        Err(err) => return Json(err)
+    }
+
+    match product.delete(&db).await {
+        Ok(product) => {
+            match AuditLog::new(product.id, AuditLogEntityType::Product, AuditLogAction::Delete, "Product deleted.".to_string(), auth.author(), None, None).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            Json(HttpResponse {
+                status: 200,
+                message: "Product deleted".to_string(),
+                data: None
+            })
+        },
+        Err(err) => Json(err)
+    }
+}
@@ -0,0 +1,43 @@
+use mongodb::bson::Uuid;
+use rocket::{get, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{http_response::HttpResponse, membership::TenantPermission, product::Product}};
+
+#[allow(unused)]
+#[get("/tenants/<tenant_id>/products?<product_group_id>", format = "json")]
+pub async fn get_all_products_from_tenant(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, product_group_id: Option<&str>) -> Json<HttpResponse<Vec<Product>>> {
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ViewTenant, &db).await {
+        return Json(err);
+    }
+
+    let product_group_uuid = match product_group_id {
+        Some(product_group_id) => match Uuid::parse_str(product_group_id) {
+            Ok(uuid) => Some(uuid),
+            Err(err) => return Json(HttpResponse {
+                status: 400,
+                message: format!("Invalid product group UUID: {:?}", err),
+                data: None
+            })
+        },
+        None => None
+    };
+
+    match Product::get_all_from_tenant(tenant_uuid, product_group_uuid, &db).await {
+        Ok(products) => Json(HttpResponse {
+            status: 200,
+            message: "Successfully retrieved all products from tenant".to_string(),
+            data: Some(products)
+        }),
+        Err(err) => Json(err)
+    }
+}
@@ -0,0 +1,41 @@
+use mongodb::bson::Uuid;
+use rocket::{get, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{http_response::HttpResponse, membership::TenantPermission, product::Product}};
+
+// Resolves a scanned barcode to the tenant's product in a single call
+#[allow(unused)]
+#[get("/tenants/<tenant_id>/products/by-barcode/<code>", format = "json")]
+pub async fn get_product_by_barcode(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, code: &str) -> Json<HttpResponse<Product>> {
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ViewTenant, &db).await {
+        return Json(err);
+    }
+
+    let code = code.trim();
+    if !Product::is_valid_barcode(code) {
+        return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid GTIN/EAN barcode: {}", code),
+            data: None
+        });
+    }
+
+    match Product::get_by_barcode(code, tenant_uuid, &db).await {
+        Ok(product) => Json(HttpResponse {
+            status: 200,
+            message: "Found product by barcode".to_string(),
+            data: Some(product)
+        }),
+        Err(err) => Json(err)
+    }
+}
@@ -0,0 +1,40 @@
+use mongodb::bson::Uuid;
+use rocket::{get, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{http_response::HttpResponse, membership::TenantPermission, product::Product}};
+
+#[allow(unused)]
+#[get("/tenants/<tenant_id>/products/<product_id>", format = "json")]
+pub async fn get_product_by_id(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, product_id: &str) -> Json<HttpResponse<Product>> {
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ViewTenant, &db).await {
+        return Json(err);
+    }
+
+    let product_uuid = match Uuid::parse_str(product_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid product UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    match Product::get_from_tenant(product_uuid, tenant_uuid, &db).await {
+        Ok(product) => Json(HttpResponse {
+            status: 200,
+            message: "Found product by id".to_string(),
+            data: Some(product)
+        }),
+        Err(err) => Json(err)
+    }
+}
@@ -0,0 +1,6 @@
+pub mod create;
+pub mod get_all_from_tenant;
+pub mod get_by_id;
+pub mod get_by_barcode;
+pub mod update;
+pub mod delete;
\ No newline at end of file
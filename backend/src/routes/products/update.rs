@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+use mongodb::bson::Uuid;
+use rocket::{error, patch, serde::{json::Json, Deserialize}};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, membership::TenantPermission, product::{Product, UnitOfMeasure}, product_group::ProductGroup}};
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct UpdateProductData {
+    name: Option<String>,
+    sku: Option<String>,
+    // Replaces the complete list of barcodes
+    barcodes: Option<Vec<String>>,
+    unit: Option<String>,
+    // An empty string removes the product from its group
+    #[serde(rename = "productGroupId")]
+    product_group_id: Option<String>,
+    // An empty string removes the image
+    #[serde(rename = "imageUrl")]
+    image_url: Option<String>,
+    active: Option<bool>
+}
+
+#[allow(unused)]
+#[patch("/tenants/<tenant_id>/products/<product_id>", format = "json", data = "<data>")]
+pub async fn update_product(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, product_id: &str, data: Json<UpdateProductData>) -> Json<HttpResponse<Product>> {
+    let data = data.into_inner();
+
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ManageCatalog, &db).await {
+        return Json(err);
+    }
+
+    let product_uuid = match Uuid::parse_str(product_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid product UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let old_product = match Product::get_from_tenant(product_uuid, tenant_uuid, &db).await {
+        Ok(product) => product,
+        Err(err) => return Json(err)
+    };
+
+    let mut new_product = old_product.clone();
+
+    let mut old_values: HashMap<String, String> = HashMap::new();
+    let mut new_values: HashMap<String, String> = HashMap::new();
+
+    if let Some(name) = data.name {
+        if name.trim().is_empty() {
+            return Json(HttpResponse {
+                status: 400,
+                message: "Product name must not be empty".to_string(),
+                data: None
+            });
+        }
+
+        new_product.name = name;
+        old_values.insert("name".to_owned(), old_product.name.clone());
+        new_values.insert("name".to_owned(), new_product.name.clone());
+    }
+
+    if let Some(sku) = data.sku {
+        if sku.trim().is_empty() {
+            return Json(HttpResponse {
+                status: 400,
+                message: "Product SKU must not be empty".to_string(),
+                data: None
+            });
+        }
+
+        new_product.sku = sku.trim().to_string();
+        old_values.insert("sku".to_owned(), old_product.sku.clone());
+        new_values.insert("sku".to_owned(), new_product.sku.clone());
+    }
+
+    if let Some(barcodes) = data.barcodes {
+        new_product.barcodes = match Product::normalize_barcodes(barcodes) {
+            Ok(barcodes) => barcodes,
+            Err(err) => return Json(err)
+        };
+        old_values.insert("barcodes".to_owned(), old_product.barcodes.join(","));
+        new_values.insert("barcodes".to_owned(), new_product.barcodes.join(","));
+    }
+
+    if let Some(unit) = data.unit {
+        new_product.unit = match UnitOfMeasure::from_string(&unit) {
+            Ok(unit) => unit,
+            Err(err) => return Json(err)
+        };
+        old_values.insert("unit".to_owned(), format!("{:?}", old_product.unit));
+        new_values.insert("unit".to_owned(), format!("{:?}", new_product.unit));
+    }
+
+    if let Some(product_group_id) = data.product_group_id {
+        new_product.product_group_id = match product_group_id.as_str() {
+            "" => None,
+            product_group_id => match Uuid::parse_str(product_group_id) {
+                Ok(uuid) => Some(uuid),
+                Err(err) => return Json(HttpResponse {
+                    status: 400,
+                    message: format!("Invalid product group UUID: {:?}", err),
+                    data: None
+                })
+            }
+        };
+
+        if let Some(product_group_id) = new_product.product_group_id {
+            if let Err(err) = ProductGroup::get_from_tenant(product_group_id, tenant_uuid, &db).await {
+                return Json(HttpResponse {
+                    status: err.status,
+                    message: err.message,
+                    data: None
+                });
+            }
+        }
+
+        old_values.insert("productGroupId".to_owned(), old_product.product_group_id.map(|id| id.to_string()).unwrap_or_default());
+        new_values.insert("productGroupId".to_owned(), new_product.product_group_id.map(|id| id.to_string()).unwrap_or_default());
+    }
+
+    if let Some(image_url) = data.image_url {
+        new_product.image_url = match image_url.as_str() {
+            "" => None,
+            _ => Some(image_url)
+        };
+        old_values.insert("imageUrl".to_owned(), old_product.image_url.clone().unwrap_or_default());
+        new_values.insert("imageUrl".to_owned(), new_product.image_url.clone().unwrap_or_default());
+    }
+
+    if let Some(active) = data.active {
+        new_product.active = active;
+        old_values.insert("active".to_owned(), old_product.active.to_string());
+        new_values.insert("active".to_owned(), new_product.active.to_string());
+    }
+
+    if new_values.is_empty() {
+        return Json(HttpResponse {
+            status: 200,
+            message: "No updates applied.".to_string(),
+            data: Some(new_product)
+        });
+    }
+
+    if let Err(err) = new_product.check_unique(&db).await {
+        return Json(err);
+    }
+
+    match new_product.update(&db).await {
+        Ok(product) => {
+            match AuditLog::new(product.id, AuditLogEntityType::Product, AuditLogAction::Update, "Product updated.".to_string(), auth.author(), Some(old_values), Some(new_values)).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            Json(HttpResponse {
+                status: 200,
+                message: "Product updated".to_string(),
+                data: Some(product)
+            })
+        },
+        Err(err) => Json(err)
+    }
+}
@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+use mongodb::bson::Uuid;
+use rocket::{error, post, serde::{json::Json, Deserialize}};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, location::Location, membership::TenantPermission, product::Product, reorder_point::ReorderPoint}};
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CreateReorderPointData {
+    #[serde(rename = "productId")]
+    product_id: String,
+    #[serde(rename = "locationId")]
+    location_id: String,
+    #[serde(rename = "minLevel")]
+    min_level: f64,
+    #[serde(rename = "reorderPoint")]
+    reorder_point: f64,
+    #[serde(rename = "maxLevel")]
+    max_level: f64
+}
+
+#[allow(unused)]
+#[post("/tenants/<tenant_id>/reorder-points", format = "json", data = "<data>")]
+pub async fn create_reorder_point(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, data: Json<CreateReorderPointData>) -> Json<HttpResponse<ReorderPoint>> {
+    let data = data.into_inner();
+
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ManageLocations, &db).await {
+        return Json(err);
+    }
+
+    let product_uuid = match Uuid::parse_str(&data.product_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid product UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let location_uuid = match Uuid::parse_str(&data.location_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid location UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = Product::get_from_tenant(product_uuid, tenant_uuid, &db).await {
+        return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        });
+    }
+
+    if let Err(err) = Location::get_from_tenant(location_uuid, tenant_uuid, &db).await {
+        return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        });
+    }
+
+    match ReorderPoint::exists(tenant_uuid, product_uuid, location_uuid, &db).await {
+        Ok(false) => (),
+        Ok(true) => return Json(HttpResponse {
+            status: 400,
+            message: "This product already has a reorder point in this location".to_string(),
+            data: None
+        }),
+        Err(err) => return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    }
+
+    let reorder_point = ReorderPoint::new(tenant_uuid, product_uuid, location_uuid, data.min_level, data.reorder_point, data.max_level);
+
+    if let Err(err) = reorder_point.validate_levels() {
+        return Json(err);
+    }
+
+    match reorder_point.insert(&db).await {
+        Ok(reorder_point) => {
+            let new_values = HashMap::from([
+                ("productId".to_string(), reorder_point.product_id.to_string()),
+                ("locationId".to_string(), reorder_point.location_id.to_string()),
+                ("minLevel".to_string(), reorder_point.min_level.to_string()),
+                ("reorderPoint".to_string(), reorder_point.reorder_point.to_string()),
+                ("maxLevel".to_string(), reorder_point.max_level.to_string())
+            ]);
+            match AuditLog::new(reorder_point.id, AuditLogEntityType::ReorderPoint, AuditLogAction::Create, "Reorder point created.".to_string(), auth.author(), None, Some(new_values)).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            Json(HttpResponse {
+                status: 201,
+                message: "Reorder point created".to_string(),
+                data: Some(reorder_point)
+            })
+        },
+        Err(err) => Json(err)
+    }
+}
@@ -0,0 +1,56 @@
+use mongodb::bson::Uuid;
+use rocket::{delete, error, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, membership::TenantPermission, reorder_point::ReorderPoint}};
+
+#[allow(unused)]
+#[delete("/tenants/<tenant_id>/reorder-points/<reorder_point_id>", format = "json")]
+pub async fn delete_reorder_point(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, reorder_point_id: &str) -> Json<HttpResponse<()>> {
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ManageLocations, &db).await {
+        return Json(err);
+    }
+
+    let reorder_point_uuid = match Uuid::parse_str(reorder_point_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid reorder point UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let reorder_point = match ReorderPoint::get_from_tenant(reorder_point_uuid, tenant_uuid, &db).await {
+        Ok(reorder_point) => reorder_point,
+        Err(err) => return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    };
+
+    match reorder_point.delete(&db).await {
+        Ok(reorder_point) => {
+            match AuditLog::new(reorder_point.id, AuditLogEntityType::ReorderPoint, AuditLogAction::Delete, "Reorder point deleted.".to_string(), auth.author(), None, None).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            Json(HttpResponse {
+                status: 200,
+                message: "Reorder point deleted".to_string(),
+                data: None
+            })
+        },
+        Err(err) => Json(err)
+    }
+}
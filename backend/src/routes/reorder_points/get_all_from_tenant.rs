@@ -0,0 +1,51 @@
+use mongodb::bson::Uuid;
+use rocket::{get, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{http_response::HttpResponse, membership::TenantPermission, reorder_point::ReorderPoint}};
+
+#[allow(unused)]
+#[get("/tenants/<tenant_id>/reorder-points?<product_id>&<location_id>", format = "json")]
+pub async fn get_all_reorder_points_from_tenant(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, product_id: Option<&str>, location_id: Option<&str>) -> Json<HttpResponse<Vec<ReorderPoint>>> {
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ViewTenant, &db).await {
+        return Json(err);
+    }
+
+    let product_uuid = match product_id.map(Uuid::parse_str) {
+        Some(Ok(uuid)) => Some(uuid),
+        Some(Err(err)) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid product UUID: {:?}", err),
+            data: None
+        }),
+        None => None
+    };
+
+    let location_uuid = match location_id.map(Uuid::parse_str) {
+        Some(Ok(uuid)) => Some(uuid),
+        Some(Err(err)) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid location UUID: {:?}", err),
+            data: None
+        }),
+        None => None
+    };
+
+    match ReorderPoint::get_all_from_tenant(tenant_uuid, product_uuid, location_uuid, &db).await {
+        Ok(reorder_points) => Json(HttpResponse {
+            status: 200,
+            message: "Successfully retrieved reorder points".to_string(),
+            data: Some(reorder_points)
+        }),
+        Err(err) => Json(err)
+    }
+}
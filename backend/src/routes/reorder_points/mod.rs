@@ -0,0 +1,4 @@
+pub mod create;
+pub mod get_all_from_tenant;
+pub mod update;
+pub mod delete;
\ No newline at end of file
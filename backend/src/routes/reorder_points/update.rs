@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+use mongodb::bson::Uuid;
+use rocket::{error, patch, serde::{json::Json, Deserialize}};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, membership::TenantPermission, reorder_point::ReorderPoint}};
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct UpdateReorderPointData {
+    #[serde(rename = "minLevel")]
+    min_level: Option<f64>,
+    #[serde(rename = "reorderPoint")]
+    reorder_point: Option<f64>,
+    #[serde(rename = "maxLevel")]
+    max_level: Option<f64>
+}
+
+#[allow(unused)]
+#[patch("/tenants/<tenant_id>/reorder-points/<reorder_point_id>", format = "json", data = "<data>")]
+pub async fn update_reorder_point(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, reorder_point_id: &str, data: Json<UpdateReorderPointData>) -> Json<HttpResponse<ReorderPoint>> {
+    let data = data.into_inner();
+
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ManageLocations, &db).await {
+        return Json(err);
+    }
+
+    let reorder_point_uuid = match Uuid::parse_str(reorder_point_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid reorder point UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let mut reorder_point = match ReorderPoint::get_from_tenant(reorder_point_uuid, tenant_uuid, &db).await {
+        Ok(reorder_point) => reorder_point,
+        Err(err) => return Json(err)
+    };
+
+    let old_values = HashMap::from([
+        ("minLevel".to_string(), reorder_point.min_level.to_string()),
+        ("reorderPoint".to_string(), reorder_point.reorder_point.to_string()),
+        ("maxLevel".to_string(), reorder_point.max_level.to_string())
+    ]);
+
+    if let Some(min_level) = data.min_level {
+        reorder_point.min_level = min_level;
+    }
+    if let Some(level) = data.reorder_point {
+        reorder_point.reorder_point = level;
+    }
+    if let Some(max_level) = data.max_level {
+        reorder_point.max_level = max_level;
+    }
+
+    if let Err(err) = reorder_point.validate_levels() {
+        return Json(err);
+    }
+
+    let new_values = HashMap::from([
+        ("minLevel".to_string(), reorder_point.min_level.to_string()),
+        ("reorderPoint".to_string(), reorder_point.reorder_point.to_string()),
+        ("maxLevel".to_string(), reorder_point.max_level.to_string())
+    ]);
+
+    match reorder_point.update(&db).await {
+        Ok(reorder_point) => {
+            match AuditLog::new(reorder_point.id, AuditLogEntityType::ReorderPoint, AuditLogAction::Update, "Reorder point updated.".to_string(), auth.author(), Some(old_values), Some(new_values)).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            Json(HttpResponse {
+                status: 200,
+                message: "Reorder point updated".to_string(),
+                data: Some(reorder_point)
+            })
+        },
+        Err(err) => Json(err)
+    }
+}
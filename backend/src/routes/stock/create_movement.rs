@@ -0,0 +1,213 @@
+use mongodb::bson::Uuid;
+use rocket::{post, serde::{json::Json, Deserialize}};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{http_response::HttpResponse, location::Location, membership::TenantPermission, product::Product, product_batch::ProductBatch, stock_movement::{StockMovement, StockMovementType}, storage_area::StorageArea}};
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CreateStockMovementData {
+    #[serde(rename = "type")]
+    movement_type: String,
+    #[serde(rename = "productId")]
+    product_id: String,
+    #[serde(rename = "batchId")]
+    batch_id: Option<String>,
+    #[serde(rename = "fromLocationId")]
+    from_location_id: Option<String>,
+    #[serde(rename = "toLocationId")]
+    to_location_id: Option<String>,
+    // Bins inside the locations, a transfer within one location puts stock away or moves it between bins
+    #[serde(rename = "fromAreaId")]
+    from_area_id: Option<String>,
+    #[serde(rename = "toAreaId")]
+    to_area_id: Option<String>,
+    // Always positive, the direction follows from the locations
+    quantity: f64,
+    #[serde(rename = "reasonCode")]
+    reason_code: String
+}
+
+#[allow(unused)]
+#[post("/tenants/<tenant_id>/stock/movements", format = "json", data = "<data>")]
+pub async fn create_stock_movement(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, data: Json<CreateStockMovementData>) -> Json<HttpResponse<StockMovement>> {
+    let data = data.into_inner();
+
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let tenant = match auth.require_permission(tenant_uuid, TenantPermission::MoveStock, &db).await {
+        Ok(tenant) => tenant,
+        Err(err) => return Json(err)
+    };
+
+    let movement_type = match StockMovementType::from_string(&data.movement_type) {
+        Ok(movement_type) => movement_type,
+        Err(err) => return Json(err)
+    };
+
+    let product_uuid = match Uuid::parse_str(&data.product_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid product UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let batch_uuid = match data.batch_id.as_deref().map(Uuid::parse_str) {
+        Some(Ok(uuid)) => Some(uuid),
+        Some(Err(err)) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid batch UUID: {:?}", err),
+            data: None
+        }),
+        None => None
+    };
+
+    let from_location_uuid = match data.from_location_id.as_deref().map(Uuid::parse_str) {
+        Some(Ok(uuid)) => Some(uuid),
+        Some(Err(err)) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid source location UUID: {:?}", err),
+            data: None
+        }),
+        None => None
+    };
+
+    let to_location_uuid = match data.to_location_id.as_deref().map(Uuid::parse_str) {
+        Some(Ok(uuid)) => Some(uuid),
+        Some(Err(err)) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid destination location UUID: {:?}", err),
+            data: None
+        }),
+        None => None
+    };
+
+    let from_area_uuid = match data.from_area_id.as_deref().map(Uuid::parse_str) {
+        Some(Ok(uuid)) => Some(uuid),
+        Some(Err(err)) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid source bin UUID: {:?}", err),
+            data: None
+        }),
+        None => None
+    };
+
+    let to_area_uuid = match data.to_area_id.as_deref().map(Uuid::parse_str) {
+        Some(Ok(uuid)) => Some(uuid),
+        Some(Err(err)) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid destination bin UUID: {:?}", err),
+            data: None
+        }),
+        None => None
+    };
+
+    // One-sided transfers leave goods in transit, which only transfer documents keep track of
+    if movement_type == StockMovementType::Transfer && (from_location_uuid.is_none() || to_location_uuid.is_none()) {
+        return Json(HttpResponse {
+            status: 400,
+            message: "Transfers need a source and a destination location, use a transfer document to ship goods".to_string(),
+            data: None
+        });
+    }
+
+    let movement = match StockMovement::new(tenant_uuid, movement_type, product_uuid, batch_uuid, from_location_uuid, to_location_uuid, from_area_uuid, to_area_uuid, data.quantity, &data.reason_code, None, auth.author()) {
+        Ok(movement) => movement,
+        Err(err) => return Json(err)
+    };
+
+    match Product::get_from_tenant(product_uuid, tenant_uuid, &db).await {
+        Ok(product) if product.active || movement_type != StockMovementType::Receipt => (),
+        Ok(_) => return Json(HttpResponse {
+            status: 400,
+            message: "Product is inactive".to_string(),
+            data: None
+        }),
+        Err(err) => return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    }
+
+    if let Some(batch_uuid) = batch_uuid {
+        match ProductBatch::get_from_tenant(batch_uuid, tenant_uuid, &db).await {
+            Ok(batch) if batch.product_id != product_uuid => return Json(HttpResponse {
+                status: 400,
+                message: "Batch belongs to another product".to_string(),
+                data: None
+            }),
+            Ok(batch) if batch.closed => return Json(HttpResponse {
+                status: 400,
+                message: "Batch is closed".to_string(),
+                data: None
+            }),
+            Ok(batch) if matches!(movement_type, StockMovementType::Issue | StockMovementType::Transfer) => {
+                if let Err(err) = batch.require_not_expired(&tenant.settings.today()) {
+                    return Json(err);
+                }
+            },
+            Ok(_) => (),
+            Err(err) => return Json(HttpResponse {
+                status: err.status,
+                message: err.message,
+                data: None
+            })
+        }
+    }
+
+    for location_uuid in [from_location_uuid, to_location_uuid].into_iter().flatten() {
+        if let Err(err) = Location::get_from_tenant(location_uuid, tenant_uuid, &db).await {
+            return Json(HttpResponse {
+                status: err.status,
+                message: err.message,
+                data: None
+            });
+        }
+    }
+
+    for (area_uuid, location_uuid) in [(from_area_uuid, from_location_uuid), (to_area_uuid, to_location_uuid)] {
+        if let (Some(area_uuid), Some(location_uuid)) = (area_uuid, location_uuid) {
+            if let Err(err) = StorageArea::require_bin(area_uuid, tenant_uuid, location_uuid, &db).await {
+                return Json(err);
+            }
+        }
+    }
+
+    // Concurrent movements out of the same location wait here, otherwise both could pass the check and overdraw it
+    let lock = match movement.lock_source(&db).await {
+        Ok(lock) => lock,
+        Err(err) => return Json(err)
+    };
+
+    let inserted = match movement.check_available(&db).await {
+        Ok(()) => movement.insert(&db).await,
+        Err(err) => Err(err)
+    };
+
+    if let Some(lock) = lock {
+        lock.release(&db).await;
+    }
+
+    match inserted {
+        Ok(movement) => Json(HttpResponse {
+            status: 201,
+            message: "Stock movement recorded".to_string(),
+            data: Some(movement)
+        }),
+        Err(err) => Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    }
+}
@@ -0,0 +1,71 @@
+use mongodb::bson::Uuid;
+use rocket::{get, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{http_response::HttpResponse, membership::TenantPermission, stock_movement::StockMovement}};
+
+#[allow(unused)]
+#[get("/tenants/<tenant_id>/stock/movements?<product_id>&<batch_id>&<location_id>", format = "json")]
+pub async fn get_stock_movements(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, product_id: Option<&str>, batch_id: Option<&str>, location_id: Option<&str>) -> Json<HttpResponse<Vec<StockMovement>>> {
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ViewTenant, &db).await {
+        return Json(err);
+    }
+
+    let product_uuid = match product_id.map(Uuid::parse_str) {
+        Some(Ok(uuid)) => Some(uuid),
+        Some(Err(err)) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid product UUID: {:?}", err),
+            data: None
+        }),
+        None => None
+    };
+
+    let batch_uuid = match batch_id.map(Uuid::parse_str) {
+        Some(Ok(uuid)) => Some(uuid),
+        Some(Err(err)) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid batch UUID: {:?}", err),
+            data: None
+        }),
+        None => None
+    };
+
+    let location_uuid = match location_id.map(Uuid::parse_str) {
+        Some(Ok(uuid)) => Some(uuid),
+        Some(Err(err)) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid location UUID: {:?}", err),
+            data: None
+        }),
+        None => None
+    };
+
+    let hidden_locations = match auth.hidden_stock_locations(tenant_uuid, &db).await {
+        Ok(locations) => locations,
+        Err(err) => return Json(err)
+    };
+    let is_hidden = |location_id: Option<Uuid>| location_id.is_some_and(|location_id| hidden_locations.contains(&location_id));
+
+    match StockMovement::get_all_from_tenant(tenant_uuid, product_uuid, batch_uuid, location_uuid, &db).await {
+        Ok(movements) => Json(HttpResponse {
+            status: 200,
+            message: "Successfully retrieved stock movements".to_string(),
+            data: Some(movements.into_iter().filter(|movement| !is_hidden(movement.from_location_id) && !is_hidden(movement.to_location_id)).collect())
+        }),
+        Err(err) => Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    }
+}
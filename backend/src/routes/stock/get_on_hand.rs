@@ -0,0 +1,70 @@
+use mongodb::bson::Uuid;
+use rocket::{get, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{http_response::HttpResponse, membership::TenantPermission, stock_movement::{StockLevel, StockMovement}}};
+
+#[allow(unused)]
+#[get("/tenants/<tenant_id>/stock/on-hand?<product_id>&<batch_id>&<location_id>", format = "json")]
+pub async fn get_stock_on_hand(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, product_id: Option<&str>, batch_id: Option<&str>, location_id: Option<&str>) -> Json<HttpResponse<Vec<StockLevel>>> {
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ViewTenant, &db).await {
+        return Json(err);
+    }
+
+    let product_uuid = match product_id.map(Uuid::parse_str) {
+        Some(Ok(uuid)) => Some(uuid),
+        Some(Err(err)) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid product UUID: {:?}", err),
+            data: None
+        }),
+        None => None
+    };
+
+    let batch_uuid = match batch_id.map(Uuid::parse_str) {
+        Some(Ok(uuid)) => Some(uuid),
+        Some(Err(err)) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid batch UUID: {:?}", err),
+            data: None
+        }),
+        None => None
+    };
+
+    let location_uuid = match location_id.map(Uuid::parse_str) {
+        Some(Ok(uuid)) => Some(uuid),
+        Some(Err(err)) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid location UUID: {:?}", err),
+            data: None
+        }),
+        None => None
+    };
+
+    let hidden_locations = match auth.hidden_stock_locations(tenant_uuid, &db).await {
+        Ok(locations) => locations,
+        Err(err) => return Json(err)
+    };
+
+    match StockMovement::on_hand(tenant_uuid, product_uuid, batch_uuid, location_uuid, &db).await {
+        Ok(levels) => Json(HttpResponse {
+            status: 200,
+            message: "Successfully retrieved stock on hand".to_string(),
+            data: Some(levels.into_iter().filter(|level| !hidden_locations.contains(&level.location_id)).collect())
+        }),
+        Err(err) => Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    }
+}
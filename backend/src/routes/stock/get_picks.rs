@@ -0,0 +1,85 @@
+use mongodb::bson::Uuid;
+use rocket::{get, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{http_response::HttpResponse, location::Location, membership::TenantPermission, product::Product, product_batch::{PickList, ProductBatch}}};
+
+// Which batches to issue `quantity` of a product from, first expired first out
+#[allow(unused)]
+#[get("/tenants/<tenant_id>/stock/picks?<product_id>&<location_id>&<quantity>", format = "json")]
+pub async fn get_stock_picks(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, product_id: &str, location_id: &str, quantity: f64) -> Json<HttpResponse<PickList>> {
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let tenant = match auth.require_permission(tenant_uuid, TenantPermission::MoveStock, &db).await {
+        Ok(tenant) => tenant,
+        Err(err) => return Json(err)
+    };
+
+    let product_uuid = match Uuid::parse_str(product_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid product UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let location_uuid = match Uuid::parse_str(location_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid location UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if quantity <= 0.0 || !quantity.is_finite() {
+        return Json(HttpResponse {
+            status: 400,
+            message: "Quantity must be greater than zero".to_string(),
+            data: None
+        });
+    }
+
+    if let Err(err) = Product::get_from_tenant(product_uuid, tenant_uuid, &db).await {
+        return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        });
+    }
+
+    if let Err(err) = Location::get_from_tenant(location_uuid, tenant_uuid, &db).await {
+        return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        });
+    }
+
+    match auth.hidden_stock_locations(tenant_uuid, &db).await {
+        Ok(locations) if locations.contains(&location_uuid) => return Json(HttpResponse {
+            status: 403,
+            message: "Stock of this location is hidden during a blind stocktake".to_string(),
+            data: None
+        }),
+        Ok(_) => (),
+        Err(err) => return Json(err)
+    }
+
+    match ProductBatch::suggest_picks(tenant_uuid, product_uuid, location_uuid, quantity, &tenant.settings.today(), &db).await {
+        Ok(picks) => Json(HttpResponse {
+            status: 200,
+            message: "Successfully suggested batches to pick".to_string(),
+            data: Some(picks)
+        }),
+        Err(err) => Json(err)
+    }
+}
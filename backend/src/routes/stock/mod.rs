@@ -0,0 +1,4 @@
+pub mod create_movement;
+pub mod get_movements;
+pub mod get_on_hand;
+pub mod get_picks;
\ No newline at end of file
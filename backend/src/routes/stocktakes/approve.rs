@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+use mongodb::bson::Uuid;
+use rocket::{error, post, serde::{json::Json, Deserialize}};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, membership::TenantPermission, stocktake::Stocktake}};
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ApproveStocktakeData {
+    // Books stock that nobody counted down to zero, otherwise uncounted stock blocks the approval
+    #[serde(rename = "zeroUncounted")]
+    zero_uncounted: Option<bool>
+}
+
+#[allow(unused)]
+#[post("/tenants/<tenant_id>/stocktakes/<stocktake_id>/approve", format = "json", data = "<data>")]
+pub async fn approve_stocktake(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, stocktake_id: &str, data: Json<ApproveStocktakeData>) -> Json<HttpResponse<Stocktake>> {
+    let data = data.into_inner();
+
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ApproveStocktakes, &db).await {
+        return Json(err);
+    }
+
+    let stocktake_uuid = match Uuid::parse_str(stocktake_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid stocktake UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let mut stocktake = match Stocktake::get_from_tenant(stocktake_uuid, tenant_uuid, &db).await {
+        Ok(stocktake) => stocktake,
+        Err(err) => return Json(err)
+    };
+
+    let old_values = HashMap::from([("status".to_string(), format!("{:?}", stocktake.status))]);
+    let movements = match stocktake.approve(data.zero_uncounted.unwrap_or(false), auth.author(), &db).await {
+        Ok(movements) => movements,
+        Err(err) => return Json(err)
+    };
+    let new_values = HashMap::from([
+        ("status".to_string(), format!("{:?}", stocktake.status)),
+        ("adjustments".to_string(), movements.len().to_string()),
+        ("movementIds".to_string(), movements.iter().map(|movement| movement.id.to_string()).collect::<Vec<String>>().join(","))
+    ]);
+
+    match AuditLog::new(stocktake.id, AuditLogEntityType::Stocktake, AuditLogAction::Update, format!("Stocktake {} approved.", stocktake.id), auth.author(), Some(old_values), Some(new_values)).insert(&db).await {
+        Ok(_) => (),
+        Err(err) => error!("{}", err)
+    }
+
+    Json(HttpResponse {
+        status: 200,
+        message: "Stocktake approved".to_string(),
+        data: Some(stocktake)
+    })
+}
@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+use mongodb::bson::Uuid;
+use rocket::{error, post, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, membership::TenantPermission, stocktake::{Stocktake, StocktakeStatus}}};
+
+#[allow(unused)]
+#[post("/tenants/<tenant_id>/stocktakes/<stocktake_id>/cancel", format = "json")]
+pub async fn cancel_stocktake(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, stocktake_id: &str) -> Json<HttpResponse<Stocktake>> {
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ApproveStocktakes, &db).await {
+        return Json(err);
+    }
+
+    let stocktake_uuid = match Uuid::parse_str(stocktake_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid stocktake UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let mut stocktake = match Stocktake::get_from_tenant(stocktake_uuid, tenant_uuid, &db).await {
+        Ok(stocktake) => stocktake,
+        Err(err) => return Json(err)
+    };
+
+    if stocktake.status != StocktakeStatus::Open {
+        return Json(HttpResponse {
+            status: 400,
+            message: "Only open stocktakes can be cancelled".to_string(),
+            data: None
+        });
+    }
+
+    let old_values = HashMap::from([("status".to_string(), format!("{:?}", stocktake.status))]);
+    stocktake.status = StocktakeStatus::Cancelled;
+    let new_values = HashMap::from([("status".to_string(), format!("{:?}", stocktake.status))]);
+
+    match stocktake.set_status_if(StocktakeStatus::Open, &db).await {
+        Ok(true) => {
+            match AuditLog::new(stocktake.id, AuditLogEntityType::Stocktake, AuditLogAction::Update, "Stocktake cancelled.".to_string(), auth.author(), Some(old_values), Some(new_values)).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            Json(HttpResponse {
+                status: 200,
+                message: "Stocktake cancelled".to_string(),
+                data: Some(stocktake)
+            })
+        },
+        Ok(false) => Json(HttpResponse {
+            status: 400,
+            message: "Only open stocktakes can be cancelled".to_string(),
+            data: None
+        }),
+        Err(err) => Json(err)
+    }
+}
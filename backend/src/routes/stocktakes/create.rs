@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+use mongodb::bson::Uuid;
+use rocket::{error, post, serde::{json::Json, Deserialize}};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, location::Location, membership::TenantPermission, product_group::ProductGroup, stocktake::{Stocktake, StocktakeStatus}}};
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CreateStocktakeData {
+    #[serde(rename = "locationId")]
+    location_id: String,
+    // Counts only this group and its subgroups instead of the whole location
+    #[serde(rename = "productGroupId")]
+    product_group_id: Option<String>,
+    // Defaults to a blind count
+    blind: Option<bool>
+}
+
+#[allow(unused)]
+#[post("/tenants/<tenant_id>/stocktakes", format = "json", data = "<data>")]
+pub async fn create_stocktake(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, data: Json<CreateStocktakeData>) -> Json<HttpResponse<Stocktake>> {
+    let data = data.into_inner();
+
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ApproveStocktakes, &db).await {
+        return Json(err);
+    }
+
+    let location_uuid = match Uuid::parse_str(&data.location_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid location UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = Location::get_from_tenant(location_uuid, tenant_uuid, &db).await {
+        return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        });
+    }
+
+    let product_group_uuid = match data.product_group_id.as_deref().map(Uuid::parse_str) {
+        Some(Ok(uuid)) => Some(uuid),
+        Some(Err(err)) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid product group UUID: {:?}", err),
+            data: None
+        }),
+        None => None
+    };
+
+    if let Some(product_group_uuid) = product_group_uuid {
+        if let Err(err) = ProductGroup::get_from_tenant(product_group_uuid, tenant_uuid, &db).await {
+            return Json(HttpResponse {
+                status: err.status,
+                message: err.message,
+                data: None
+            });
+        }
+    }
+
+    match Stocktake::get_all_from_tenant(tenant_uuid, Some(StocktakeStatus::Open), Some(location_uuid), &db).await {
+        Ok(open) if open.is_empty() => (),
+        Ok(_) => return Json(HttpResponse {
+            status: 400,
+            message: "There is already an open stocktake for this location".to_string(),
+            data: None
+        }),
+        Err(err) => return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    }
+
+    let stocktake = Stocktake::new(tenant_uuid, location_uuid, product_group_uuid, data.blind.unwrap_or(true));
+
+    match stocktake.insert(&db).await {
+        Ok(stocktake) => {
+            let mut new_values = HashMap::from([
+                ("locationId".to_string(), stocktake.location_id.to_string()),
+                ("blind".to_string(), stocktake.blind.to_string())
+            ]);
+            if let Some(product_group_id) = stocktake.product_group_id {
+                new_values.insert("productGroupId".to_string(), product_group_id.to_string());
+            }
+            match AuditLog::new(stocktake.id, AuditLogEntityType::Stocktake, AuditLogAction::Create, "Stocktake started.".to_string(), auth.author(), None, Some(new_values)).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            Json(HttpResponse {
+                status: 201,
+                message: "Stocktake started".to_string(),
+                data: Some(stocktake)
+            })
+        },
+        Err(err) => Json(err)
+    }
+}
@@ -0,0 +1,47 @@
+use mongodb::bson::Uuid;
+use rocket::{get, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{http_response::HttpResponse, membership::TenantPermission, stocktake::{Stocktake, StocktakeStatus}}};
+
+#[allow(unused)]
+#[get("/tenants/<tenant_id>/stocktakes?<status>&<location_id>", format = "json")]
+pub async fn get_all_stocktakes_from_tenant(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, status: Option<&str>, location_id: Option<&str>) -> Json<HttpResponse<Vec<Stocktake>>> {
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ViewTenant, &db).await {
+        return Json(err);
+    }
+
+    let status = match status.map(StocktakeStatus::from_string) {
+        Some(Ok(status)) => Some(status),
+        Some(Err(err)) => return Json(err),
+        None => None
+    };
+
+    let location_uuid = match location_id.map(Uuid::parse_str) {
+        Some(Ok(uuid)) => Some(uuid),
+        Some(Err(err)) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid location UUID: {:?}", err),
+            data: None
+        }),
+        None => None
+    };
+
+    match Stocktake::get_all_from_tenant(tenant_uuid, status, location_uuid, &db).await {
+        Ok(stocktakes) => Json(HttpResponse {
+            status: 200,
+            message: "Successfully retrieved stocktakes".to_string(),
+            data: Some(stocktakes)
+        }),
+        Err(err) => Json(err)
+    }
+}
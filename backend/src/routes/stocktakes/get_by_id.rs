@@ -0,0 +1,40 @@
+use mongodb::bson::Uuid;
+use rocket::{get, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{http_response::HttpResponse, membership::TenantPermission, stocktake::Stocktake}};
+
+#[allow(unused)]
+#[get("/tenants/<tenant_id>/stocktakes/<stocktake_id>", format = "json")]
+pub async fn get_stocktake_by_id(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, stocktake_id: &str) -> Json<HttpResponse<Stocktake>> {
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ViewTenant, &db).await {
+        return Json(err);
+    }
+
+    let stocktake_uuid = match Uuid::parse_str(stocktake_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid stocktake UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    match Stocktake::get_from_tenant(stocktake_uuid, tenant_uuid, &db).await {
+        Ok(stocktake) => Json(HttpResponse {
+            status: 200,
+            message: "Successfully retrieved stocktake".to_string(),
+            data: Some(stocktake)
+        }),
+        Err(err) => Json(err)
+    }
+}
@@ -0,0 +1,68 @@
+use mongodb::bson::Uuid;
+use rocket::{get, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{http_response::HttpResponse, membership::TenantPermission, stocktake::{Stocktake, StocktakeStatus, VarianceLine}}};
+
+#[allow(unused)]
+#[get("/tenants/<tenant_id>/stocktakes/<stocktake_id>/variance", format = "json")]
+pub async fn get_stocktake_variance(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, stocktake_id: &str) -> Json<HttpResponse<Vec<VarianceLine>>> {
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ViewTenant, &db).await {
+        return Json(err);
+    }
+
+    let stocktake_uuid = match Uuid::parse_str(stocktake_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid stocktake UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let stocktake = match Stocktake::get_from_tenant(stocktake_uuid, tenant_uuid, &db).await {
+        Ok(stocktake) => stocktake,
+        Err(err) => return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    };
+
+    if let Some(variance) = stocktake.approved_variance {
+        return Json(HttpResponse {
+            status: 200,
+            message: "Successfully retrieved variance report".to_string(),
+            data: Some(variance)
+        });
+    }
+
+    // Counters of a blind count only learn the system quantities once it is approved
+    if stocktake.blind && stocktake.status == StocktakeStatus::Open {
+        if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ApproveStocktakes, &db).await {
+            return Json(err);
+        }
+    }
+
+    match stocktake.variance(&db).await {
+        Ok(variance) => Json(HttpResponse {
+            status: 200,
+            message: "Successfully retrieved variance report".to_string(),
+            data: Some(variance)
+        }),
+        Err(err) => Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    }
+}
@@ -0,0 +1,7 @@
+pub mod create;
+pub mod get_all_from_tenant;
+pub mod get_by_id;
+pub mod record_counts;
+pub mod get_variance;
+pub mod approve;
+pub mod cancel;
\ No newline at end of file
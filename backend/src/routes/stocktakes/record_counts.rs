@@ -0,0 +1,147 @@
+use mongodb::bson::Uuid;
+use rocket::{post, serde::{json::Json, Deserialize}};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{http_response::HttpResponse, membership::TenantPermission, product::Product, product_batch::ProductBatch, stocktake::{Stocktake, StocktakeStatus}}};
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct StocktakeCountData {
+    #[serde(rename = "productId")]
+    product_id: String,
+    #[serde(rename = "batchId")]
+    batch_id: Option<String>,
+    quantity: f64
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct RecordStocktakeCountsData {
+    lines: Vec<StocktakeCountData>
+}
+
+#[allow(unused)]
+#[post("/tenants/<tenant_id>/stocktakes/<stocktake_id>/counts", format = "json", data = "<data>")]
+pub async fn record_stocktake_counts(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, stocktake_id: &str, data: Json<RecordStocktakeCountsData>) -> Json<HttpResponse<Stocktake>> {
+    let data = data.into_inner();
+
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::MoveStock, &db).await {
+        return Json(err);
+    }
+
+    let stocktake_uuid = match Uuid::parse_str(stocktake_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid stocktake UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let stocktake = match Stocktake::get_from_tenant(stocktake_uuid, tenant_uuid, &db).await {
+        Ok(stocktake) => stocktake,
+        Err(err) => return Json(err)
+    };
+
+    if stocktake.status != StocktakeStatus::Open {
+        return Json(HttpResponse {
+            status: 400,
+            message: "Stocktake is not open".to_string(),
+            data: None
+        });
+    }
+
+    let scope = match stocktake.scope(&db).await {
+        Ok(scope) => scope,
+        Err(err) => return Json(err)
+    };
+
+    let mut counts = Vec::new();
+    for line in data.lines {
+        let product_uuid = match Uuid::parse_str(&line.product_id) {
+            Ok(uuid) => uuid,
+            Err(err) => return Json(HttpResponse {
+                status: 400,
+                message: format!("Invalid product UUID: {:?}", err),
+                data: None
+            })
+        };
+
+        let batch_uuid = match line.batch_id.as_deref().map(Uuid::parse_str) {
+            Some(Ok(uuid)) => Some(uuid),
+            Some(Err(err)) => return Json(HttpResponse {
+                status: 400,
+                message: format!("Invalid batch UUID: {:?}", err),
+                data: None
+            }),
+            None => None
+        };
+
+        if line.quantity < 0.0 || !line.quantity.is_finite() {
+            return Json(HttpResponse {
+                status: 400,
+                message: "Counted quantities must not be negative".to_string(),
+                data: None
+            });
+        }
+
+        if let Err(err) = Product::get_from_tenant(product_uuid, tenant_uuid, &db).await {
+            return Json(HttpResponse {
+                status: err.status,
+                message: err.message,
+                data: None
+            });
+        }
+
+        if scope.as_ref().is_some_and(|scope| !scope.contains(&product_uuid)) {
+            return Json(HttpResponse {
+                status: 400,
+                message: format!("Product {} is not part of this stocktake", product_uuid),
+                data: None
+            });
+        }
+
+        if let Some(batch_uuid) = batch_uuid {
+            match ProductBatch::get_from_tenant(batch_uuid, tenant_uuid, &db).await {
+                Ok(batch) if batch.product_id != product_uuid => return Json(HttpResponse {
+                    status: 400,
+                    message: "Batch belongs to another product".to_string(),
+                    data: None
+                }),
+                Ok(_) => (),
+                Err(err) => return Json(HttpResponse {
+                    status: err.status,
+                    message: err.message,
+                    data: None
+                })
+            }
+        }
+
+        counts.push((product_uuid, batch_uuid, line.quantity));
+    }
+
+    // Lines are only recorded once all of them are valid
+    for (product_uuid, batch_uuid, quantity) in counts {
+        if let Err(err) = stocktake.record_count(product_uuid, batch_uuid, quantity, auth.author(), &db).await {
+            return Json(err);
+        }
+    }
+
+    match Stocktake::get_from_tenant(stocktake.id, tenant_uuid, &db).await {
+        Ok(stocktake) => Json(HttpResponse {
+            status: 200,
+            message: "Counts recorded".to_string(),
+            data: Some(stocktake)
+        }),
+        Err(err) => Json(err)
+    }
+}
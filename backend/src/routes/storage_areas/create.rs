@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use mongodb::bson::Uuid;
+use rocket::{error, post, serde::{json::Json, Deserialize}};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, location::Location, membership::TenantPermission, storage_area::{StorageArea, StorageAreaKind}}};
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CreateStorageAreaData {
+    kind: String,
+    code: String,
+    name: Option<String>,
+    #[serde(rename = "parentId")]
+    parent_id: Option<String>
+}
+
+#[allow(unused)]
+#[post("/tenants/<tenant_id>/locations/<location_id>/areas", format = "json", data = "<data>")]
+pub async fn create_storage_area(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, location_id: &str, data: Json<CreateStorageAreaData>) -> Json<HttpResponse<StorageArea>> {
+    let data = data.into_inner();
+
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ManageLocations, &db).await {
+        return Json(err);
+    }
+
+    let location_uuid = match Uuid::parse_str(location_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid location UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = Location::get_from_tenant(location_uuid, tenant_uuid, &db).await {
+        return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        });
+    }
+
+    let kind = match StorageAreaKind::from_string(&data.kind) {
+        Ok(kind) => kind,
+        Err(err) => return Json(err)
+    };
+
+    let code = match StorageArea::normalize_code(&data.code) {
+        Ok(code) => code,
+        Err(err) => return Json(err)
+    };
+
+    let parent_uuid = match data.parent_id.as_deref().map(Uuid::parse_str) {
+        Some(Ok(uuid)) => Some(uuid),
+        Some(Err(err)) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid parent UUID: {:?}", err),
+            data: None
+        }),
+        None => None
+    };
+
+    match StorageArea::get_by_code(&code, tenant_uuid, location_uuid, &db).await {
+        Ok(_) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Storage area with code {} already exists in this location", code),
+            data: None
+        }),
+        Err(err) if err.status != 404 => return Json(err),
+        Err(_) => ()
+    }
+
+    let area = StorageArea::new(tenant_uuid, location_uuid, parent_uuid, kind, code, data.name);
+
+    if let Some(parent_uuid) = parent_uuid {
+        if let Err(err) = area.validate_parent(parent_uuid, &db).await {
+            return Json(err);
+        }
+    }
+
+    match area.insert(&db).await {
+        Ok(area) => {
+            let new_values = HashMap::from([
+                ("locationId".to_string(), area.location_id.to_string()),
+                ("parentId".to_string(), area.parent_id.map(|id| id.to_string()).unwrap_or_default()),
+                ("kind".to_string(), format!("{:?}", area.kind)),
+                ("code".to_string(), area.code.clone())
+            ]);
+            match AuditLog::new(area.id, AuditLogEntityType::StorageArea, AuditLogAction::Create, "Storage area created.".to_string(), auth.author(), None, Some(new_values)).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            Json(HttpResponse {
+                status: 201,
+                message: "Storage area created".to_string(),
+                data: Some(area)
+            })
+        },
+        Err(err) => Json(err)
+    }
+}
@@ -0,0 +1,89 @@
+use mongodb::bson::Uuid;
+use rocket::{delete, error, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, membership::TenantPermission, storage_area::StorageArea}};
+
+#[allow(unused)]
+#[delete("/tenants/<tenant_id>/locations/<location_id>/areas/<area_id>", format = "json")]
+pub async fn delete_storage_area(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, location_id: &str, area_id: &str) -> Json<HttpResponse<()>> {
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ManageLocations, &db).await {
+        return Json(err);
+    }
+
+    let location_uuid = match Uuid::parse_str(location_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid location UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let area_uuid = match Uuid::parse_str(area_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid storage area UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let area = match StorageArea::get_from_location(area_uuid, tenant_uuid, location_uuid, &db).await {
+        Ok(area) => area,
+        Err(err) => return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    };
+
+    match area.has_children(&db).await {
+        Ok(false) => (),
+        Ok(true) => return Json(HttpResponse {
+            status: 400,
+            message: "Storage area still contains other storage areas".to_string(),
+            data: None
+        }),
+        Err(err) => return Json(err)
+    }
+
+    match StorageArea::get_stock(tenant_uuid, location_uuid, None, Some(&[area.id]), &db).await {
+        Ok(stock) if stock.is_empty() => (),
+        Ok(_) => return Json(HttpResponse {
+            status: 400,
+            message: "Storage area still holds stock".to_string(),
+            data: None
+        }),
+        Err(err) => return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    }
+
+    match area.delete(&db).await {
+        Ok(area) => {
+            match AuditLog::new(area.id, AuditLogEntityType::StorageArea, AuditLogAction::Delete, "Storage area deleted.".to_string(), auth.author(), None, None).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            Json(HttpResponse {
+                status: 200,
+                message: "Storage area deleted".to_string(),
+                data: None
+            })
+        },
+        Err(err) => Json(err)
+    }
+}
@@ -0,0 +1,41 @@
+use mongodb::bson::Uuid;
+use rocket::{get, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{http_response::HttpResponse, membership::TenantPermission, storage_area::StorageArea}};
+
+// Flat list of the location's areas, the tree follows from `parentId`
+#[allow(unused)]
+#[get("/tenants/<tenant_id>/locations/<location_id>/areas", format = "json")]
+pub async fn get_all_storage_areas_from_location(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, location_id: &str) -> Json<HttpResponse<Vec<StorageArea>>> {
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ViewTenant, &db).await {
+        return Json(err);
+    }
+
+    let location_uuid = match Uuid::parse_str(location_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid location UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    match StorageArea::get_all_from_location(tenant_uuid, location_uuid, &db).await {
+        Ok(areas) => Json(HttpResponse {
+            status: 200,
+            message: "Successfully retrieved storage areas".to_string(),
+            data: Some(areas)
+        }),
+        Err(err) => Json(err)
+    }
+}
@@ -0,0 +1,41 @@
+use mongodb::bson::Uuid;
+use rocket::{get, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{http_response::HttpResponse, membership::TenantPermission, storage_area::StorageArea}};
+
+// Resolves a scanned area label to the storage area
+#[allow(unused)]
+#[get("/tenants/<tenant_id>/locations/<location_id>/areas/by-code/<code>", format = "json")]
+pub async fn get_storage_area_by_code(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, location_id: &str, code: &str) -> Json<HttpResponse<StorageArea>> {
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ViewTenant, &db).await {
+        return Json(err);
+    }
+
+    let location_uuid = match Uuid::parse_str(location_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid location UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    match StorageArea::get_by_code(code, tenant_uuid, location_uuid, &db).await {
+        Ok(area) => Json(HttpResponse {
+            status: 200,
+            message: "Successfully retrieved storage area".to_string(),
+            data: Some(area)
+        }),
+        Err(err) => Json(err)
+    }
+}
@@ -0,0 +1,78 @@
+use mongodb::bson::Uuid;
+use rocket::{get, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{http_response::HttpResponse, membership::TenantPermission, storage_area::{AreaStock, StorageArea}}};
+
+// Everything stored in the area and the areas nested inside it, e.g. all bins on a shelf
+#[allow(unused)]
+#[get("/tenants/<tenant_id>/locations/<location_id>/areas/<area_id>/stock", format = "json")]
+pub async fn get_storage_area_stock(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, location_id: &str, area_id: &str) -> Json<HttpResponse<Vec<AreaStock>>> {
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ViewTenant, &db).await {
+        return Json(err);
+    }
+
+    let location_uuid = match Uuid::parse_str(location_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid location UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let area_uuid = match Uuid::parse_str(area_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid storage area UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    match auth.hidden_stock_locations(tenant_uuid, &db).await {
+        Ok(locations) if locations.contains(&location_uuid) => return Json(HttpResponse {
+            status: 403,
+            message: "Stock of this location is hidden during a blind stocktake".to_string(),
+            data: None
+        }),
+        Ok(_) => (),
+        Err(err) => return Json(err)
+    }
+
+    let areas = match StorageArea::get_all_from_location(tenant_uuid, location_uuid, &db).await {
+        Ok(areas) => areas,
+        Err(err) => return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    };
+
+    let area_ids = match areas.iter().find(|area| area.id == area_uuid) {
+        Some(area) => area.subtree(&areas),
+        None => return Json(HttpResponse {
+            status: 404,
+            message: "Storage area not found".to_string(),
+            data: None
+        })
+    };
+
+    match StorageArea::get_stock(tenant_uuid, location_uuid, None, Some(&area_ids), &db).await {
+        Ok(stock) => Json(HttpResponse {
+            status: 200,
+            message: "Successfully retrieved storage area stock".to_string(),
+            data: Some(stock)
+        }),
+        Err(err) => Json(err)
+    }
+}
@@ -0,0 +1,60 @@
+use mongodb::bson::Uuid;
+use rocket::{get, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{http_response::HttpResponse, membership::TenantPermission, storage_area::{AreaStock, StorageArea}}};
+
+// Bins in the location holding the product, plus stock of it that hasn't been put away yet
+#[allow(unused)]
+#[get("/tenants/<tenant_id>/locations/<location_id>/products/<product_id>/areas", format = "json")]
+pub async fn locate_product_in_location(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, location_id: &str, product_id: &str) -> Json<HttpResponse<Vec<AreaStock>>> {
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ViewTenant, &db).await {
+        return Json(err);
+    }
+
+    let location_uuid = match Uuid::parse_str(location_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid location UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let product_uuid = match Uuid::parse_str(product_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid product UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    match auth.hidden_stock_locations(tenant_uuid, &db).await {
+        Ok(locations) if locations.contains(&location_uuid) => return Json(HttpResponse {
+            status: 403,
+            message: "Stock of this location is hidden during a blind stocktake".to_string(),
+            data: None
+        }),
+        Ok(_) => (),
+        Err(err) => return Json(err)
+    }
+
+    match StorageArea::get_stock(tenant_uuid, location_uuid, Some(product_uuid), None, &db).await {
+        Ok(stock) => Json(HttpResponse {
+            status: 200,
+            message: "Successfully located product".to_string(),
+            data: Some(stock)
+        }),
+        Err(err) => Json(err)
+    }
+}
@@ -0,0 +1,7 @@
+pub mod create;
+pub mod get_all_from_location;
+pub mod get_by_code;
+pub mod get_stock;
+pub mod locate_product;
+pub mod update;
+pub mod delete;
\ No newline at end of file
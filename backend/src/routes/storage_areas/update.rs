@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+use mongodb::bson::Uuid;
+use rocket::{error, patch, serde::{json::Json, Deserialize}};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, membership::TenantPermission, storage_area::StorageArea}};
+
+// The kind of an area is fixed, its children depend on it
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct UpdateStorageAreaData {
+    code: Option<String>,
+    name: Option<String>,
+    // An empty string moves the area to the top level
+    #[serde(rename = "parentId")]
+    parent_id: Option<String>
+}
+
+#[allow(unused)]
+#[patch("/tenants/<tenant_id>/locations/<location_id>/areas/<area_id>", format = "json", data = "<data>")]
+pub async fn update_storage_area(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, location_id: &str, area_id: &str, data: Json<UpdateStorageAreaData>) -> Json<HttpResponse<StorageArea>> {
+    let data = data.into_inner();
+
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ManageLocations, &db).await {
+        return Json(err);
+    }
+
+    let location_uuid = match Uuid::parse_str(location_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid location UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let area_uuid = match Uuid::parse_str(area_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid storage area UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let old_area = match StorageArea::get_from_location(area_uuid, tenant_uuid, location_uuid, &db).await {
+        Ok(area) => area,
+        Err(err) => return Json(err)
+    };
+
+    let mut new_area = old_area.clone();
+
+    let mut old_values: HashMap<String, String> = HashMap::new();
+    let mut new_values: HashMap<String, String> = HashMap::new();
+
+    if let Some(code) = data.code {
+        new_area.code = match StorageArea::normalize_code(&code) {
+            Ok(code) => code,
+            Err(err) => return Json(err)
+        };
+
+        if new_area.code != old_area.code {
+            match StorageArea::get_by_code(&new_area.code, tenant_uuid, location_uuid, &db).await {
+                Ok(_) => return Json(HttpResponse {
+                    status: 400,
+                    message: format!("Storage area with code {} already exists in this location", new_area.code),
+                    data: None
+                }),
+                Err(err) if err.status != 404 => return Json(err),
+                Err(_) => ()
+            }
+        }
+
+        old_values.insert("code".to_owned(), old_area.code.clone());
+        new_values.insert("code".to_owned(), new_area.code.clone());
+    }
+
+    if let Some(name) = data.name {
+        new_area.name = Some(name);
+        old_values.insert("name".to_owned(), old_area.name.clone().unwrap_or_default());
+        new_values.insert("name".to_owned(), new_area.name.clone().unwrap_or_default());
+    }
+
+    if let Some(parent_id) = data.parent_id {
+        new_area.parent_id = match parent_id.as_str() {
+            "" => None,
+            parent_id => match Uuid::parse_str(parent_id) {
+                Ok(uuid) => Some(uuid),
+                Err(err) => return Json(HttpResponse {
+                    status: 400,
+                    message: format!("Invalid parent UUID: {:?}", err),
+                    data: None
+                })
+            }
+        };
+
+        if let Some(parent_id) = new_area.parent_id {
+            if let Err(err) = new_area.validate_parent(parent_id, &db).await {
+                return Json(err);
+            }
+        }
+
+        old_values.insert("parentId".to_owned(), old_area.parent_id.map(|id| id.to_string()).unwrap_or_default());
+        new_values.insert("parentId".to_owned(), new_area.parent_id.map(|id| id.to_string()).unwrap_or_default());
+    }
+
+    if new_values.is_empty() {
+        return Json(HttpResponse {
+            status: 200,
+            message: "No updates applied.".to_string(),
+            data: Some(new_area)
+        });
+    }
+
+    match new_area.update(&db).await {
+        Ok(area) => {
+            match AuditLog::new(area.id, AuditLogEntityType::StorageArea, AuditLogAction::Update, "Storage area updated.".to_string(), auth.author(), Some(old_values), Some(new_values)).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            Json(HttpResponse {
+                status: 200,
+                message: "Storage area updated".to_string(),
+                data: Some(area)
+            })
+        },
+        Err(err) => Json(err)
+    }
+}
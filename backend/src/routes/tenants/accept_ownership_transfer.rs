@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+use mongodb::bson::Uuid;
+use rocket::{error, post, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::AuthenticatedUser, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, membership::{Membership, TenantPermission, TenantRole}, tenant::Tenant, user::User}};
+
+// The proposed member takes over the tenant, the previous owner stays on as manager
+#[allow(unused)]
+#[post("/tenants/<id>/ownership-transfer/accept", format = "json")]
+pub async fn accept_ownership_transfer(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser, id: &str) -> Json<HttpResponse<Tenant>> {
+    let uuid = match Uuid::parse_str(id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let mut tenant = match auth.require_permission(uuid, TenantPermission::ViewTenant, &db).await {
+        Ok(tenant) => tenant,
+        Err(err) => return Json(err)
+    };
+
+    if tenant.pending_owner_id != Some(auth.user.id) {
+        return Json(HttpResponse {
+            status: 403,
+            message: "No ownership transfer is pending for you".to_string(),
+            data: None
+        });
+    }
+
+    let new_owner = match User::get_full_by_id(auth.user.id, &db).await {
+        Ok(user) => user,
+        Err(err) => return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    };
+
+    // The proposal stays pending only while the recipient is a member
+    if new_owner.membership(tenant.id).is_none() {
+        return Json(HttpResponse {
+            status: 400,
+            message: "You are no longer a member of this tenant".to_string(),
+            data: None
+        });
+    }
+
+    let previous_owner = match User::get_full_by_id(tenant.owner_id, &db).await {
+        Ok(user) => user,
+        Err(err) => return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    };
+
+    let old_values = HashMap::from([
+        ("ownerId".to_string(), tenant.owner_id.to_string()),
+        ("pendingOwnerId".to_string(), new_owner.id.to_string())
+    ]);
+
+    // The tenant decides who is owner, so it is written first
+    match tenant.transfer_ownership(new_owner.id, &db).await {
+        Ok(true) => (),
+        Ok(false) => return Json(HttpResponse {
+            status: 409,
+            message: "Ownership transfer was changed in the meantime".to_string(),
+            data: None
+        }),
+        Err(err) => return Json(err)
+    }
+
+    let updated = match new_owner.set_membership_role(tenant.id, None, TenantRole::Owner, &db).await {
+        Ok(_) => match previous_owner.set_membership_role(tenant.id, None, TenantRole::Manager, &db).await {
+            Ok(Some(_)) => Ok(()),
+            Ok(None) => previous_owner.add_membership(&Membership::new(tenant.id, TenantRole::Manager), &db).await.map(|_| ()),
+            Err(err) => Err(err)
+        },
+        Err(err) => Err(err)
+    };
+    if let Err(err) = updated {
+        return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        });
+    }
+
+    let new_values = HashMap::from([
+        ("ownerId".to_string(), tenant.owner_id.to_string()),
+        ("previousOwnerRole".to_string(), format!("{:?}", TenantRole::Manager))
+    ]);
+    match AuditLog::new(tenant.id, AuditLogEntityType::Tenant, AuditLogAction::Update, "Ownership transfer accepted.".to_string(), auth.author(), Some(old_values), Some(new_values)).insert(&db).await {
+        Ok(_) => (),
+        Err(err) => error!("{}", err)
+    }
+
+    Json(HttpResponse {
+        status: 200,
+        message: "Ownership transferred".to_string(),
+        data: Some(tenant)
+    })
+}
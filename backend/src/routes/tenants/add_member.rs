@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+use mongodb::bson::Uuid;
+use rocket::{error, post, serde::{json::Json, Deserialize}};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::AuthenticatedUser, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, membership::{Membership, TenantPermission, TenantRole}, user::{User, UserMinimal}, tenant_plan::QuotaResource}};
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct AddMemberData {
+    #[serde(rename = "userId")]
+    user_id: String,
+    role: String
+}
+
+#[allow(unused)]
+#[post("/tenants/<id>/members", format = "json", data = "<data>")]
+pub async fn add_member(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser, id: &str, data: Json<AddMemberData>) -> Json<HttpResponse<UserMinimal>> {
+    let data = data.into_inner();
+
+    let uuid = match Uuid::parse_str(id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let user_uuid = match Uuid::parse_str(&data.user_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid user UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let role = match TenantRole::from_string(&data.role) {
+        Ok(role) => role,
+        Err(err) => return Json(err)
+    };
+
+    if role == TenantRole::Owner {
+        return Json(HttpResponse {
+            status: 400,
+            message: "Ownership can only be transferred, not granted".to_string(),
+            data: None
+        });
+    }
+
+    let tenant = match auth.require_permission(uuid, TenantPermission::ManageMembers, &db).await {
+        Ok(tenant) => tenant,
+        Err(err) => return Json(err)
+    };
+
+    if let Err(err) = auth.require_can_manage_role(&tenant, role) {
+        return Json(err);
+    }
+
+    let user = match User::get_full_by_id(user_uuid, &db).await {
+        Ok(user) => user,
+        Err(err) => return Json(err)
+    };
+
+    if user.membership(tenant.id).is_some() {
+        return Json(HttpResponse {
+            status: 400,
+            message: "User is already a member of this tenant".to_string(),
+            data: None
+        });
+    }
+
+    if let Err(err) = tenant.require_quota(QuotaResource::Members, &db).await {
+        return Json(err);
+    }
+
+    match user.add_membership(&Membership::new(tenant.id, role), &db).await {
+        Ok(Some(user)) => {
+            let new_values = HashMap::from([
+                ("memberId".to_string(), user.id.to_string()),
+                ("role".to_string(), format!("{:?}", role))
+            ]);
+            match AuditLog::new(tenant.id, AuditLogEntityType::Tenant, AuditLogAction::Update, "Member added.".to_string(), auth.author(), None, Some(new_values)).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            Json(HttpResponse {
+                status: 201,
+                message: "Member added".to_string(),
+                data: Some(user)
+            })
+        },
+        Ok(None) => Json(HttpResponse {
+            status: 400,
+            message: "User is already a member of this tenant".to_string(),
+            data: None
+        }),
+        Err(err) => Json(err)
+    }
+}
@@ -0,0 +1,65 @@
+use std::collections::HashMap;
+use mongodb::bson::Uuid;
+use rocket::{delete, error, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::AuthenticatedUser, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, membership::TenantPermission, tenant::Tenant}};
+
+// Withdrawn by the owner or declined by the recipient
+#[allow(unused)]
+#[delete("/tenants/<id>/ownership-transfer", format = "json")]
+pub async fn cancel_ownership_transfer(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser, id: &str) -> Json<HttpResponse<Tenant>> {
+    let uuid = match Uuid::parse_str(id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let mut tenant = match auth.require_permission(uuid, TenantPermission::ViewTenant, &db).await {
+        Ok(tenant) => tenant,
+        Err(err) => return Json(err)
+    };
+
+    let Some(pending_owner_id) = tenant.pending_owner_id else {
+        return Json(HttpResponse {
+            status: 404,
+            message: "No ownership transfer is pending".to_string(),
+            data: None
+        });
+    };
+
+    if auth.user.id != tenant.owner_id && auth.user.id != pending_owner_id {
+        return Json(HttpResponse {
+            status: 403,
+            message: "Only the owner or the proposed owner can cancel the ownership transfer".to_string(),
+            data: None
+        });
+    }
+
+    match tenant.set_pending_owner(None, &db).await {
+        Ok(false) => Json(HttpResponse {
+            status: 409,
+            message: "Ownership transfer was changed in the meantime".to_string(),
+            data: None
+        }),
+        Ok(true) => {
+            let old_values = HashMap::from([
+                ("pendingOwnerId".to_string(), pending_owner_id.to_string())
+            ]);
+            match AuditLog::new(tenant.id, AuditLogEntityType::Tenant, AuditLogAction::Update, "Ownership transfer cancelled.".to_string(), auth.author(), Some(old_values), None).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            Json(HttpResponse {
+                status: 200,
+                message: "Ownership transfer cancelled".to_string(),
+                data: Some(tenant)
+            })
+        },
+        Err(err) => Json(err)
+    }
+}
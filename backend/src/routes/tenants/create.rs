@@ -1,8 +1,7 @@
-use mongodb::bson::Uuid;
 use rocket::{error, post, serde::{json::Json, Deserialize}};
 use rocket_db_pools::Connection;
 
-use crate::{db::ShelfWatcherDatabase, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, tenant::Tenant}};
+use crate::{db::ShelfWatcherDatabase, middleware::auth::AuthenticatedUser, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, membership::{Membership, TenantRole}, tenant::Tenant}};
 
 #[derive(Deserialize)]
 #[serde(crate = "rocket::serde")]
@@ -12,7 +11,7 @@ pub struct CreateTenantData {
 
 #[allow(unused)]
 #[post("/tenants", format = "json", data = "<data>")] 
-pub async fn create_tenant(db: Connection<ShelfWatcherDatabase>, data: Json<CreateTenantData>) -> Json<HttpResponse<Tenant>> { 
+pub async fn create_tenant(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser, data: Json<CreateTenantData>) -> Json<HttpResponse<Tenant>> { 
     let data = data.into_inner();
 
     if Tenant::get_by_name(data.name.clone(), &db).await.is_ok() {
@@ -23,12 +22,16 @@ pub async fn create_tenant(db: Connection<ShelfWatcherDatabase>, data: Json<Crea
         });
     }
 
-    let tenant = Tenant::new(data.name);
+    let tenant = Tenant::new(data.name, auth.user.id);
     
     match tenant.insert(&db).await {
         Ok(tenant) => {
-            // TODO: Implement author_id
-            match AuditLog::new(tenant.id, AuditLogEntityType::Tenant, AuditLogAction::Create, "Tenant created.".to_string(), Uuid::new(), None, None).insert(&db).await {
+            match auth.user.add_membership(&Membership::new(tenant.id, TenantRole::Owner), &db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err.message)
+            }
+
+            match AuditLog::new(tenant.id, AuditLogEntityType::Tenant, AuditLogAction::Create, "Tenant created.".to_string(), auth.author(), None, None).insert(&db).await {
                 Ok(_) => (),
                 Err(err) => error!("{}", err)
             }
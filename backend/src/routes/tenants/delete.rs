@@ -1,12 +1,14 @@
+use std::collections::HashMap;
 use mongodb::bson::Uuid;
-use rocket::{delete, error, serde::json::Json};
+use rocket::{delete, error, serde::json::Json, State};
 use rocket_db_pools::Connection;
 
-use crate::{db::ShelfWatcherDatabase, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, tenant::Tenant}};
+use crate::{db::ShelfWatcherDatabase, jobs::JobsConfig, middleware::auth::AuthenticatedUser, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, membership::TenantPermission, tenant::Tenant}};
 
+// Soft delete, the tenant's data is purged by a background job once the grace period is over
 #[allow(unused)]
 #[delete("/tenants/<id>", format = "json")] 
-pub async fn delete_tenant(db: Connection<ShelfWatcherDatabase>, id: &str) -> Json<HttpResponse<()>> {
+pub async fn delete_tenant(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser, config: &State<JobsConfig>, id: &str) -> Json<HttpResponse<Tenant>> {
     let uuid = match Uuid::parse_str(id) {
         Ok(uuid) => uuid,
         Err(err) => return Json(HttpResponse {
@@ -16,30 +18,30 @@ pub async fn delete_tenant(db: Connection<ShelfWatcherDatabase>, id: &str) -> Js
         })
     };
 
-    let tenant = match Tenant::get_by_id(uuid, &db).await {
+    let mut tenant = match auth.require_permission(uuid, TenantPermission::DeleteTenant, &db).await {
         Ok(tenant) => tenant,
-        Err(err) => return Json(HttpResponse {
-            status: err.status,
-            message: err.message,
-            data: None
-        })
+        Err(err) => return Json(err)
     };
 
+    tenant.soft_delete(config.tenant_deletion_grace_days);
 
-    match tenant.delete(&db).await {
+    match tenant.update(&db).await {
         Ok(tenant) => {
-            // TODO: Implement author_id
-            match AuditLog::new(tenant.id, AuditLogEntityType::Tenant, AuditLogAction::Delete, "Tenant deleted.".to_string(), Uuid::new(), None, None).insert(&db).await {
+            let new_values = HashMap::from([
+                ("deletedAt".to_string(), tenant.deleted_at.clone().unwrap_or_default()),
+                ("purgeAfter".to_string(), tenant.purge_after.map(|date| date.to_string()).unwrap_or_default())
+            ]);
+            match AuditLog::new(tenant.id, AuditLogEntityType::Tenant, AuditLogAction::Delete, "Tenant deleted.".to_string(), auth.author(), None, Some(new_values)).insert(&db).await {
                 Ok(_) => (),
                 Err(err) => error!("{}", err)
             }
 
             Json(HttpResponse {
                 status: 200,
-                message: "Tenant deleted".to_string(),
-                data: None,
+                message: format!("Tenant deleted, it can be restored for {} days", config.tenant_deletion_grace_days),
+                data: Some(tenant),
             })
-    },
+        },
         Err(err) => Json(err)
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,64 @@
+use mongodb::bson::Uuid;
+use rocket::{error, get, http::{ContentType, Header}, serde::json::Json, Responder};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::AuthenticatedUser, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, tenant::Tenant, tenant_archive::TenantArchive}};
+
+#[derive(Responder)]
+pub struct ArchiveDownload {
+    archive: Vec<u8>,
+    content_type: ContentType,
+    disposition: Header<'static>,
+}
+
+// Downloads all data of the tenant as an archive, only the owner or an admin can do this. The tenant's two-factor requirement applies.
+// Deleted tenants can still be exported until they are purged.
+#[allow(unused)]
+#[get("/tenants/<id>/export")]
+pub async fn export_tenant(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser, id: &str) -> Result<ArchiveDownload, Json<HttpResponse<()>>> {
+    let uuid = match Uuid::parse_str(id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Err(Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid UUID: {:?}", err),
+            data: None
+        }))
+    };
+
+    let tenant = match Tenant::get_by_id(uuid, &db).await {
+        Ok(tenant) => tenant,
+        Err(err) => return Err(Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        }))
+    };
+
+    if tenant.owner_id != auth.user.id && !auth.is_admin() {
+        return Err(Json(HttpResponse {
+            status: 403,
+            message: "Only the tenant owner can export the tenant".to_string(),
+            data: None
+        }));
+    }
+
+    if let Err(err) = auth.check_two_factor(&tenant) {
+        return Err(Json(err));
+    }
+
+    match TenantArchive::export(&tenant, &db).await {
+        Ok(archive) => {
+            match AuditLog::new(tenant.id, AuditLogEntityType::Tenant, AuditLogAction::Export, "Tenant exported.".to_string(), auth.author(), None, None).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            Ok(ArchiveDownload {
+                archive,
+                content_type: ContentType::TAR,
+                disposition: Header::new("Content-Disposition", format!("attachment; filename=\"{}\"", TenantArchive::file_name(&tenant)))
+            })
+        },
+        Err(err) => Err(Json(err))
+    }
+}
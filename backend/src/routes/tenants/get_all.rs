@@ -1,12 +1,15 @@
 use rocket::{get, serde::json::Json};
 use rocket_db_pools::Connection;
 
-use crate::{db::ShelfWatcherDatabase, models::{http_response::HttpResponse, tenant::Tenant}};
+use crate::{db::ShelfWatcherDatabase, middleware::auth::AuthenticatedUser, models::{http_response::HttpResponse, tenant::Tenant}};
 
 #[allow(unused)]
 #[get("/tenants", format = "json")] 
-pub async fn get_all_tenants(db: Connection<ShelfWatcherDatabase>) -> Json<HttpResponse<Vec<Tenant>>> {
-    // TODO: Only allow this for admins
+pub async fn get_all_tenants(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser) -> Json<HttpResponse<Vec<Tenant>>> {
+    if let Err(err) = auth.require_admin() {
+        return Json(err);
+    }
+
     match Tenant::get_all(&db).await {
         Ok(tenants) => Json(HttpResponse {
             status: 200,
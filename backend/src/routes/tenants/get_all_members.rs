@@ -2,12 +2,11 @@ use mongodb::bson::Uuid;
 use rocket::{get, serde::json::Json};
 use rocket_db_pools::Connection;
 
-use crate::{db::ShelfWatcherDatabase, models::{http_response::HttpResponse, tenant::Tenant, user::{User, UserMinimal}}};
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{http_response::HttpResponse, membership::TenantPermission, user::{User, UserMinimal}}};
 
 #[allow(unused)]
-#[get("/tenants/<id>/membes", format = "json")] 
-pub async fn get_all_members(id: &str, db: Connection<ShelfWatcherDatabase>) -> Json<HttpResponse<Vec<UserMinimal>>> {
-    // TODO: Only allow this for team members & admins
+#[get("/tenants/<id>/members?<include_disabled>", format = "json")] 
+pub async fn get_all_members(id: &str, include_disabled: Option<bool>, db: Connection<ShelfWatcherDatabase>, auth: Authenticated) -> Json<HttpResponse<Vec<UserMinimal>>> {
     let uuid = match Uuid::parse_str(id) {
         Ok(uuid) => uuid,
         Err(err) => return Json(HttpResponse {
@@ -17,24 +16,20 @@ pub async fn get_all_members(id: &str, db: Connection<ShelfWatcherDatabase>) ->
         })
     };
 
-    let users = match User::get_all(&db).await {
-        Ok(users) => users,
+    let tenant = match auth.require_permission(uuid, TenantPermission::ViewTenant, &db).await {
+        Ok(tenant) => tenant,
         Err(err) => return Json(err)
     };
 
-    match Tenant::get_by_id(uuid, &db).await {
-        Ok(tenant) => {
-            let members = users.iter().filter(|user| user.tenants.contains(&tenant.id)).cloned().collect();
-        
-            Json(HttpResponse {
-                status: 200,
-                message: "Successfully retrieved all members".to_string(),
-                data: Some(members),
-            })
-        },
+    match User::get_all_from_tenant(tenant.id, include_disabled.unwrap_or(false), &db).await {
+        Ok(members) => Json(HttpResponse {
+            status: 200,
+            message: "Successfully retrieved all members".to_string(),
+            data: Some(members),
+        }),
         Err(err) => Json(HttpResponse {
             status: 500,
-            message: format!("Failed to retrieve all members: {:?}", err),
+            message: format!("Failed to retrieve all members: {}", err.message),
             data: None,
         })
     }
@@ -2,11 +2,11 @@ use mongodb::bson::Uuid;
 use rocket::{get, serde::json::Json};
 use rocket_db_pools::Connection;
 
-use crate::{db::ShelfWatcherDatabase, models::{http_response::HttpResponse, tenant::Tenant}};
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{http_response::HttpResponse, membership::TenantPermission, tenant::Tenant}};
 
 #[allow(unused)]
 #[get("/tenants/<id>", format = "json")] 
-pub async fn get_tenant_by_id(db: Connection<ShelfWatcherDatabase>, id: &str) -> Json<HttpResponse<Tenant>> {
+pub async fn get_tenant_by_id(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, id: &str) -> Json<HttpResponse<Tenant>> {
     let uuid = match Uuid::parse_str(id) {
         Ok(uuid) => uuid,
         Err(err) => return Json(HttpResponse {
@@ -17,7 +17,7 @@ pub async fn get_tenant_by_id(db: Connection<ShelfWatcherDatabase>, id: &str) ->
     };
 
 
-    match Tenant::get_by_id(uuid, &db).await {
+    match auth.require_permission(uuid, TenantPermission::ViewTenant, &db).await {
         Ok(tenant) => Json(HttpResponse {
             status: 200,
             message: "Found tenant by id".to_string(),
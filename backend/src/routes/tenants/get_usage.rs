@@ -0,0 +1,33 @@
+use mongodb::bson::Uuid;
+use rocket::{get, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{http_response::HttpResponse, membership::TenantPermission, tenant_plan::TenantUsage}};
+
+// Current consumption of the tenant against the limits of its plan and overrides
+#[allow(unused)]
+#[get("/tenants/<id>/usage", format = "json")]
+pub async fn get_tenant_usage(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, id: &str) -> Json<HttpResponse<TenantUsage>> {
+    let uuid = match Uuid::parse_str(id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let tenant = match auth.require_permission(uuid, TenantPermission::ViewTenant, &db).await {
+        Ok(tenant) => tenant,
+        Err(err) => return Json(err)
+    };
+
+    match TenantUsage::of(&tenant, &db).await {
+        Ok(usage) => Json(HttpResponse {
+            status: 200,
+            message: "Successfully retrieved tenant usage".to_string(),
+            data: Some(usage)
+        }),
+        Err(err) => Json(err)
+    }
+}
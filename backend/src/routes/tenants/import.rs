@@ -0,0 +1,47 @@
+use rocket::{data::{Data, Limits, ToByteUnit}, post, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::AuthenticatedUser, models::{http_response::HttpResponse, tenant_archive::{ImportedTenant, TenantArchive}}};
+
+// Creates a new tenant from an exported archive, owned by the importing user. The archive's name is used unless `name` is given.
+// Only admins keep the archived plan, everyone else starts on the default plan and its quotas.
+// The upload size is limited by the `tenant-archive` limit, 256 MiB unless configured.
+#[allow(unused)]
+#[post("/tenants/import?<name>", format = "application/x-tar", data = "<archive>")]
+pub async fn import_tenant(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser, limits: &Limits, name: Option<&str>, archive: Data<'_>) -> Json<HttpResponse<ImportedTenant>> {
+    let archive = match archive.open(limits.get("tenant-archive").unwrap_or(256.mebibytes())).into_bytes().await {
+        Ok(archive) if archive.is_complete() => archive.into_inner(),
+        Ok(_) => return Json(HttpResponse {
+            status: 413,
+            message: "Archive is too large".to_string(),
+            data: None
+        }),
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Error reading archive: {}", err),
+            data: None
+        })
+    };
+
+    let name = match name.map(str::trim) {
+        Some("") => return Json(HttpResponse {
+            status: 400,
+            message: "Name must not be empty".to_string(),
+            data: None
+        }),
+        name => name.map(str::to_string)
+    };
+
+    match TenantArchive::import(&archive, name, &auth.user, auth.is_admin(), auth.author(), &db).await {
+        Ok(imported) => Json(HttpResponse {
+            status: 201,
+            message: "Tenant imported".to_string(),
+            data: Some(imported)
+        }),
+        Err(err) => Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    }
+}
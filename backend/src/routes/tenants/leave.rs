@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+use mongodb::bson::Uuid;
+use rocket::{error, post, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::AuthenticatedUser, models::{api_key::ApiKey, audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, tenant::Tenant}};
+
+#[allow(unused)]
+#[post("/tenants/<id>/leave", format = "json")]
+pub async fn leave_tenant(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser, id: &str) -> Json<HttpResponse<()>> {
+    let uuid = match Uuid::parse_str(id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let tenant = match Tenant::get_by_id(uuid, &db).await {
+        Ok(tenant) => tenant,
+        Err(err) => return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    };
+
+    if tenant.owner_id == auth.user.id {
+        return Json(HttpResponse {
+            status: 400,
+            message: "The owner cannot leave the tenant, transfer the ownership first".to_string(),
+            data: None
+        });
+    }
+
+    let role = match auth.user.membership(tenant.id) {
+        Some(membership) => membership.role,
+        None => return Json(HttpResponse {
+            status: 404,
+            message: "You are not a member of this tenant".to_string(),
+            data: None
+        })
+    };
+
+    match auth.user.remove_membership(tenant.id, role, &db).await {
+        Ok(Some(user)) => {
+            let old_values = HashMap::from([
+                ("memberId".to_string(), user.id.to_string()),
+                ("role".to_string(), format!("{:?}", role))
+            ]);
+            match AuditLog::new(tenant.id, AuditLogEntityType::Tenant, AuditLogAction::Update, "Member left tenant.".to_string(), auth.author(), Some(old_values), None).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            // The keys they created must not keep the access they just lost
+            if let Err(err) = ApiKey::revoke_all_created_by(user.id, Some(tenant.id), &db).await {
+                return Json(HttpResponse {
+                    status: 500,
+                    message: format!("Left tenant, but revoking their API keys failed: {}", err.message),
+                    data: None
+                });
+            }
+
+            Json(HttpResponse {
+                status: 200,
+                message: "Left tenant".to_string(),
+                data: None
+            })
+        },
+        Ok(None) => Json(HttpResponse {
+            status: 400,
+            message: "Your membership was changed in the meantime".to_string(),
+            data: None
+        }),
+        Err(err) => Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    }
+}
@@ -2,5 +2,19 @@ pub mod create;
 pub mod get_all;
 pub mod get_by_id;
 pub mod get_all_members;
+pub mod add_member;
+pub mod update_member;
+pub mod remove_member;
+pub mod leave;
 pub mod update;
-pub mod delete;
\ No newline at end of file
+pub mod delete;
+
+pub mod get_usage;
+pub mod update_plan;
+pub mod restore;
+pub mod propose_ownership_transfer;
+pub mod accept_ownership_transfer;
+pub mod cancel_ownership_transfer;
+pub mod update_settings;
+pub mod export;
+pub mod import;
\ No newline at end of file
@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+use mongodb::bson::Uuid;
+use rocket::{error, post, serde::{json::Json, Deserialize}};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::AuthenticatedUser, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, membership::TenantPermission, tenant::Tenant, user::User}};
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ProposeOwnershipTransferData {
+    #[serde(rename = "userId")]
+    user_id: String
+}
+
+// The owner offers the tenant to another member, a new proposal replaces a pending one
+#[allow(unused)]
+#[post("/tenants/<id>/ownership-transfer", format = "json", data = "<data>")]
+pub async fn propose_ownership_transfer(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser, id: &str, data: Json<ProposeOwnershipTransferData>) -> Json<HttpResponse<Tenant>> {
+    let data = data.into_inner();
+
+    let uuid = match Uuid::parse_str(id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let user_uuid = match Uuid::parse_str(&data.user_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid user UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let mut tenant = match auth.require_permission(uuid, TenantPermission::ViewTenant, &db).await {
+        Ok(tenant) => tenant,
+        Err(err) => return Json(err)
+    };
+
+    if tenant.owner_id != auth.user.id {
+        return Json(HttpResponse {
+            status: 403,
+            message: "Only the tenant owner can transfer ownership".to_string(),
+            data: None
+        });
+    }
+
+    if user_uuid == tenant.owner_id {
+        return Json(HttpResponse {
+            status: 400,
+            message: "User already owns this tenant".to_string(),
+            data: None
+        });
+    }
+
+    let user = match User::get_full_by_id(user_uuid, &db).await {
+        Ok(user) => user,
+        Err(err) => return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    };
+
+    if user.membership(tenant.id).is_none() {
+        return Json(HttpResponse {
+            status: 400,
+            message: "The new owner must already be a member of this tenant".to_string(),
+            data: None
+        });
+    }
+
+    let old_values = HashMap::from([
+        ("pendingOwnerId".to_string(), tenant.pending_owner_id.map(|id| id.to_string()).unwrap_or_default())
+    ]);
+
+    match tenant.set_pending_owner(Some(user.id), &db).await {
+        Ok(false) => Json(HttpResponse {
+            status: 409,
+            message: "Ownership transfer was changed in the meantime".to_string(),
+            data: None
+        }),
+        Ok(true) => {
+            let new_values = HashMap::from([
+                ("pendingOwnerId".to_string(), user.id.to_string())
+            ]);
+            match AuditLog::new(tenant.id, AuditLogEntityType::Tenant, AuditLogAction::Update, "Ownership transfer proposed.".to_string(), auth.author(), Some(old_values), Some(new_values)).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            Json(HttpResponse {
+                status: 200,
+                message: "Ownership transfer proposed".to_string(),
+                data: Some(tenant)
+            })
+        },
+        Err(err) => Json(err)
+    }
+}
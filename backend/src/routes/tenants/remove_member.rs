@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+use mongodb::bson::Uuid;
+use rocket::{delete, error, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::AuthenticatedUser, models::{api_key::ApiKey, audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, membership::TenantPermission, user::User}};
+
+#[allow(unused)]
+#[delete("/tenants/<id>/members/<user_id>", format = "json")]
+pub async fn remove_member(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser, id: &str, user_id: &str) -> Json<HttpResponse<()>> {
+    let uuid = match Uuid::parse_str(id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let user_uuid = match Uuid::parse_str(user_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid user UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let tenant = match auth.require_permission(uuid, TenantPermission::ManageMembers, &db).await {
+        Ok(tenant) => tenant,
+        Err(err) => return Json(err)
+    };
+
+    if tenant.owner_id == user_uuid {
+        return Json(HttpResponse {
+            status: 400,
+            message: "The owner cannot be removed, transfer the ownership first".to_string(),
+            data: None
+        });
+    }
+
+    let user = match User::get_full_by_id(user_uuid, &db).await {
+        Ok(user) => user,
+        Err(err) => return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    };
+
+    let role = match user.membership(tenant.id) {
+        Some(membership) => membership.role,
+        None => return Json(HttpResponse {
+            status: 404,
+            message: "User is not a member of this tenant".to_string(),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_can_manage_role(&tenant, role) {
+        return Json(err);
+    }
+
+    match user.remove_membership(tenant.id, role, &db).await {
+        Ok(Some(user)) => {
+            let old_values = HashMap::from([
+                ("memberId".to_string(), user.id.to_string()),
+                ("role".to_string(), format!("{:?}", role))
+            ]);
+            match AuditLog::new(tenant.id, AuditLogEntityType::Tenant, AuditLogAction::Update, "Member removed.".to_string(), auth.author(), Some(old_values), None).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            // The keys they created must not keep the access they just lost
+            if let Err(err) = ApiKey::revoke_all_created_by(user.id, Some(tenant.id), &db).await {
+                return Json(HttpResponse {
+                    status: 500,
+                    message: format!("Member removed, but revoking their API keys failed: {}", err.message),
+                    data: None
+                });
+            }
+
+            Json(HttpResponse {
+                status: 200,
+                message: "Member removed".to_string(),
+                data: None
+            })
+        },
+        Ok(None) => Json(HttpResponse {
+            status: 400,
+            message: "The membership was changed in the meantime".to_string(),
+            data: None
+        }),
+        Err(err) => Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    }
+}
@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+use mongodb::bson::Uuid;
+use rocket::{error, post, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::AuthenticatedUser, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, tenant::Tenant}};
+
+// Undoes a deletion during the grace period, only the owner or an admin can do this
+#[allow(unused)]
+#[post("/tenants/<id>/restore", format = "json")]
+pub async fn restore_tenant(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser, id: &str) -> Json<HttpResponse<Tenant>> {
+    let uuid = match Uuid::parse_str(id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let mut tenant = match Tenant::get_by_id(uuid, &db).await {
+        Ok(tenant) => tenant,
+        Err(err) => return Json(err)
+    };
+
+    if tenant.owner_id != auth.user.id && !auth.is_admin() {
+        return Json(HttpResponse {
+            status: 403,
+            message: "Only the tenant owner can restore the tenant".to_string(),
+            data: None
+        });
+    }
+
+    if !tenant.is_deleted() {
+        return Json(HttpResponse {
+            status: 400,
+            message: "Tenant is not deleted".to_string(),
+            data: None
+        });
+    }
+
+    let old_values = HashMap::from([
+        ("deletedAt".to_string(), tenant.deleted_at.clone().unwrap_or_default()),
+        ("purgeAfter".to_string(), tenant.purge_after.map(|date| date.to_string()).unwrap_or_default())
+    ]);
+
+    match tenant.restore(&db).await {
+        Ok(true) => {
+            match AuditLog::new(tenant.id, AuditLogEntityType::Tenant, AuditLogAction::Update, "Tenant restored.".to_string(), auth.author(), Some(old_values), None).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            Json(HttpResponse {
+                status: 200,
+                message: "Tenant restored".to_string(),
+                data: Some(tenant)
+            })
+        },
+        Ok(false) => Json(HttpResponse {
+            status: 409,
+            message: "Tenant was restored or is being purged in the meantime".to_string(),
+            data: None
+        }),
+        Err(err) => Json(err)
+    }
+}
@@ -3,19 +3,25 @@ use mongodb::bson::Uuid;
 use rocket::{error, patch, serde::{json::Json, Deserialize}};
 use rocket_db_pools::Connection;
 
-use crate::{db::ShelfWatcherDatabase, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, tenant::Tenant}};
+use crate::{db::ShelfWatcherDatabase, middleware::auth::AuthenticatedUser, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, membership::{TenantPermission, TenantRole}, tenant::Tenant, tenant_settings::TenantSettings}};
 
 #[derive(Deserialize)]
 #[serde(crate = "rocket::serde")]
 pub struct UpdateTenantData {
     name: Option<String>,
+    // Rejected, ownership moves through the ownership transfer routes
     #[serde(rename = "ownerId")]
-    owner_id: Option<String>
+    owner_id: Option<String>,
+    #[serde(rename = "requireTwoFactor")]
+    require_two_factor: Option<bool>,
+    // Deprecated, kept for older clients. Moved to the tenant settings.
+    #[serde(rename = "expiryWarningDays")]
+    expiry_warning_days: Option<i64>
 }
 
 #[allow(unused)]
 #[patch("/tenants/<id>", format = "json", data = "<data>")] 
-pub async fn update_tenant(db: Connection<ShelfWatcherDatabase>, id: &str, data: Json<UpdateTenantData>) -> Json<HttpResponse<Tenant>> { 
+pub async fn update_tenant(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser, id: &str, data: Json<UpdateTenantData>) -> Json<HttpResponse<Tenant>> { 
     let data = data.into_inner();
 
     let uuid = match Uuid::parse_str(id) {
@@ -27,7 +33,15 @@ pub async fn update_tenant(db: Connection<ShelfWatcherDatabase>, id: &str, data:
         })
     };
 
-    let old_tenant = match Tenant::get_by_id(uuid, &db).await {
+    if data.owner_id.is_some() {
+        return Json(HttpResponse {
+            status: 400,
+            message: "Ownership can only change through an ownership transfer".to_string(),
+            data: None
+        });
+    }
+
+    let old_tenant = match auth.require_permission(uuid, TenantPermission::UpdateTenant, &db).await {
         Ok(tenant) => tenant,
         Err(err) => return Json(err)
     };
@@ -37,24 +51,33 @@ pub async fn update_tenant(db: Connection<ShelfWatcherDatabase>, id: &str, data:
     let mut old_values: HashMap<String, String> = HashMap::new();
     let mut new_values: HashMap<String, String> = HashMap::new();
 
-    if data.name.is_some() {
-        new_tenant.name = data.name.unwrap();
+    if let Some(name) = data.name {
+        new_tenant.name = name;
         old_values.insert("name".to_owned(), old_tenant.name.clone());
         new_values.insert("name".to_owned(), new_tenant.name.clone());
     }
-    if data.owner_id.is_some() {
-        let owner_id = match Uuid::parse_str(data.owner_id.unwrap()) {
-            Ok(owner_id) => owner_id,
-            Err(err) => return Json(HttpResponse {
-                status: 400,
-                message: format!("Invalid owner ID: {:?}", err),
+
+    if let Some(require_two_factor) = data.require_two_factor {
+        if auth.role_in(&old_tenant) != Some(TenantRole::Owner) && !auth.is_admin() {
+            return Json(HttpResponse {
+                status: 403,
+                message: "Only the tenant owner can change the two-factor requirement".to_string(),
                 data: None
-            })
-        };
+            });
+        }
 
-        new_tenant.owner_id = owner_id;
-        old_values.insert("ownerId".to_owned(), old_tenant.owner_id.clone().to_string());
-        new_values.insert("ownerId".to_owned(), new_tenant.owner_id.clone().to_string());
+        new_tenant.require_two_factor = require_two_factor;
+        old_values.insert("requireTwoFactor".to_owned(), old_tenant.require_two_factor.to_string());
+        new_values.insert("requireTwoFactor".to_owned(), new_tenant.require_two_factor.to_string());
+    }
+
+    if let Some(expiry_warning_days) = data.expiry_warning_days {
+        new_tenant.settings.expiry_warning_days = match TenantSettings::validate_expiry_warning_days(expiry_warning_days) {
+            Ok(days) => days,
+            Err(err) => return Json(err)
+        };
+        old_values.insert("settings.expiryWarningDays".to_owned(), old_tenant.settings.expiry_warning_days.to_string());
+        new_values.insert("settings.expiryWarningDays".to_owned(), new_tenant.settings.expiry_warning_days.to_string());
     }
 
     if new_values.is_empty() {
@@ -67,8 +90,7 @@ pub async fn update_tenant(db: Connection<ShelfWatcherDatabase>, id: &str, data:
 
     match new_tenant.update(&db).await {
         Ok(tenant) => {
-            // TODO: Implement author_id
-            match AuditLog::new(tenant.id, AuditLogEntityType::Tenant, AuditLogAction::Update, "Tenant updated.".to_string(), Uuid::new(), Some(old_values), Some(new_values)).insert(&db).await {
+            match AuditLog::new(tenant.id, AuditLogEntityType::Tenant, AuditLogAction::Update, "Tenant updated.".to_string(), auth.author(), Some(old_values), Some(new_values)).insert(&db).await {
                 Ok(_) => (),
                 Err(err) => error!("{}", err)
             }
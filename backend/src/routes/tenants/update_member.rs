@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+use mongodb::bson::Uuid;
+use rocket::{error, patch, serde::{json::Json, Deserialize}};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::AuthenticatedUser, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, membership::{TenantPermission, TenantRole}, user::{User, UserMinimal}}};
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct UpdateMemberData {
+    role: String
+}
+
+#[allow(unused)]
+#[patch("/tenants/<id>/members/<user_id>", format = "json", data = "<data>")]
+pub async fn update_member(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser, id: &str, user_id: &str, data: Json<UpdateMemberData>) -> Json<HttpResponse<UserMinimal>> {
+    let data = data.into_inner();
+
+    let uuid = match Uuid::parse_str(id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let user_uuid = match Uuid::parse_str(user_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid user UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let new_role = match TenantRole::from_string(&data.role) {
+        Ok(role) => role,
+        Err(err) => return Json(err)
+    };
+
+    if new_role == TenantRole::Owner {
+        return Json(HttpResponse {
+            status: 400,
+            message: "Ownership can only be transferred, not granted".to_string(),
+            data: None
+        });
+    }
+
+    let tenant = match auth.require_permission(uuid, TenantPermission::ManageMembers, &db).await {
+        Ok(tenant) => tenant,
+        Err(err) => return Json(err)
+    };
+
+    if tenant.owner_id == user_uuid {
+        return Json(HttpResponse {
+            status: 400,
+            message: "The owner's role can only change through an ownership transfer".to_string(),
+            data: None
+        });
+    }
+
+    let user = match User::get_full_by_id(user_uuid, &db).await {
+        Ok(user) => user,
+        Err(err) => return Json(err)
+    };
+
+    let old_role = match user.membership(tenant.id) {
+        Some(membership) => membership.role,
+        None => return Json(HttpResponse {
+            status: 404,
+            message: "User is not a member of this tenant".to_string(),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_can_manage_role(&tenant, old_role).and(auth.require_can_manage_role(&tenant, new_role)) {
+        return Json(err);
+    }
+
+    if old_role == new_role {
+        return Json(HttpResponse {
+            status: 200,
+            message: "No updates applied.".to_string(),
+            data: Some(user.to_minimal())
+        });
+    }
+
+    match user.set_membership_role(tenant.id, Some(old_role), new_role, &db).await {
+        Ok(Some(user)) => {
+            let old_values = HashMap::from([
+                ("memberId".to_string(), user.id.to_string()),
+                ("role".to_string(), format!("{:?}", old_role))
+            ]);
+            let new_values = HashMap::from([
+                ("memberId".to_string(), user.id.to_string()),
+                ("role".to_string(), format!("{:?}", new_role))
+            ]);
+            match AuditLog::new(tenant.id, AuditLogEntityType::Tenant, AuditLogAction::Update, "Member role changed.".to_string(), auth.author(), Some(old_values), Some(new_values)).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            Json(HttpResponse {
+                status: 200,
+                message: "Member updated".to_string(),
+                data: Some(user)
+            })
+        },
+        Ok(None) => Json(HttpResponse {
+            status: 400,
+            message: "The membership was changed in the meantime".to_string(),
+            data: None
+        }),
+        Err(err) => Json(err)
+    }
+}
@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+use mongodb::bson::Uuid;
+use rocket::{error, patch, serde::{json::Json, Deserialize}};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::AuthenticatedUser, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, tenant::Tenant, tenant_plan::{QuotaOverrides, TenantPlan}}};
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct UpdateTenantPlanData {
+    plan: Option<String>,
+    // Replaces all overrides, an empty object goes back to the plan's limits
+    #[serde(rename = "quotaOverrides")]
+    quota_overrides: Option<QuotaOverrides>
+}
+
+#[allow(unused)]
+#[patch("/tenants/<id>/plan", format = "json", data = "<data>")]
+pub async fn update_tenant_plan(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser, id: &str, data: Json<UpdateTenantPlanData>) -> Json<HttpResponse<Tenant>> {
+    let data = data.into_inner();
+
+    if let Err(err) = auth.require_admin() {
+        return Json(err);
+    }
+
+    let uuid = match Uuid::parse_str(id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let old_tenant = match Tenant::get_by_id(uuid, &db).await {
+        Ok(tenant) => tenant,
+        Err(err) => return Json(err)
+    };
+
+    let mut new_tenant = old_tenant.clone();
+
+    let mut old_values: HashMap<String, String> = HashMap::new();
+    let mut new_values: HashMap<String, String> = HashMap::new();
+
+    if let Some(plan) = data.plan {
+        new_tenant.plan = match TenantPlan::from_string(&plan) {
+            Ok(plan) => plan,
+            Err(err) => return Json(err)
+        };
+
+        old_values.insert("plan".to_owned(), format!("{:?}", old_tenant.plan));
+        new_values.insert("plan".to_owned(), format!("{:?}", new_tenant.plan));
+    }
+
+    if let Some(quota_overrides) = data.quota_overrides {
+        if let Err(err) = quota_overrides.validate() {
+            return Json(err);
+        }
+
+        new_tenant.quota_overrides = quota_overrides;
+        old_values.extend(old_tenant.quota_overrides.to_values());
+        new_values.extend(new_tenant.quota_overrides.to_values());
+    }
+
+    if new_values.is_empty() {
+        return Json(HttpResponse {
+            status: 200,
+            message: "No updates applied.".to_string(),
+            data: Some(new_tenant)
+        });
+    }
+
+    // Lowering a limit below the current usage is allowed, it only blocks creating more
+    match new_tenant.update(&db).await {
+        Ok(tenant) => {
+            match AuditLog::new(tenant.id, AuditLogEntityType::Tenant, AuditLogAction::Update, "Tenant plan updated.".to_string(), auth.author(), Some(old_values), Some(new_values)).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            Json(HttpResponse {
+                status: 200,
+                message: "Tenant plan updated".to_string(),
+                data: Some(tenant)
+            })
+        },
+        Err(err) => Json(err)
+    }
+}
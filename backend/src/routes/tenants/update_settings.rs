@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+use mongodb::bson::Uuid;
+use rocket::{error, patch, serde::{json::Json, Deserialize}};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::AuthenticatedUser, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, membership::TenantPermission, tenant::Tenant, tenant_settings::{DateFormat, MeasurementSystem, TenantSettings}}};
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct UpdateTenantSettingsData {
+    currency: Option<String>,
+    timezone: Option<String>,
+    #[serde(rename = "measurementSystem")]
+    measurement_system: Option<String>,
+    #[serde(rename = "expiryWarningDays")]
+    expiry_warning_days: Option<i64>,
+    #[serde(rename = "dateFormat")]
+    date_format: Option<String>,
+    language: Option<String>
+}
+
+// Changing the currency doesn't convert anything, batches keep the currency their cost was recorded in
+#[allow(unused)]
+#[patch("/tenants/<id>/settings", format = "json", data = "<data>")]
+pub async fn update_tenant_settings(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser, id: &str, data: Json<UpdateTenantSettingsData>) -> Json<HttpResponse<Tenant>> {
+    let data = data.into_inner();
+
+    let uuid = match Uuid::parse_str(id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let old_tenant = match auth.require_permission(uuid, TenantPermission::UpdateTenant, &db).await {
+        Ok(tenant) => tenant,
+        Err(err) => return Json(err)
+    };
+
+    let old_settings = &old_tenant.settings;
+    let mut new_tenant = old_tenant.clone();
+
+    let mut old_values: HashMap<String, String> = HashMap::new();
+    let mut new_values: HashMap<String, String> = HashMap::new();
+
+    if let Some(currency) = data.currency {
+        new_tenant.settings.currency = match TenantSettings::normalize_currency(&currency) {
+            Ok(currency) => currency,
+            Err(err) => return Json(err)
+        };
+        old_values.insert("settings.currency".to_owned(), old_settings.currency.clone());
+        new_values.insert("settings.currency".to_owned(), new_tenant.settings.currency.clone());
+    }
+
+    if let Some(timezone) = data.timezone {
+        new_tenant.settings.timezone = match TenantSettings::normalize_timezone(&timezone) {
+            Ok(timezone) => timezone,
+            Err(err) => return Json(err)
+        };
+        old_values.insert("settings.timezone".to_owned(), old_settings.timezone.clone());
+        new_values.insert("settings.timezone".to_owned(), new_tenant.settings.timezone.clone());
+    }
+
+    if let Some(measurement_system) = data.measurement_system {
+        new_tenant.settings.measurement_system = match MeasurementSystem::from_string(&measurement_system) {
+            Ok(measurement_system) => measurement_system,
+            Err(err) => return Json(err)
+        };
+        old_values.insert("settings.measurementSystem".to_owned(), format!("{:?}", old_settings.measurement_system));
+        new_values.insert("settings.measurementSystem".to_owned(), format!("{:?}", new_tenant.settings.measurement_system));
+    }
+
+    if let Some(expiry_warning_days) = data.expiry_warning_days {
+        new_tenant.settings.expiry_warning_days = match TenantSettings::validate_expiry_warning_days(expiry_warning_days) {
+            Ok(days) => days,
+            Err(err) => return Json(err)
+        };
+        old_values.insert("settings.expiryWarningDays".to_owned(), old_settings.expiry_warning_days.to_string());
+        new_values.insert("settings.expiryWarningDays".to_owned(), new_tenant.settings.expiry_warning_days.to_string());
+    }
+
+    if let Some(date_format) = data.date_format {
+        new_tenant.settings.date_format = match DateFormat::from_string(&date_format) {
+            Ok(date_format) => date_format,
+            Err(err) => return Json(err)
+        };
+        old_values.insert("settings.dateFormat".to_owned(), old_settings.date_format.as_str().to_string());
+        new_values.insert("settings.dateFormat".to_owned(), new_tenant.settings.date_format.as_str().to_string());
+    }
+
+    if let Some(language) = data.language {
+        new_tenant.settings.language = match TenantSettings::normalize_language(&language) {
+            Ok(language) => language,
+            Err(err) => return Json(err)
+        };
+        old_values.insert("settings.language".to_owned(), old_settings.language.clone());
+        new_values.insert("settings.language".to_owned(), new_tenant.settings.language.clone());
+    }
+
+    if new_values.is_empty() {
+        return Json(HttpResponse {
+            status: 200,
+            message: "No updates applied.".to_string(),
+            data: Some(new_tenant)
+        });
+    }
+
+    match new_tenant.update(&db).await {
+        Ok(tenant) => {
+            match AuditLog::new(tenant.id, AuditLogEntityType::Tenant, AuditLogAction::Update, "Tenant settings updated.".to_string(), auth.author(), Some(old_values), Some(new_values)).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            Json(HttpResponse {
+                status: 200,
+                message: "Tenant settings updated".to_string(),
+                data: Some(tenant)
+            })
+        },
+        Err(err) => Json(err)
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use mongodb::bson::Uuid;
+use rocket::{error, post, serde::{json::Json, Deserialize}};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, location::Location, membership::TenantPermission, transfer::{Transfer, TransferLine}}};
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct TransferLineData {
+    #[serde(rename = "productId")]
+    pub product_id: String,
+    #[serde(rename = "batchId")]
+    pub batch_id: Option<String>,
+    pub quantity: f64
+}
+
+impl TransferLineData {
+    pub fn parse<T>(&self) -> Result<(Uuid, Option<Uuid>), HttpResponse<T>> {
+        let product_uuid = match Uuid::parse_str(&self.product_id) {
+            Ok(uuid) => uuid,
+            Err(err) => return Err(HttpResponse {
+                status: 400,
+                message: format!("Invalid product UUID: {:?}", err),
+                data: None
+            })
+        };
+
+        let batch_uuid = match self.batch_id.as_deref().map(Uuid::parse_str) {
+            Some(Ok(uuid)) => Some(uuid),
+            Some(Err(err)) => return Err(HttpResponse {
+                status: 400,
+                message: format!("Invalid batch UUID: {:?}", err),
+                data: None
+            }),
+            None => None
+        };
+
+        Ok((product_uuid, batch_uuid))
+    }
+
+    pub fn to_lines<T>(lines: &[Self]) -> Result<Vec<TransferLine>, HttpResponse<T>> {
+        let mut parsed = Vec::new();
+
+        for line in lines {
+            let (product_id, batch_id) = line.parse()?;
+            parsed.push(TransferLine {
+                product_id,
+                batch_id,
+                quantity: line.quantity,
+                received_quantity: None,
+                discrepancy: None
+            });
+        }
+
+        Ok(parsed)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CreateTransferData {
+    #[serde(rename = "sourceLocationId")]
+    source_location_id: String,
+    #[serde(rename = "destinationLocationId")]
+    destination_location_id: String,
+    lines: Vec<TransferLineData>,
+    note: Option<String>
+}
+
+#[allow(unused)]
+#[post("/tenants/<tenant_id>/transfers", format = "json", data = "<data>")]
+pub async fn create_transfer(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, data: Json<CreateTransferData>) -> Json<HttpResponse<Transfer>> {
+    let data = data.into_inner();
+
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let tenant = match auth.require_permission(tenant_uuid, TenantPermission::MoveStock, &db).await {
+        Ok(tenant) => tenant,
+        Err(err) => return Json(err)
+    };
+
+    let source_location_uuid = match Uuid::parse_str(&data.source_location_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid source location UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let destination_location_uuid = match Uuid::parse_str(&data.destination_location_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid destination location UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    for location_uuid in [source_location_uuid, destination_location_uuid] {
+        if let Err(err) = Location::get_from_tenant(location_uuid, tenant_uuid, &db).await {
+            return Json(HttpResponse {
+                status: err.status,
+                message: err.message,
+                data: None
+            });
+        }
+    }
+
+    let lines = match TransferLineData::to_lines(&data.lines) {
+        Ok(lines) => lines,
+        Err(err) => return Json(err)
+    };
+
+    let note = data.note.map(|note| note.trim().to_string()).filter(|note| !note.is_empty());
+    let transfer = Transfer::new(tenant_uuid, source_location_uuid, destination_location_uuid, lines, note);
+
+    if let Err(err) = transfer.validate(&tenant.settings.today(), &db).await {
+        return Json(err);
+    }
+
+    match transfer.insert(&db).await {
+        Ok(transfer) => {
+            let new_values = HashMap::from([
+                ("sourceLocationId".to_string(), transfer.source_location_id.to_string()),
+                ("destinationLocationId".to_string(), transfer.destination_location_id.to_string()),
+                ("lines".to_string(), transfer.lines.len().to_string())
+            ]);
+            match AuditLog::new(transfer.id, AuditLogEntityType::Transfer, AuditLogAction::Create, "Transfer created.".to_string(), auth.author(), None, Some(new_values)).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            Json(HttpResponse {
+                status: 201,
+                message: "Transfer created".to_string(),
+                data: Some(transfer)
+            })
+        },
+        Err(err) => Json(err)
+    }
+}
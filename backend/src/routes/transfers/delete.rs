@@ -0,0 +1,65 @@
+use mongodb::bson::Uuid;
+use rocket::{delete, error, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, membership::TenantPermission, transfer::{Transfer, TransferStatus}}};
+
+#[allow(unused)]
+#[delete("/tenants/<tenant_id>/transfers/<transfer_id>", format = "json")]
+pub async fn delete_transfer(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, transfer_id: &str) -> Json<HttpResponse<()>> {
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::MoveStock, &db).await {
+        return Json(err);
+    }
+
+    let transfer_uuid = match Uuid::parse_str(transfer_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid transfer UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let transfer = match Transfer::get_from_tenant(transfer_uuid, tenant_uuid, &db).await {
+        Ok(transfer) => transfer,
+        Err(err) => return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    };
+
+    // Shipped transfers have moved stock already
+    if transfer.status != TransferStatus::Draft {
+        return Json(HttpResponse {
+            status: 400,
+            message: "Only draft transfers can be deleted".to_string(),
+            data: None
+        });
+    }
+
+    match transfer.delete(&db).await {
+        Ok(transfer) => {
+            match AuditLog::new(transfer.id, AuditLogEntityType::Transfer, AuditLogAction::Delete, "Transfer deleted.".to_string(), auth.author(), None, None).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            Json(HttpResponse {
+                status: 200,
+                message: "Transfer deleted".to_string(),
+                data: None
+            })
+        },
+        Err(err) => Json(err)
+    }
+}
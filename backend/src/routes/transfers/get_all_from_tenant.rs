@@ -0,0 +1,47 @@
+use mongodb::bson::Uuid;
+use rocket::{get, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{http_response::HttpResponse, membership::TenantPermission, transfer::{Transfer, TransferStatus}}};
+
+#[allow(unused)]
+#[get("/tenants/<tenant_id>/transfers?<status>&<location_id>", format = "json")]
+pub async fn get_all_transfers_from_tenant(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, status: Option<&str>, location_id: Option<&str>) -> Json<HttpResponse<Vec<Transfer>>> {
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ViewTenant, &db).await {
+        return Json(err);
+    }
+
+    let status = match status.map(TransferStatus::from_string) {
+        Some(Ok(status)) => Some(status),
+        Some(Err(err)) => return Json(err),
+        None => None
+    };
+
+    let location_uuid = match location_id.map(Uuid::parse_str) {
+        Some(Ok(uuid)) => Some(uuid),
+        Some(Err(err)) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid location UUID: {:?}", err),
+            data: None
+        }),
+        None => None
+    };
+
+    match Transfer::get_all_from_tenant(tenant_uuid, status, location_uuid, &db).await {
+        Ok(transfers) => Json(HttpResponse {
+            status: 200,
+            message: "Successfully retrieved transfers".to_string(),
+            data: Some(transfers)
+        }),
+        Err(err) => Json(err)
+    }
+}
@@ -0,0 +1,40 @@
+use mongodb::bson::Uuid;
+use rocket::{get, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{http_response::HttpResponse, membership::TenantPermission, transfer::Transfer}};
+
+#[allow(unused)]
+#[get("/tenants/<tenant_id>/transfers/<transfer_id>", format = "json")]
+pub async fn get_transfer_by_id(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, transfer_id: &str) -> Json<HttpResponse<Transfer>> {
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ViewTenant, &db).await {
+        return Json(err);
+    }
+
+    let transfer_uuid = match Uuid::parse_str(transfer_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid transfer UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    match Transfer::get_from_tenant(transfer_uuid, tenant_uuid, &db).await {
+        Ok(transfer) => Json(HttpResponse {
+            status: 200,
+            message: "Successfully retrieved transfer".to_string(),
+            data: Some(transfer)
+        }),
+        Err(err) => Json(err)
+    }
+}
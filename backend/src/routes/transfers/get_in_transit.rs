@@ -0,0 +1,52 @@
+use mongodb::bson::Uuid;
+use rocket::{get, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{http_response::HttpResponse, membership::TenantPermission, transfer::{Transfer, TransitLine}}};
+
+// Goods that left their source location but haven't been received yet, they aren't part of the stock on hand
+#[allow(unused)]
+#[get("/tenants/<tenant_id>/stock/in-transit?<product_id>&<location_id>", format = "json")]
+pub async fn get_stock_in_transit(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, product_id: Option<&str>, location_id: Option<&str>) -> Json<HttpResponse<Vec<TransitLine>>> {
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::ViewTenant, &db).await {
+        return Json(err);
+    }
+
+    let product_uuid = match product_id.map(Uuid::parse_str) {
+        Some(Ok(uuid)) => Some(uuid),
+        Some(Err(err)) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid product UUID: {:?}", err),
+            data: None
+        }),
+        None => None
+    };
+
+    let location_uuid = match location_id.map(Uuid::parse_str) {
+        Some(Ok(uuid)) => Some(uuid),
+        Some(Err(err)) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid location UUID: {:?}", err),
+            data: None
+        }),
+        None => None
+    };
+
+    match Transfer::get_in_transit(tenant_uuid, product_uuid, location_uuid, &db).await {
+        Ok(lines) => Json(HttpResponse {
+            status: 200,
+            message: "Successfully retrieved stock in transit".to_string(),
+            data: Some(lines)
+        }),
+        Err(err) => Json(err)
+    }
+}
@@ -0,0 +1,8 @@
+pub mod create;
+pub mod get_all_from_tenant;
+pub mod get_by_id;
+pub mod get_in_transit;
+pub mod update;
+pub mod delete;
+pub mod ship;
+pub mod receive;
\ No newline at end of file
@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+use mongodb::bson::Uuid;
+use rocket::{error, post, serde::{json::Json, Deserialize}};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, membership::TenantPermission, transfer::Transfer}};
+
+use super::create::TransferLineData;
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReceiveTransferData {
+    // Counted quantities of lines that didn't arrive in full, the quantity is the received one
+    lines: Option<Vec<TransferLineData>>
+}
+
+#[allow(unused)]
+#[post("/tenants/<tenant_id>/transfers/<transfer_id>/receive", format = "json", data = "<data>")]
+pub async fn receive_transfer(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, transfer_id: &str, data: Json<ReceiveTransferData>) -> Json<HttpResponse<Transfer>> {
+    let data = data.into_inner();
+
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if let Err(err) = auth.require_permission(tenant_uuid, TenantPermission::MoveStock, &db).await {
+        return Json(err);
+    }
+
+    let transfer_uuid = match Uuid::parse_str(transfer_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid transfer UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let mut received = Vec::new();
+    for line in data.lines.unwrap_or_default() {
+        match line.parse() {
+            Ok((product_uuid, batch_uuid)) => received.push((product_uuid, batch_uuid, line.quantity)),
+            Err(err) => return Json(err)
+        }
+    }
+
+    let mut transfer = match Transfer::get_from_tenant(transfer_uuid, tenant_uuid, &db).await {
+        Ok(transfer) => transfer,
+        Err(err) => return Json(err)
+    };
+
+    let mut old_values = HashMap::from([("status".to_string(), format!("{:?}", transfer.status))]);
+    if let Err(err) = transfer.receive(&received, auth.author(), &db).await {
+        return Json(err);
+    }
+    let mut new_values = HashMap::from([("status".to_string(), format!("{:?}", transfer.status))]);
+
+    // Each discrepancy is kept on its line, the audit log records shipped and received quantity per line
+    for line in transfer.lines.iter().filter(|line| line.discrepancy.is_some_and(|discrepancy| discrepancy != 0.0)) {
+        let key = match line.batch_id {
+            Some(batch_id) => format!("{}/{}", line.product_id, batch_id),
+            None => line.product_id.to_string()
+        };
+        old_values.insert(key.clone(), line.quantity.to_string());
+        new_values.insert(key, line.received_quantity.unwrap_or_default().to_string());
+    }
+
+    let reason = if transfer.has_discrepancies() { "Transfer received with discrepancies." } else { "Transfer received." };
+
+    // `receive` has written the transfer already
+    match AuditLog::new(transfer.id, AuditLogEntityType::Transfer, AuditLogAction::Update, reason.to_string(), auth.author(), Some(old_values), Some(new_values)).insert(&db).await {
+        Ok(_) => (),
+        Err(err) => error!("{}", err)
+    }
+
+    Json(HttpResponse {
+        status: 200,
+        message: "Transfer received".to_string(),
+        data: Some(transfer)
+    })
+}
@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+use mongodb::bson::Uuid;
+use rocket::{error, post, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, membership::TenantPermission, transfer::Transfer}};
+
+#[allow(unused)]
+#[post("/tenants/<tenant_id>/transfers/<transfer_id>/ship", format = "json")]
+pub async fn ship_transfer(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, transfer_id: &str) -> Json<HttpResponse<Transfer>> {
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let tenant = match auth.require_permission(tenant_uuid, TenantPermission::MoveStock, &db).await {
+        Ok(tenant) => tenant,
+        Err(err) => return Json(err)
+    };
+
+    let transfer_uuid = match Uuid::parse_str(transfer_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid transfer UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let mut transfer = match Transfer::get_from_tenant(transfer_uuid, tenant_uuid, &db).await {
+        Ok(transfer) => transfer,
+        Err(err) => return Json(err)
+    };
+
+    // Batches may have been closed since the draft was written
+    if let Err(err) = transfer.validate(&tenant.settings.today(), &db).await {
+        return Json(err);
+    }
+
+    let old_values = HashMap::from([("status".to_string(), format!("{:?}", transfer.status))]);
+    if let Err(err) = transfer.ship(auth.author(), &db).await {
+        return Json(err);
+    }
+    let new_values = HashMap::from([("status".to_string(), format!("{:?}", transfer.status))]);
+
+    // `ship` has written the transfer already
+    match AuditLog::new(transfer.id, AuditLogEntityType::Transfer, AuditLogAction::Update, "Transfer shipped.".to_string(), auth.author(), Some(old_values), Some(new_values)).insert(&db).await {
+        Ok(_) => (),
+        Err(err) => error!("{}", err)
+    }
+
+    Json(HttpResponse {
+        status: 200,
+        message: "Transfer shipped".to_string(),
+        data: Some(transfer)
+    })
+}
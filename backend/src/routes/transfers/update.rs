@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+use mongodb::bson::Uuid;
+use rocket::{error, patch, serde::{json::Json, Deserialize}};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::Authenticated, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, location::Location, membership::TenantPermission, transfer::{Transfer, TransferStatus}}};
+
+use super::create::TransferLineData;
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct UpdateTransferData {
+    #[serde(rename = "sourceLocationId")]
+    source_location_id: Option<String>,
+    #[serde(rename = "destinationLocationId")]
+    destination_location_id: Option<String>,
+    // Replaces all lines
+    lines: Option<Vec<TransferLineData>>,
+    note: Option<String>
+}
+
+#[allow(unused)]
+#[patch("/tenants/<tenant_id>/transfers/<transfer_id>", format = "json", data = "<data>")]
+pub async fn update_transfer(db: Connection<ShelfWatcherDatabase>, auth: Authenticated, tenant_id: &str, transfer_id: &str, data: Json<UpdateTransferData>) -> Json<HttpResponse<Transfer>> {
+    let data = data.into_inner();
+
+    let tenant_uuid = match Uuid::parse_str(tenant_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid tenant UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let tenant = match auth.require_permission(tenant_uuid, TenantPermission::MoveStock, &db).await {
+        Ok(tenant) => tenant,
+        Err(err) => return Json(err)
+    };
+
+    let transfer_uuid = match Uuid::parse_str(transfer_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid transfer UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let mut transfer = match Transfer::get_from_tenant(transfer_uuid, tenant_uuid, &db).await {
+        Ok(transfer) => transfer,
+        Err(err) => return Json(err)
+    };
+
+    if transfer.status != TransferStatus::Draft {
+        return Json(HttpResponse {
+            status: 400,
+            message: "Only draft transfers can be changed".to_string(),
+            data: None
+        });
+    }
+
+    let mut old_values = HashMap::new();
+    let mut new_values = HashMap::new();
+
+    if let Some(source_location_id) = data.source_location_id {
+        let source_location_uuid = match Uuid::parse_str(&source_location_id) {
+            Ok(uuid) => uuid,
+            Err(err) => return Json(HttpResponse {
+                status: 400,
+                message: format!("Invalid source location UUID: {:?}", err),
+                data: None
+            })
+        };
+
+        if let Err(err) = Location::get_from_tenant(source_location_uuid, tenant_uuid, &db).await {
+            return Json(HttpResponse {
+                status: err.status,
+                message: err.message,
+                data: None
+            });
+        }
+
+        old_values.insert("sourceLocationId".to_string(), transfer.source_location_id.to_string());
+        new_values.insert("sourceLocationId".to_string(), source_location_uuid.to_string());
+        transfer.source_location_id = source_location_uuid;
+    }
+
+    if let Some(destination_location_id) = data.destination_location_id {
+        let destination_location_uuid = match Uuid::parse_str(&destination_location_id) {
+            Ok(uuid) => uuid,
+            Err(err) => return Json(HttpResponse {
+                status: 400,
+                message: format!("Invalid destination location UUID: {:?}", err),
+                data: None
+            })
+        };
+
+        if let Err(err) = Location::get_from_tenant(destination_location_uuid, tenant_uuid, &db).await {
+            return Json(HttpResponse {
+                status: err.status,
+                message: err.message,
+                data: None
+            });
+        }
+
+        old_values.insert("destinationLocationId".to_string(), transfer.destination_location_id.to_string());
+        new_values.insert("destinationLocationId".to_string(), destination_location_uuid.to_string());
+        transfer.destination_location_id = destination_location_uuid;
+    }
+
+    if let Some(lines) = data.lines {
+        let lines = match TransferLineData::to_lines(&lines) {
+            Ok(lines) => lines,
+            Err(err) => return Json(err)
+        };
+
+        old_values.insert("lines".to_string(), transfer.lines.len().to_string());
+        new_values.insert("lines".to_string(), lines.len().to_string());
+        transfer.lines = lines;
+    }
+
+    if let Some(note) = data.note {
+        let note = Some(note.trim().to_string()).filter(|note| !note.is_empty());
+
+        old_values.insert("note".to_string(), transfer.note.clone().unwrap_or_default());
+        new_values.insert("note".to_string(), note.clone().unwrap_or_default());
+        transfer.note = note;
+    }
+
+    if let Err(err) = transfer.validate(&tenant.settings.today(), &db).await {
+        return Json(err);
+    }
+
+    // A ship that landed since the transfer was read wins, the edit is rejected
+    match transfer.replace_if(TransferStatus::Draft, &db).await {
+        Ok(false) => Json(HttpResponse {
+            status: 400,
+            message: "Only draft transfers can be updated".to_string(),
+            data: None
+        }),
+        Ok(true) => {
+            match AuditLog::new(transfer.id, AuditLogEntityType::Transfer, AuditLogAction::Update, "Transfer updated.".to_string(), auth.author(), Some(old_values), Some(new_values)).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            Json(HttpResponse {
+                status: 200,
+                message: "Transfer updated".to_string(),
+                data: Some(transfer)
+            })
+        },
+        Err(err) => Json(err)
+    }
+}
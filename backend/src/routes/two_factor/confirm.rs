@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+use rocket::{error, post, serde::{json::Json, Deserialize, Serialize}};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::AuthenticatedUser, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse}};
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ConfirmTwoFactorData {
+    code: String,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct RecoveryCodesResponse {
+    #[serde(rename = "recoveryCodes")]
+    pub recovery_codes: Vec<String>,
+}
+
+#[allow(unused)]
+#[post("/auth/2fa/confirm", format = "json", data = "<data>")]
+pub async fn confirm_two_factor(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser, data: Json<ConfirmTwoFactorData>) -> Json<HttpResponse<RecoveryCodesResponse>> {
+    let data = data.into_inner();
+    let mut user = auth.user.clone();
+
+    if user.totp_pending_secret.is_none() {
+        return Json(HttpResponse {
+            status: 400,
+            message: "No two-factor enrollment in progress".to_string(),
+            data: None
+        });
+    }
+
+    if !user.verify_pending_totp(&data.code) {
+        return Json(HttpResponse {
+            status: 400,
+            message: "Invalid two-factor code".to_string(),
+            data: None
+        });
+    }
+
+    user.totp_secret = user.totp_pending_secret.take();
+    let recovery_codes = user.generate_recovery_codes();
+
+    match user.update_two_factor(&db).await {
+        Ok(user) => {
+            let old_values = HashMap::from([("twoFactor".to_string(), "disabled".to_string())]);
+            let new_values = HashMap::from([("twoFactor".to_string(), "enabled".to_string())]);
+            match AuditLog::new(user.id, AuditLogEntityType::User, AuditLogAction::Update, "Two-factor authentication enabled.".to_string(), auth.author(), Some(old_values), Some(new_values)).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            Json(HttpResponse {
+                status: 200,
+                message: "Two-factor authentication enabled, store the recovery codes safely".to_string(),
+                data: Some(RecoveryCodesResponse { recovery_codes })
+            })
+        },
+        Err(err) => Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    }
+}
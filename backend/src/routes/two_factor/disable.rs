@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+use rocket::{error, post, serde::{json::Json, Deserialize}};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::AuthenticatedUser, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse}};
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct DisableTwoFactorData {
+    #[serde(rename = "totpCode")]
+    totp_code: Option<String>,
+    #[serde(rename = "recoveryCode")]
+    recovery_code: Option<String>,
+}
+
+#[allow(unused)]
+#[post("/auth/2fa/disable", format = "json", data = "<data>")]
+pub async fn disable_two_factor(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser, data: Json<DisableTwoFactorData>) -> Json<HttpResponse<()>> {
+    let data = data.into_inner();
+    let mut user = auth.user.clone();
+
+    if user.totp_secret.is_none() {
+        return Json(HttpResponse {
+            status: 400,
+            message: "Two-factor authentication is not enabled".to_string(),
+            data: None
+        });
+    }
+
+    let verified = match user.verify_second_factor(data.totp_code.as_deref(), data.recovery_code.as_deref(), &db).await {
+        Ok(verified) => verified,
+        Err(err) => return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    };
+    if !verified {
+        return Json(HttpResponse {
+            status: 400,
+            message: "Invalid two-factor code".to_string(),
+            data: None
+        });
+    }
+
+    user.totp_secret = None;
+    user.totp_pending_secret = None;
+    user.recovery_codes.clear();
+
+    match user.update_two_factor(&db).await {
+        Ok(user) => {
+            let old_values = HashMap::from([("twoFactor".to_string(), "enabled".to_string())]);
+            let new_values = HashMap::from([("twoFactor".to_string(), "disabled".to_string())]);
+            match AuditLog::new(user.id, AuditLogEntityType::User, AuditLogAction::Update, "Two-factor authentication disabled.".to_string(), auth.author(), Some(old_values), Some(new_values)).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            Json(HttpResponse {
+                status: 200,
+                message: "Two-factor authentication disabled".to_string(),
+                data: None
+            })
+        },
+        Err(err) => Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    }
+}
@@ -0,0 +1,51 @@
+use rocket::{post, serde::{json::Json, Serialize}};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::AuthenticatedUser, models::{http_response::HttpResponse, user::User}};
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct EnrollTwoFactorResponse {
+    secret: String,
+    #[serde(rename = "provisioningUri")]
+    provisioning_uri: String,
+}
+
+#[allow(unused)]
+#[post("/auth/2fa/enroll", format = "json")]
+pub async fn enroll_two_factor(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser) -> Json<HttpResponse<EnrollTwoFactorResponse>> {
+    let mut user = auth.user.clone();
+
+    if user.totp_secret.is_some() {
+        return Json(HttpResponse {
+            status: 400,
+            message: "Two-factor authentication is already enabled".to_string(),
+            data: None
+        });
+    }
+
+    let secret = User::generate_totp_secret();
+    let provisioning_uri = match user.totp_provisioning_uri(&secret) {
+        Some(uri) => uri,
+        None => return Json(HttpResponse {
+            status: 500,
+            message: "Failed to build provisioning URI".to_string(),
+            data: None
+        })
+    };
+
+    user.totp_pending_secret = Some(secret.clone());
+
+    match user.update_two_factor(&db).await {
+        Ok(_) => Json(HttpResponse {
+            status: 200,
+            message: "Two-factor enrollment started, confirm it with a code".to_string(),
+            data: Some(EnrollTwoFactorResponse { secret, provisioning_uri })
+        }),
+        Err(err) => Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    }
+}
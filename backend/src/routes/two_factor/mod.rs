@@ -0,0 +1,4 @@
+pub mod enroll;
+pub mod confirm;
+pub mod disable;
+pub mod regenerate_recovery_codes;
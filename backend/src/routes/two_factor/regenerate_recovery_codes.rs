@@ -0,0 +1,49 @@
+use rocket::{error, post, serde::{json::Json, Deserialize}};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::AuthenticatedUser, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse}};
+
+use super::confirm::RecoveryCodesResponse;
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct RegenerateRecoveryCodesData {
+    code: String,
+}
+
+#[allow(unused)]
+#[post("/auth/2fa/recovery-codes", format = "json", data = "<data>")]
+pub async fn regenerate_recovery_codes(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser, data: Json<RegenerateRecoveryCodesData>) -> Json<HttpResponse<RecoveryCodesResponse>> {
+    let data = data.into_inner();
+    let mut user = auth.user.clone();
+
+    if !user.verify_totp(&data.code) {
+        return Json(HttpResponse {
+            status: 400,
+            message: "Invalid two-factor code".to_string(),
+            data: None
+        });
+    }
+
+    let recovery_codes = user.generate_recovery_codes();
+
+    match user.update_two_factor(&db).await {
+        Ok(user) => {
+            match AuditLog::new(user.id, AuditLogEntityType::User, AuditLogAction::Update, "Recovery codes regenerated.".to_string(), auth.author(), None, None).insert(&db).await {
+                Ok(_) => (),
+                Err(err) => error!("{}", err)
+            }
+
+            Json(HttpResponse {
+                status: 200,
+                message: "Recovery codes regenerated".to_string(),
+                data: Some(RecoveryCodesResponse { recovery_codes })
+            })
+        },
+        Err(err) => Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    }
+}
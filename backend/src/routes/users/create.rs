@@ -1,26 +1,23 @@
-use rocket::{error, post, serde::{json::Json, Deserialize}};
+use rocket::{error, post, serde::{json::Json, Deserialize}, State};
 use rocket_db_pools::Connection;
 
-use crate::{db::ShelfWatcherDatabase, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, user::{User, UserMinimal}}};
+use crate::{db::ShelfWatcherDatabase, mailer::{MailConfig, Mailer}, models::{audit_log::{AuditLog, AuditLogAction, AuditLogAuthor, AuditLogEntityType}, http_response::HttpResponse, user::{User, UserMinimal}}, routes::auth::request_email_verification::send_verification_mail};
 
 #[derive(Deserialize)]
 #[serde(crate = "rocket::serde")]
 pub struct CreateUserData {
-    email: String,
-    password: String,
+    pub email: String,
+    pub password: String,
     #[serde(rename = "firstName")]
-    first_name: String,
+    pub first_name: String,
     #[serde(rename = "lastName")]
-    last_name: String,
+    pub last_name: String,
 }
 
-#[allow(unused)]
-#[post("/users", format = "json", data = "<data>")] 
-pub async fn create_user(db: Connection<ShelfWatcherDatabase>, data: Json<CreateUserData>) -> Json<HttpResponse<UserMinimal>> { 
-    let data = data.into_inner();
-
-    if User::get_by_email(&data.email, &db).await.is_ok() {
-        return Json(HttpResponse {
+// Shared by the public sign up and by accepting an invitation
+pub async fn register_user(db: &Connection<ShelfWatcherDatabase>, data: CreateUserData) -> Result<UserMinimal, HttpResponse<UserMinimal>> {
+    if User::get_by_email(&data.email, db).await.is_ok() {
+        return Err(HttpResponse {
             status: 400,
             message: "User with that email already exists".to_string(),
             data: None
@@ -28,23 +25,43 @@ pub async fn create_user(db: Connection<ShelfWatcherDatabase>, data: Json<Create
     }
 
     let user = match User::new(data.email, data.password, data.first_name, data.last_name) {
+        Ok(user) => user,
+        Err(err) => return Err(err)
+    };
+
+    let user = match user.insert(db).await {
+        Ok(user) => user,
+        Err(err) => return Err(err)
+    };
+
+    match AuditLog::new(user.id, AuditLogEntityType::User, AuditLogAction::Create, "User created.".to_string(), AuditLogAuthor::User(user.id), None, None).insert(db).await {
+        Ok(_) => (),
+        Err(err) => error!("{}", err)
+    }
+
+    Ok(user)
+}
+
+#[allow(unused)]
+#[post("/users", format = "json", data = "<data>")] 
+pub async fn create_user(db: Connection<ShelfWatcherDatabase>, mailer: &State<Box<dyn Mailer>>, mail_config: &State<MailConfig>, data: Json<CreateUserData>) -> Json<HttpResponse<UserMinimal>> { 
+    let user = match register_user(&db, data.into_inner()).await {
         Ok(user) => user,
         Err(err) => return Json(err)
     };
-    
-    match user.insert(&db).await {
+
+    match user.to_full(&db).await {
         Ok(user) => {
-            match AuditLog::new(user.id, AuditLogEntityType::User, AuditLogAction::Create, "User created.".to_string(), user.id, None, None).insert(&db).await {
-                Ok(_) => (),
-                Err(err) => error!("{}", err)
+            if let Err(err) = send_verification_mail(&db, mailer.as_ref(), mail_config, &user).await {
+                error!("{}", err.message);
             }
-            
-            Json(HttpResponse {
-                status: 201,
-                message: "User created".to_string(),
-                data: Some(user)
-            })
         },
-        Err(err) => Json(err)
+        Err(err) => error!("{}", err.message)
     }
-}
\ No newline at end of file
+
+    Json(HttpResponse {
+        status: 201,
+        message: "User created".to_string(),
+        data: Some(user)
+    })
+}
@@ -1,12 +1,12 @@
-use mongodb::bson::Uuid;
+use mongodb::bson::{doc, Uuid};
 use rocket::{delete, error, serde::json::Json};
 use rocket_db_pools::Connection;
 
-use crate::{db::ShelfWatcherDatabase, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, user::User}};
+use crate::{db::{count_documents, ShelfWatcherDatabase}, middleware::auth::AuthenticatedUser, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, tenant::Tenant, user::User}};
 
 #[allow(unused)]
 #[delete("/users/<id>", format = "json")] 
-pub async fn delete_user(db: Connection<ShelfWatcherDatabase>, id: &str) -> Json<HttpResponse<()>> {
+pub async fn delete_user(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser, id: &str) -> Json<HttpResponse<()>> {
     let uuid = match Uuid::parse_str(id) {
         Ok(uuid) => uuid,
         Err(err) => return Json(HttpResponse {
@@ -16,6 +16,10 @@ pub async fn delete_user(db: Connection<ShelfWatcherDatabase>, id: &str) -> Json
         })
     };
 
+    if let Err(err) = auth.require_self_or_admin(uuid) {
+        return Json(err);
+    }
+
     let user = match User::get_full_by_id(uuid, &db).await {
         Ok(tenant) => tenant,
         Err(err) => return Json(HttpResponse {
@@ -25,11 +29,20 @@ pub async fn delete_user(db: Connection<ShelfWatcherDatabase>, id: &str) -> Json
         })
     };
 
+    // Tenants keep their owner until they are purged, they can't be restored or handed over without one
+    match count_documents(Tenant::COLLECTION_NAME, doc! { "ownerId": user.id }, &db).await {
+        Ok(0) => (),
+        Ok(_) => return Json(HttpResponse {
+            status: 400,
+            message: "User still owns tenants, transfer their ownership first or delete them and wait until they are purged".to_string(),
+            data: None
+        }),
+        Err(err) => return Json(err)
+    }
 
     match user.delete(&db).await {
         Ok(user) => {
-            // TODO: Implement author_id -> maybe admin action
-            match AuditLog::new(user.id, AuditLogEntityType::User, AuditLogAction::Delete, "User deleted.".to_string(), user.id, None, None).insert(&db).await {
+            match AuditLog::new(user.id, AuditLogEntityType::User, AuditLogAction::Delete, "User deleted.".to_string(), auth.author(), None, None).insert(&db).await {
                 Ok(_) => (),
                 Err(err) => error!("{}", err)
             }
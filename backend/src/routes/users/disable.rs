@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+use mongodb::bson::Uuid;
+use rocket::{error, post, serde::{json::Json, Deserialize}};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::AuthenticatedUser, models::{api_key::ApiKey, audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, session::Session, user::{User, UserMinimal}}};
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct DisableUserData {
+    pub reason: String
+}
+
+// Disabling logs the user out everywhere and revokes the API keys they created
+#[allow(unused)]
+#[post("/users/<id>/disable", format = "json", data = "<data>")]
+pub async fn disable_user(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser, id: &str, data: Json<DisableUserData>) -> Json<HttpResponse<UserMinimal>> {
+    let data = data.into_inner();
+
+    if let Err(err) = auth.require_admin() {
+        return Json(err);
+    }
+
+    let uuid = match Uuid::parse_str(id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if data.reason.trim().is_empty() {
+        return Json(HttpResponse {
+            status: 400,
+            message: "A reason is required".to_string(),
+            data: None
+        });
+    }
+
+    if uuid == auth.user.id {
+        return Json(HttpResponse {
+            status: 400,
+            message: "You can't disable your own account".to_string(),
+            data: None
+        });
+    }
+
+    let mut user = match User::get_full_by_id(uuid, &db).await {
+        Ok(user) => user,
+        Err(err) => return Json(err)
+    };
+
+    if user.disabled {
+        return Json(HttpResponse {
+            status: 400,
+            message: "User is already disabled".to_string(),
+            data: None
+        });
+    }
+
+    if let Err(err) = user.disable(&db).await {
+        return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        });
+    }
+    user.disabled = true;
+
+    let old_values = HashMap::from([("disabled".to_string(), "false".to_string())]);
+    let new_values = HashMap::from([("disabled".to_string(), "true".to_string())]);
+    match AuditLog::new(user.id, AuditLogEntityType::User, AuditLogAction::Update, format!("User disabled: {}", data.reason.trim()), auth.author(), Some(old_values), Some(new_values)).insert(&db).await {
+        Ok(_) => (),
+        Err(err) => error!("{}", err)
+    }
+
+    // The user is disabled either way, but their sessions and keys must not keep working unnoticed
+    if let Err(err) = Session::revoke_all_for_user(user.id, &db).await {
+        return Json(HttpResponse {
+            status: 500,
+            message: format!("User disabled, but revoking their sessions failed: {}", err.message),
+            data: None
+        });
+    }
+    if let Err(err) = ApiKey::revoke_all_created_by(user.id, None, &db).await {
+        return Json(HttpResponse {
+            status: 500,
+            message: format!("User disabled, but revoking their API keys failed: {}", err.message),
+            data: None
+        });
+    }
+
+    Json(HttpResponse {
+        status: 200,
+        message: "User disabled".to_string(),
+        data: Some(user.to_minimal())
+    })
+}
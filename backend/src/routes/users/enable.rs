@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+use mongodb::bson::Uuid;
+use rocket::{error, post, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::AuthenticatedUser, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, user::{User, UserMinimal}}};
+
+use super::disable::DisableUserData;
+
+// Revoked sessions and API keys stay revoked, the user has to log in again
+#[allow(unused)]
+#[post("/users/<id>/enable", format = "json", data = "<data>")]
+pub async fn enable_user(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser, id: &str, data: Json<DisableUserData>) -> Json<HttpResponse<UserMinimal>> {
+    let data = data.into_inner();
+
+    if let Err(err) = auth.require_admin() {
+        return Json(err);
+    }
+
+    let uuid = match Uuid::parse_str(id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    if data.reason.trim().is_empty() {
+        return Json(HttpResponse {
+            status: 400,
+            message: "A reason is required".to_string(),
+            data: None
+        });
+    }
+
+    let mut user = match User::get_full_by_id(uuid, &db).await {
+        Ok(user) => user,
+        Err(err) => return Json(err)
+    };
+
+    if !user.disabled {
+        return Json(HttpResponse {
+            status: 400,
+            message: "User is not disabled".to_string(),
+            data: None
+        });
+    }
+
+    if let Err(err) = user.enable(&db).await {
+        return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        });
+    }
+    user.disabled = false;
+
+    let old_values = HashMap::from([("disabled".to_string(), "true".to_string())]);
+    let new_values = HashMap::from([("disabled".to_string(), "false".to_string())]);
+    match AuditLog::new(user.id, AuditLogEntityType::User, AuditLogAction::Update, format!("User enabled: {}", data.reason.trim()), auth.author(), Some(old_values), Some(new_values)).insert(&db).await {
+        Ok(_) => (),
+        Err(err) => error!("{}", err)
+    }
+
+    Json(HttpResponse {
+        status: 200,
+        message: "User enabled".to_string(),
+        data: Some(user.to_minimal())
+    })
+}
@@ -1,12 +1,15 @@
 use rocket::{get, serde::json::Json};
 use rocket_db_pools::Connection;
 
-use crate::{db::ShelfWatcherDatabase, models::{http_response::HttpResponse, user::{User, UserMinimal}}};
+use crate::{db::ShelfWatcherDatabase, middleware::auth::AuthenticatedUser, models::{http_response::HttpResponse, user::{User, UserMinimal}}};
 
 #[allow(unused)]
 #[get("/users", format = "json")] 
-pub async fn get_all_users(db: Connection<ShelfWatcherDatabase>) -> Json<HttpResponse<Vec<UserMinimal>>> {
-    // TODO: Only allow this for admins
+pub async fn get_all_users(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser) -> Json<HttpResponse<Vec<UserMinimal>>> {
+    if let Err(err) = auth.require_admin() {
+        return Json(err);
+    }
+
     match User::get_all(&db).await {
         Ok(users) => Json(HttpResponse {
             status: 200,
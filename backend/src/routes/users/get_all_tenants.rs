@@ -2,11 +2,11 @@ use mongodb::bson::Uuid;
 use rocket::{get, serde::json::Json};
 use rocket_db_pools::Connection;
 
-use crate::{db::ShelfWatcherDatabase, models::{http_response::HttpResponse, tenant::Tenant, user::User}};
+use crate::{db::ShelfWatcherDatabase, middleware::auth::AuthenticatedUser, models::{http_response::HttpResponse, tenant::Tenant, user::User}};
 
 #[allow(unused)]
 #[get("/users/<id>/tenants", format = "json")] 
-pub async fn get_all_tenants(id: &str, db: Connection<ShelfWatcherDatabase>) -> Json<HttpResponse<Vec<Tenant>>> {
+pub async fn get_all_tenants(id: &str, db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser) -> Json<HttpResponse<Vec<Tenant>>> {
     let uuid = match Uuid::parse_str(id) {
         Ok(uuid) => uuid,
         Err(err) => return  Json(HttpResponse {
@@ -16,6 +16,10 @@ pub async fn get_all_tenants(id: &str, db: Connection<ShelfWatcherDatabase>) ->
         })
     };
 
+    if let Err(err) = auth.require_self_or_admin(uuid) {
+        return Json(err);
+    }
+
     let user = match User::get_by_id(uuid, &db).await {
         Ok(user) => user,
         Err(err) => return Json(HttpResponse {
@@ -29,7 +33,7 @@ pub async fn get_all_tenants(id: &str, db: Connection<ShelfWatcherDatabase>) ->
         Ok(tenants) => Json(HttpResponse {
             status: 200,
             message: "Successfully retrieved all tenants with set owner id".to_string(),
-            data: Some(tenants.into_iter().filter(|tenant| user.tenants.contains(&tenant.id)).collect()),
+            data: Some(tenants.into_iter().filter(|tenant| user.membership(tenant.id).is_some()).collect()),
         }),
         Err(err) => Json(err)
     }
@@ -2,11 +2,11 @@ use mongodb::bson::Uuid;
 use rocket::{get, serde::json::Json};
 use rocket_db_pools::Connection;
 
-use crate::{db::ShelfWatcherDatabase, models::{http_response::HttpResponse, user::{User, UserMinimal}}};
+use crate::{db::ShelfWatcherDatabase, middleware::auth::AuthenticatedUser, models::{http_response::HttpResponse, user::{User, UserMinimal}}};
 
 #[allow(unused)]
 #[get("/users/<id>", format = "json")] 
-pub async fn get_user_by_id(db: Connection<ShelfWatcherDatabase>, id: &str) -> Json<HttpResponse<UserMinimal>> {
+pub async fn get_user_by_id(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser, id: &str) -> Json<HttpResponse<UserMinimal>> {
     let uuid = match Uuid::parse_str(id) {
         Ok(uuid) => uuid,
         Err(err) => return Json(HttpResponse {
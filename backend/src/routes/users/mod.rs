@@ -3,4 +3,7 @@ pub mod get_all;
 pub mod get_all_tenants;
 pub mod get_by_id;
 pub mod update;
-pub mod delete;
\ No newline at end of file
+pub mod delete;
+pub mod disable;
+pub mod enable;
+pub mod unlock;
\ No newline at end of file
@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+use mongodb::bson::Uuid;
+use rocket::{error, post, serde::json::Json};
+use rocket_db_pools::Connection;
+
+use crate::{db::ShelfWatcherDatabase, middleware::auth::AuthenticatedUser, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, login_throttle::{LoginThrottle, LoginThrottleKind}, user::User}};
+
+// Resets the failed login counter of a user, lifting a lockout before it expires
+#[allow(unused)]
+#[post("/users/<id>/unlock", format = "json")]
+pub async fn unlock_user(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser, id: &str) -> Json<HttpResponse<()>> {
+    if let Err(err) = auth.require_admin() {
+        return Json(err);
+    }
+
+    let uuid = match Uuid::parse_str(id) {
+        Ok(uuid) => uuid,
+        Err(err) => return Json(HttpResponse {
+            status: 400,
+            message: format!("Invalid UUID: {:?}", err),
+            data: None
+        })
+    };
+
+    let user = match User::get_full_by_id(uuid, &db).await {
+        Ok(user) => user,
+        Err(err) => return Json(HttpResponse {
+            status: err.status,
+            message: err.message,
+            data: None
+        })
+    };
+
+    let throttle = match LoginThrottle::get(LoginThrottleKind::Account, &user.email, &db).await {
+        Ok(throttle) => throttle,
+        Err(err) => return Json(err)
+    };
+
+    if throttle.failures == 0 {
+        return Json(HttpResponse {
+            status: 400,
+            message: "User has no failed login attempts".to_string(),
+            data: None
+        });
+    }
+
+    if let Err(err) = LoginThrottle::clear(LoginThrottleKind::Account, &user.email, &db).await {
+        return Json(err);
+    }
+
+    let old_values = HashMap::from([
+        ("failedLogins".to_string(), throttle.failures.to_string()),
+        ("locked".to_string(), throttle.locked.to_string())
+    ]);
+    let new_values = HashMap::from([
+        ("failedLogins".to_string(), "0".to_string()),
+        ("locked".to_string(), "false".to_string())
+    ]);
+    match AuditLog::new(user.id, AuditLogEntityType::User, AuditLogAction::Update, "Account unlocked.".to_string(), auth.author(), Some(old_values), Some(new_values)).insert(&db).await {
+        Ok(_) => (),
+        Err(err) => error!("{}", err)
+    }
+
+    Json(HttpResponse {
+        status: 200,
+        message: "User unlocked".to_string(),
+        data: None
+    })
+}
@@ -1,10 +1,10 @@
 use std::collections::HashMap;
-use mongodb::bson::Uuid;
+use mongodb::bson::{Document, Uuid};
 use pwhash::bcrypt;
-use rocket::{error, patch, serde::{json::Json, Deserialize}};
+use rocket::{error, patch, serde::{json::Json, Deserialize}, State};
 use rocket_db_pools::Connection;
 
-use crate::{db::ShelfWatcherDatabase, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, user::{User, UserMinimal}}};
+use crate::{db::ShelfWatcherDatabase, mailer::{MailConfig, Mailer}, middleware::auth::AuthenticatedUser, models::{audit_log::{AuditLog, AuditLogAction, AuditLogEntityType}, http_response::HttpResponse, user::{User, UserMinimal}}, routes::auth::request_email_verification::send_verification_mail};
 
 #[derive(Deserialize)]
 #[serde(crate = "rocket::serde")]
@@ -14,12 +14,15 @@ pub struct UpdateUserData {
     #[serde(rename = "firstName")]
     first_name: Option<String>,
     #[serde(rename = "lastName")]
-    last_name: Option<String>
+    last_name: Option<String>,
+    // Required to change email or password, unless an admin does it
+    #[serde(rename = "currentPassword")]
+    current_password: Option<String>
 }
 
 #[allow(unused)]
 #[patch("/users/<id>", format = "json", data = "<data>")] 
-pub async fn update_user(db: Connection<ShelfWatcherDatabase>, id: &str, data: Json<UpdateUserData>) -> Json<HttpResponse<UserMinimal>> { 
+pub async fn update_user(db: Connection<ShelfWatcherDatabase>, auth: AuthenticatedUser, mailer: &State<Box<dyn Mailer>>, mail_config: &State<MailConfig>, id: &str, data: Json<UpdateUserData>) -> Json<HttpResponse<UserMinimal>> { 
     let data = data.into_inner();
 
     let uuid = match Uuid::parse_str(id) {
@@ -31,6 +34,10 @@ pub async fn update_user(db: Connection<ShelfWatcherDatabase>, id: &str, data: J
         })
     };
 
+    if let Err(err) = auth.require_self_or_admin(uuid) {
+        return Json(err);
+    }
+
     let old_user = match User::get_by_id(uuid, &db).await {
         Ok(user) => user,
         Err(err) => return Json(err)
@@ -41,16 +48,44 @@ pub async fn update_user(db: Connection<ShelfWatcherDatabase>, id: &str, data: J
         Err(err) => return Json(err)
     };
 
+    let mut fields = Document::new();
     let mut old_values: HashMap<String, String> = HashMap::new();
     let mut new_values: HashMap<String, String> = HashMap::new();
 
-    if data.email.is_some() {
-        new_user.email = data.email.unwrap();
+    if (data.email.is_some() || data.password.is_some()) && !auth.is_admin() {
+        match &data.current_password {
+            Some(current_password) if bcrypt::verify(current_password, &new_user.password_hash) => (),
+            Some(_) => return Json(HttpResponse {
+                status: 403,
+                message: "Current password is incorrect".to_string(),
+                data: None
+            }),
+            None => return Json(HttpResponse {
+                status: 400,
+                message: "currentPassword is required to change email or password".to_string(),
+                data: None
+            })
+        }
+    }
+
+    if let Some(email) = data.email.filter(|email| *email != old_user.email) {
+        if User::get_by_email(&email, &db).await.is_ok() {
+            return Json(HttpResponse {
+                status: 400,
+                message: "User with that email already exists".to_string(),
+                data: None
+            });
+        }
+
+        new_user.email = email;
+        new_user.email_verified = false;
+        fields.insert("email", &new_user.email);
+        fields.insert("emailVerified", false);
         old_values.insert("email".to_string(), old_user.email.clone());
         new_values.insert("email".to_string(), new_user.email.clone());
     }
-    if data.password.is_some() {
-        let password_hash = match bcrypt::hash(data.password.unwrap()) {
+    if let Some(password) = data.password {
+        let password_hash = match bcrypt::hash(password) {
             Ok(hash) => hash,
             Err(err) => return Json(HttpResponse {
                 status: 500,
@@ -60,16 +95,19 @@ pub async fn update_user(db: Connection<ShelfWatcherDatabase>, id: &str, data: J
         };
         
         new_user.password_hash = password_hash;
+        fields.insert("passwordHash", &new_user.password_hash);
         old_values.insert("password".to_string(), "HIDDEN".to_string());
         new_values.insert("password".to_string(), "HIDDEN".to_string());
     }
-    if data.first_name.is_some() {
-        new_user.first_name = data.first_name.unwrap();
+    if let Some(first_name) = data.first_name {
+        new_user.first_name = first_name;
+        fields.insert("firstName", &new_user.first_name);
         old_values.insert("firstName".to_string(), old_user.first_name.clone());
         new_values.insert("firstName".to_string(), new_user.first_name.clone());
     }
-    if data.last_name.is_some() {
-        new_user.last_name = data.last_name.unwrap();
+    if let Some(last_name) = data.last_name {
+        new_user.last_name = last_name;
+        fields.insert("lastName", &new_user.last_name);
         old_values.insert("lastName".to_string(), old_user.last_name.clone());
         new_values.insert("lastName".to_string(), new_user.last_name.clone());
     }
@@ -82,10 +120,15 @@ pub async fn update_user(db: Connection<ShelfWatcherDatabase>, id: &str, data: J
         });
     }
 
-    match new_user.update(&db).await {
+    match new_user.update_fields(fields, &db).await {
         Ok(user) => {
-            // TODO: Implement author_id -> maybe admin action
-            match AuditLog::new(user.id, AuditLogEntityType::User, AuditLogAction::Update, "User updated.".to_string(), user.id, Some(old_values), Some(new_values)).insert(&db).await {
+            if new_values.contains_key("email") {
+                if let Err(err) = send_verification_mail(&db, mailer.as_ref(), mail_config, &new_user).await {
+                    error!("{}", err.message);
+                }
+            }
+
+            match AuditLog::new(user.id, AuditLogEntityType::User, AuditLogAction::Update, "User updated.".to_string(), auth.author(), Some(old_values), Some(new_values)).insert(&db).await {
                 Ok(_) => (),
                 Err(err) => error!("{}", err)
             }